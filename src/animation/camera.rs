@@ -0,0 +1,153 @@
+use animation::track::Track;
+use camera::aperture::Aperture;
+use camera::apertures::CircularAperture;
+use camera::camera::Camera;
+use camera::cameras::{self, PerspectiveCamera};
+use ray::Ray;
+use sampler::sampler::Sampler;
+use vec3::Vec3;
+
+/// A perspective camera whose position, target, field of view, aperture and focus
+/// distance are keyframed. The camera is evaluated at the time of each ray, so its
+/// own motion is blurred while the shutter is open.
+#[derive(Clone)]
+pub struct AnimatedCamera {
+    pub look_from: Track<Vec3>,
+    pub look_at: Track<Vec3>,
+    pub view_up: Vec3,
+    /// The vertical field of view (in degrees)
+    pub vert_fov: Track<f64>,
+    pub aperture: Track<f64>,
+    pub focus_distance: Track<f64>,
+    pub aspect_ratio: f64,
+    pub shutter_open_time: f64,
+    pub shutter_close_time: f64,
+}
+
+impl AnimatedCamera {
+    /// Creates a new animated camera, with a pinhole lens focused on its target.
+    /// #### Arguments:
+    /// - `look_from`: The position of the camera over time
+    /// - `look_at`: The point at the center of the image over time
+    /// - `view_up`: The "up" direction of the camera
+    /// - `vert_fov`: The vertical field of view (in degrees) over time
+    /// - `aspect_ratio`: Aspect ratio to use for the camera
+    /// - `shutter_open_time`: Point of time at which the camera shutter opens
+    /// - `shutter_close_time`: Point of time at which the camera shutter closes
+    pub fn new(
+        look_from: Track<Vec3>,
+        look_at: Track<Vec3>,
+        view_up: Vec3,
+        vert_fov: Track<f64>,
+        aspect_ratio: f64,
+        shutter_open_time: f64,
+        shutter_close_time: f64,
+    ) -> Self {
+        AnimatedCamera {
+            look_from,
+            look_at,
+            view_up,
+            vert_fov,
+            aperture: Track::constant(0.0),
+            focus_distance: Track::constant(0.0),
+            aspect_ratio,
+            shutter_open_time,
+            shutter_close_time,
+        }
+    }
+
+    /// Evaluates the camera at the given time.
+    ///
+    /// A focus distance of zero (or less) focuses the camera on its target.
+    pub fn camera_at(&self, time: f64) -> PerspectiveCamera {
+        let look_from = self.look_from.evaluate(time);
+        let look_at = self.look_at.evaluate(time);
+        let mut camera = PerspectiveCamera::look_at(
+            look_from,
+            look_at,
+            self.view_up,
+            self.vert_fov.evaluate(time),
+            self.aspect_ratio,
+            self.aperture.evaluate(time),
+            time,
+            time,
+        );
+        let focus_distance = self.focus_distance.evaluate(time);
+        if focus_distance > 0.0 {
+            camera.set_focus_distance(focus_distance);
+        }
+        camera
+    }
+}
+
+impl Camera for AnimatedCamera {
+    fn create_ray(&self, x: f64, y: f64, sampler: &mut Sampler) -> Option<Ray> {
+        // The lens position and time are drawn in the same order as a PerspectiveCamera's
+        // (whose aperture is circular when evaluated by `camera_at`), so the sampler's
+        // time dimension is the one which drives the tracks
+        let lens_point = CircularAperture.sample(sampler);
+        let time = cameras::sample_time(self.shutter_open_time, self.shutter_close_time, sampler);
+        self.camera_at(time).create_ray_at(x, y, lens_point, time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use animation::track::Keyframe;
+
+    /// A sampler which returns fixed values, and counts the dimensions requested
+    struct CountingSampler {
+        dimensions: usize,
+    }
+
+    impl Sampler for CountingSampler {
+        fn start_pixel_sample(&mut self, _pixel: (u32, u32), _sample_index: usize) {
+            self.dimensions = 0;
+        }
+
+        fn get_1d(&mut self) -> f64 {
+            self.dimensions += 1;
+            0.25
+        }
+
+        fn get_2d(&mut self) -> (f64, f64) {
+            self.dimensions += 2;
+            (0.5, 0.5)
+        }
+
+        fn samples_per_pixel(&self) -> usize {
+            1
+        }
+
+        fn clone_sampler(&self) -> Box<Sampler> {
+            Box::new(CountingSampler { dimensions: 0 })
+        }
+    }
+
+    #[test]
+    fn draws_the_same_dimensions_as_a_perspective_camera() {
+        let animated = AnimatedCamera::new(
+            Track::new(vec![
+                Keyframe::linear(0.0, Vec3::new(0.0, 0.0, 0.0)),
+                Keyframe::linear(1.0, Vec3::new(4.0, 0.0, 0.0)),
+            ]),
+            Track::constant(Vec3::new(0.0, 0.0, -10.0)),
+            Vec3::new(0.0, 1.0, 0.0),
+            Track::constant(40.0),
+            1.5,
+            0.0,
+            1.0,
+        );
+        let mut sampler = CountingSampler { dimensions: 0 };
+        let ray = animated.create_ray(0.5, 0.5, &mut sampler).unwrap();
+        let animated_dimensions = sampler.dimensions;
+        // The time of the ray is drawn from the time dimension, and moves the camera
+        assert_eq!(ray.time, 0.25);
+        assert!((ray.origin - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-12);
+
+        sampler.start_pixel_sample((0, 0), 0);
+        animated.camera_at(0.0).create_ray(0.5, 0.5, &mut sampler);
+        assert_eq!(animated_dimensions, sampler.dimensions);
+    }
+}
@@ -0,0 +1,67 @@
+/// The range of frames rendered by an animation, along with their timing
+#[derive(Copy, Clone, Debug)]
+pub struct FrameRange {
+    /// The first frame to render
+    pub start: u32,
+    /// The last frame to render (inclusive)
+    pub end: u32,
+    pub frames_per_second: f64,
+    /// The fraction of each frame's duration for which the shutter is open
+    /// (e.g. 0.5 for a 180° shutter)
+    pub shutter_fraction: f64,
+    /// Whether the output files are numbered by frame
+    pub numbered: bool,
+}
+
+impl FrameRange {
+    /// Creates a new range of frames, rendered as a numbered image sequence
+    /// #### Arguments:
+    /// - `start`: The first frame to render
+    /// - `end`: The last frame to render (inclusive)
+    /// - `frames_per_second`: The frame rate of the animation
+    /// - `shutter_fraction`: The fraction of each frame's duration for which the shutter is open
+    pub fn new(start: u32, end: u32, frames_per_second: f64, shutter_fraction: f64) -> Self {
+        FrameRange {
+            start,
+            end,
+            frames_per_second,
+            shutter_fraction,
+            numbered: true,
+        }
+    }
+
+    /// A single, unnumbered frame whose shutter is open between the times 0 and 1
+    pub fn single() -> Self {
+        FrameRange {
+            start: 0,
+            end: 0,
+            frames_per_second: 1.0,
+            shutter_fraction: 1.0,
+            numbered: false,
+        }
+    }
+
+    /// The frames within the range
+    pub fn frames(&self) -> ::std::ops::RangeInclusive<u32> {
+        self.start..=self.end
+    }
+
+    /// Calculates the times at which the shutter opens and closes during the given frame
+    pub fn shutter_interval(&self, frame: u32) -> (f64, f64) {
+        let open = frame as f64 / self.frames_per_second;
+        (open, open + self.shutter_fraction / self.frames_per_second)
+    }
+
+    /// Calculates the path of a frame's output file, inserting the frame number (if the
+    /// range is numbered) between the file's name and its extension
+    /// (e.g. `output.png` becomes `output_0001.png`)
+    pub fn frame_path(&self, path: &str, frame: u32) -> String {
+        if !self.numbered {
+            return path.to_string();
+        }
+        match path.rfind('.') {
+            Some(index) => format!("{}_{:04}{}", &path[..index], frame, &path[index..]),
+            None => format!("{}_{:04}", path, frame),
+        }
+    }
+}
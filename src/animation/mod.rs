@@ -0,0 +1,3 @@
+pub mod camera;
+pub mod frames;
+pub mod track;
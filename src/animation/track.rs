@@ -0,0 +1,241 @@
+use std::marker::{Send, Sync};
+use vec3::Vec3;
+
+/// A trait declaring that a value can be interpolated between keyframes
+pub trait Animatable: Copy + Send + Sync {
+    /// Computes `self + scale * other`
+    fn add_scaled(&self, other: &Self, scale: f64) -> Self;
+
+    /// Linearly interpolates between `self` (at `t` = 0) and `other` (at `t` = 1)
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        self.add_scaled(other, t).add_scaled(self, -t)
+    }
+}
+
+impl Animatable for f64 {
+    fn add_scaled(&self, other: &f64, scale: f64) -> f64 {
+        self + scale * other
+    }
+}
+
+impl Animatable for Vec3 {
+    fn add_scaled(&self, other: &Vec3, scale: f64) -> Vec3 {
+        *self + scale * *other
+    }
+}
+
+/// How a track's value changes between a keyframe and the next
+#[derive(Copy, Clone, Debug)]
+pub enum Interpolation<T> {
+    /// The value is held until the next keyframe
+    Step,
+    /// The value changes at a constant rate
+    Linear,
+    /// The value follows a cubic Bezier curve, leaving this keyframe and arriving
+    /// at the next with the given rates of change (per unit of time).
+    /// Rates of zero ease in and out of the keyframes.
+    Bezier { out_rate: T, in_rate: T },
+}
+
+/// The value of a track at a point in time
+#[derive(Copy, Clone, Debug)]
+pub struct Keyframe<T> {
+    pub time: f64,
+    pub value: T,
+    /// The interpolation used between this keyframe and the next
+    pub interpolation: Interpolation<T>,
+}
+
+impl<T: Animatable> Keyframe<T> {
+    pub fn new(time: f64, value: T, interpolation: Interpolation<T>) -> Self {
+        Keyframe {
+            time,
+            value,
+            interpolation,
+        }
+    }
+
+    /// Creates a keyframe which is linearly interpolated towards the next
+    pub fn linear(time: f64, value: T) -> Self {
+        Keyframe::new(time, value, Interpolation::Linear)
+    }
+
+    /// Creates a keyframe which eases out of its value, and into the value of the next
+    /// keyframe (i.e. a Bezier curve with zero rates of change at both ends)
+    pub fn eased(time: f64, value: T) -> Self {
+        let zero = value.add_scaled(&value, -1.0);
+        Keyframe::new(
+            time,
+            value,
+            Interpolation::Bezier {
+                out_rate: zero,
+                in_rate: zero,
+            },
+        )
+    }
+}
+
+/// A sequence of keyframes describing how a value changes over time.
+///
+/// Before the first and after the last keyframe, the track holds its first and last values.
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Animatable> Track<T> {
+    /// Creates a new track from the given keyframes, which are sorted by time.
+    ///
+    /// Panics if there are no keyframes, or if the time of a keyframe isn't finite.
+    pub fn new(mut keyframes: Vec<Keyframe<T>>) -> Self {
+        assert!(
+            !keyframes.is_empty(),
+            "A track requires at least one keyframe"
+        );
+        if let Some(key) = keyframes.iter().find(|key| !key.time.is_finite()) {
+            panic!("The time of a keyframe must be finite, but is {}", key.time);
+        }
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Track { keyframes }
+    }
+
+    /// Creates a track whose value never changes
+    pub fn constant(value: T) -> Self {
+        Track::new(vec![Keyframe::linear(0.0, value)])
+    }
+
+    /// Evaluates the track at the given time
+    pub fn evaluate(&self, time: f64) -> T {
+        let first = &self.keyframes[0];
+        if time <= first.time {
+            return first.value;
+        }
+        // Find the keyframes surrounding the given time
+        let next_index = self.keyframes.partition_point(|key| key.time <= time);
+        if next_index == self.keyframes.len() {
+            return self.keyframes[next_index - 1].value;
+        }
+        let (start, end) = (&self.keyframes[next_index - 1], &self.keyframes[next_index]);
+        let duration = end.time - start.time;
+        let t = (time - start.time) / duration;
+        match start.interpolation {
+            Interpolation::Step => start.value,
+            Interpolation::Linear => start.value.lerp(&end.value, t),
+            Interpolation::Bezier { out_rate, in_rate } => {
+                let (control_1, control_2) = bezier_controls(start, end, out_rate, in_rate);
+                // de Casteljau's algorithm
+                let a = start.value.lerp(&control_1, t);
+                let b = control_1.lerp(&control_2, t);
+                let c = control_2.lerp(&end.value, t);
+                let d = a.lerp(&b, t);
+                let e = b.lerp(&c, t);
+                d.lerp(&e, t)
+            }
+        }
+    }
+
+    /// Finds values whose convex hull contains every value the track takes between the
+    /// given times (so any bounds of these values also bound the track's motion)
+    pub fn hull_values(&self, start_time: f64, end_time: f64) -> Vec<T> {
+        let mut values = vec![self.evaluate(start_time), self.evaluate(end_time)];
+        for pair in self.keyframes.windows(2) {
+            let (start, end) = (&pair[0], &pair[1]);
+            if end.time <= start_time || start.time >= end_time {
+                continue;
+            }
+            match start.interpolation {
+                Interpolation::Step => values.push(start.value),
+                Interpolation::Linear => {
+                    values.push(self.evaluate(start.time.max(start_time)));
+                    values.push(self.evaluate(end.time.min(end_time)));
+                }
+                // A Bezier curve lies within the convex hull of its control points
+                Interpolation::Bezier { out_rate, in_rate } => {
+                    let (control_1, control_2) = bezier_controls(start, end, out_rate, in_rate);
+                    values.extend_from_slice(&[start.value, control_1, control_2, end.value]);
+                }
+            }
+        }
+        values
+    }
+}
+
+/// Computes the inner control points of the Bezier curve between two keyframes, which
+/// lie a third of the way along each tangent
+fn bezier_controls<T: Animatable>(
+    start: &Keyframe<T>,
+    end: &Keyframe<T>,
+    out_rate: T,
+    in_rate: T,
+) -> (T, T) {
+    let duration = end.time - start.time;
+    (
+        start.value.add_scaled(&out_rate, duration / 3.0),
+        end.value.add_scaled(&in_rate, -duration / 3.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorts_keyframes_and_holds_end_values() {
+        let track = Track::new(vec![Keyframe::linear(2.0, 4.0), Keyframe::linear(0.0, 0.0)]);
+        assert_eq!(track.evaluate(-1.0), 0.0);
+        assert_eq!(track.evaluate(1.0), 2.0);
+        assert_eq!(track.evaluate(3.0), 4.0);
+    }
+
+    #[test]
+    fn interpolates_each_kind_of_keyframe() {
+        let step = Track::new(vec![
+            Keyframe::new(0.0, 1.0, Interpolation::Step),
+            Keyframe::linear(1.0, 3.0),
+        ]);
+        assert_eq!(step.evaluate(0.99), 1.0);
+        assert_eq!(step.evaluate(1.0), 3.0);
+        let eased = Track::new(vec![Keyframe::eased(0.0, 0.0), Keyframe::linear(2.0, 8.0)]);
+        assert!((eased.evaluate(1.0) - 4.0).abs() < 1e-12);
+        assert!(eased.evaluate(0.2) < 0.2 * 4.0);
+    }
+
+    #[test]
+    fn hull_values_bound_the_track() {
+        let track = Track::new(vec![
+            Keyframe::new(
+                0.0,
+                0.0,
+                Interpolation::Bezier {
+                    out_rate: 10.0,
+                    in_rate: 10.0,
+                },
+            ),
+            Keyframe::linear(1.0, 1.0),
+            Keyframe::new(2.0, -1.0, Interpolation::Step),
+        ]);
+        let (start, end) = (0.25, 1.5);
+        let hull = track.hull_values(start, end);
+        let min = hull.iter().cloned().fold(std::f64::MAX, f64::min);
+        let max = hull.iter().cloned().fold(std::f64::MIN, f64::max);
+        for i in 0..=1000 {
+            let value = track.evaluate(start + (end - start) * i as f64 / 1000.0);
+            assert!(value >= min && value <= max);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one keyframe")]
+    fn rejects_empty_tracks() {
+        Track::<f64>::new(vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be finite")]
+    fn rejects_non_finite_times() {
+        Track::new(vec![
+            Keyframe::linear(0.0, 1.0),
+            Keyframe::linear(std::f64::NAN, 2.0),
+        ]);
+    }
+}
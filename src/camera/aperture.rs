@@ -0,0 +1,11 @@
+use sampler::sampler::Sampler;
+use std::marker::{Send, Sync};
+use vec3::Vec3;
+
+/// A trait declaring that an object describes the shape of a lens' aperture,
+/// which determines the shape of out-of-focus highlights (i.e. bokeh).
+pub trait Aperture: Send + Sync {
+    /// Samples a point on the aperture, within the square [-1, 1]^2 of the XY plane
+    /// (which is then scaled by the lens radius)
+    fn sample(&self, sampler: &mut Sampler) -> Vec3;
+}
@@ -0,0 +1,118 @@
+use camera::aperture::Aperture;
+use hitable::utils;
+use image;
+use sampler::sampler::Sampler;
+use sampler::utils::ONE_MINUS_EPSILON;
+use std::f64::consts::PI;
+use std::path::Path;
+use vec3::Vec3;
+
+/// A circular aperture, producing round bokeh
+#[derive(Copy, Clone, Debug)]
+pub struct CircularAperture;
+
+impl Aperture for CircularAperture {
+    fn sample(&self, sampler: &mut Sampler) -> Vec3 {
+        utils::random_point_in_unit_disk(sampler)
+    }
+}
+
+/// A regular polygonal aperture, such as the one formed by the blades of an iris diaphragm
+#[derive(Copy, Clone, Debug)]
+pub struct PolygonalAperture {
+    /// The number of blades (i.e. the number of sides of the polygon)
+    pub blades: u32,
+    /// The rotation of the polygon about the optical axis (in degrees)
+    pub rotation: f64,
+}
+
+impl PolygonalAperture {
+    pub fn new(blades: u32, rotation: f64) -> Self {
+        PolygonalAperture {
+            blades: blades.max(3),
+            rotation,
+        }
+    }
+}
+
+impl Aperture for PolygonalAperture {
+    fn sample(&self, sampler: &mut Sampler) -> Vec3 {
+        // The polygon is split into triangles fanning out from its center, each of equal area.
+        // The first sample value selects a triangle, and is then remapped to [0, 1).
+        let (u, v) = sampler.get_2d();
+        let scaled = u * self.blades as f64;
+        let triangle = (scaled as u32).min(self.blades - 1);
+        let u = (scaled - triangle as f64).min(ONE_MINUS_EPSILON);
+        let sector = 2.0 * PI / self.blades as f64;
+        let theta_0 = self.rotation.to_radians() + triangle as f64 * sector;
+        let theta_1 = theta_0 + sector;
+        // Uniformly sample the triangle formed by the center and two adjacent vertices
+        let s = u.sqrt();
+        let (a, b) = (s * (1.0 - v), s * v);
+        Vec3::new(
+            a * theta_0.cos() + b * theta_1.cos(),
+            a * theta_0.sin() + b * theta_1.sin(),
+            0.0,
+        )
+    }
+}
+
+/// An aperture whose shape (and transmission) is given by a grayscale image,
+/// stretched over the square enclosing the lens.
+///
+/// Points are sampled proportionally to the brightness of the image's pixels,
+/// so any shape (e.g. a star or a heart) can be used to produce shaped bokeh.
+#[derive(Clone, Debug)]
+pub struct ImageAperture {
+    width: u32,
+    height: u32,
+    /// The cumulative distribution of the brightness of the image's pixels
+    cdf: Vec<f64>,
+}
+
+impl ImageAperture {
+    /// Loads an aperture from the image at `path`
+    pub fn new(path: &Path) -> image::ImageResult<Self> {
+        let image = image::open(path)?.to_luma();
+        let (width, height) = image.dimensions();
+        let mut total = 0.0;
+        let cdf = image
+            .pixels()
+            .map(|pixel| {
+                total += pixel[0] as f64 / 255.0;
+                total
+            })
+            .collect();
+        Ok(ImageAperture { width, height, cdf })
+    }
+}
+
+impl Aperture for ImageAperture {
+    fn sample(&self, sampler: &mut Sampler) -> Vec3 {
+        let (u, v) = sampler.get_2d();
+        let total = self.cdf.last().cloned().unwrap_or(0.0);
+        if total <= 0.0 {
+            // A completely black image blocks all light except through the lens' center
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let target = u * total;
+        // Select the first pixel whose cumulative brightness exceeds the target
+        let index = self
+            .cdf
+            .partition_point(|&value| value <= target)
+            .min(self.cdf.len() - 1);
+        // Reuse the position of the first value within the selected pixel's
+        // portion of the distribution to jitter the point horizontally
+        let start = if index == 0 { 0.0 } else { self.cdf[index - 1] };
+        let jitter_x = ((target - start) / (self.cdf[index] - start))
+            .max(0.0)
+            .min(ONE_MINUS_EPSILON);
+        let (x, y) = (index as u32 % self.width, index as u32 / self.width);
+        Vec3::new(
+            2.0 * (x as f64 + jitter_x) / self.width as f64 - 1.0,
+            // Image rows increase downwards
+            1.0 - 2.0 * (y as f64 + v) / self.height as f64,
+            0.0,
+        )
+    }
+}
@@ -0,0 +1,23 @@
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::marker::{Send, Sync};
+
+/// A trait declaring that an object can map points on the film to rays cast into the scene.
+pub trait Camera: Send + Sync {
+    /// Creates the ray passing through the given point on the film.
+    /// #### Arguments:
+    /// - `x`: The horizontal film coordinate, in [0, 1] from left to right
+    /// - `y`: The vertical film coordinate, in [0, 1] from bottom to top
+    /// - `sampler`: Provides the position on the lens and the time at which the ray is cast
+    ///
+    /// #### Returns:
+    /// - The generated ray, or `None` if the point does not receive any light
+    /// (e.g. it lies outside a fisheye's image circle, or is blocked by vignetting)
+    fn create_ray(&self, x: f64, y: f64, sampler: &mut Sampler) -> Option<Ray>;
+
+    /// The factor by which the radiance arriving at the given point on the film is scaled,
+    /// accounting for the camera's exposure settings and any vignetting.
+    fn exposure(&self, _x: f64, _y: f64) -> f64 {
+        1.0
+    }
+}
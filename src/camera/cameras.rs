@@ -0,0 +1,607 @@
+use camera::aperture::Aperture;
+use camera::apertures::CircularAperture;
+use camera::camera::Camera;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::consts::PI;
+use std::f64::MAX as FLOAT_MAX;
+use std::sync::Arc;
+use vec3::{cross, dot, unit_vector, Vec3};
+
+/// Computes the orthonormal basis (u, v, w) of a camera, where `u` points right,
+/// `v` points up and `w` points *away* from the view direction
+fn camera_basis(look_in: Vec3, view_up: Vec3) -> (Vec3, Vec3, Vec3) {
+    let w = unit_vector(-look_in);
+    let u = unit_vector(cross(&view_up, &w));
+    let v = cross(&w, &u);
+    (u, v, w)
+}
+
+/// Samples the point in time at which a ray is cast, while the shutter is open
+pub fn sample_time(shutter_open_time: f64, shutter_close_time: f64, sampler: &mut Sampler) -> f64 {
+    shutter_open_time + sampler.get_1d() * (shutter_close_time - shutter_open_time)
+}
+
+/// The settings of a real camera body and lens, from which the field of view,
+/// depth of field and exposure of a PerspectiveCamera can be derived.
+#[derive(Copy, Clone, Debug)]
+pub struct PhotographicSettings {
+    /// The focal length of the lens (in millimetres)
+    pub focal_length: f64,
+    /// The width of the sensor (in millimetres), e.g. 36 for a full-frame sensor.
+    /// The height of the sensor is determined by the aspect ratio of the image.
+    pub sensor_width: f64,
+    /// The ratio of the focal length to the diameter of the aperture
+    pub f_stop: f64,
+    /// The length of time for which the shutter is open (in seconds)
+    pub shutter_speed: f64,
+    /// The sensitivity of the sensor
+    pub iso: f64,
+    /// Whether the natural (cos^4) falloff of light towards the edges of the image is simulated
+    pub vignetting: bool,
+    /// The number of scene units per metre
+    pub scene_scale: f64,
+}
+
+impl PhotographicSettings {
+    /// Creates the settings of a full-frame camera, without vignetting,
+    /// for a scene measured in metres.
+    /// #### Arguments:
+    /// - `focal_length`: The focal length of the lens (in millimetres)
+    /// - `f_stop`: The f-number of the aperture
+    /// - `shutter_speed`: The length of time for which the shutter is open (in seconds)
+    /// - `iso`: The sensitivity of the sensor
+    pub fn new(focal_length: f64, f_stop: f64, shutter_speed: f64, iso: f64) -> Self {
+        PhotographicSettings {
+            focal_length,
+            sensor_width: 36.0,
+            f_stop,
+            shutter_speed,
+            iso,
+            vignetting: false,
+            scene_scale: 1.0,
+        }
+    }
+
+    /// Calculates the vertical field of view (in degrees) for an image of the given aspect ratio
+    pub fn vertical_fov(&self, aspect_ratio: f64) -> f64 {
+        let sensor_height = self.sensor_width / aspect_ratio;
+        2.0 * (sensor_height / (2.0 * self.focal_length)).atan() * 180.0 / PI
+    }
+
+    /// Calculates the diameter of the aperture (in scene units)
+    pub fn aperture(&self) -> f64 {
+        self.focal_length / self.f_stop / 1000.0 * self.scene_scale
+    }
+
+    /// Calculates the factor by which scene radiance is scaled when it is recorded.
+    ///
+    /// The exposure is proportional to the shutter speed and ISO, and inversely proportional
+    /// to the square of the f-number. It is normalized using the "sunny 16" rule, such
+    /// that f/16, a 1/100 second shutter speed and ISO 100 produce an exposure of 1
+    /// (i.e. a radiance of 1 is treated as a diffuse surface in bright sunlight).
+    pub fn exposure(&self) -> f64 {
+        let sunny_16 = (1.0 / 100.0) / (16.0 * 16.0);
+        (self.shutter_speed * self.iso / 100.0) / (self.f_stop * self.f_stop) / sunny_16
+    }
+}
+
+/// A perspective camera with a thin lens, which focuses on a plane
+/// at a fixed distance and blurs everything in front of or behind it.
+#[derive(Clone)]
+pub struct PerspectiveCamera {
+    pub lens_radius: f64,
+    pub lower_left_corner: Vec3,
+    pub horizontal: Vec3,
+    pub origin: Vec3,
+    pub vertical: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+    pub aspect_ratio: f64,
+    /// The distance from the lens to the plane in perfect focus
+    pub focus_distance: f64,
+    pub shutter_open_time: f64,
+    pub shutter_close_time: f64,
+    /// The factor by which all incoming radiance is scaled
+    pub exposure: f64,
+    /// Whether the natural (cos^4) falloff of light towards the edges of the image is simulated
+    pub vignetting: bool,
+    /// The shape of the lens' aperture, which determines the shape of the bokeh
+    pub aperture_shape: Arc<Aperture>,
+    /// The strength of the mechanical vignetting towards the edges of the image, which
+    /// clips the aperture into a "cat's eye" shape (0 disables vignetting, while 1 closes
+    /// the aperture completely at the corners of the image)
+    pub cats_eye: f64,
+}
+
+impl PerspectiveCamera {
+    /// Creates a new perspective camera, with a circular aperture and no vignetting.
+    /// #### Arguments:
+    /// - `look_from`: The point from which to center the camera
+    /// - `look_in`: The *direction* in which the camera is aimed
+    /// - `view_up`: The "up" direction of the camera
+    /// - `vert_fov`: The vertical field of view in degrees
+    /// - `aspect_ratio`: Aspect ratio to use for the camera
+    /// - `aperture`: The simulated aperture of the camera lens
+    /// - `focus_distance`: The distance from the lens to the focal point
+    /// - `shutter_open_time`: Point of time at which the camera shutter opens
+    /// - `shutter_close_time`: Point of time at which the camera shutter closes
+    pub fn new(
+        look_from: Vec3,
+        look_in: Vec3,
+        view_up: Vec3,
+        vert_fov: f64,
+        aspect_ratio: f64,
+        aperture: f64,
+        focus_distance: f64,
+        shutter_open_time: f64,
+        shutter_close_time: f64,
+    ) -> Self {
+        let theta = vert_fov * PI / 180.0;
+        let half_height = (theta / 2.0).tan();
+        let half_width = aspect_ratio * half_height;
+        let (u, v, w) = camera_basis(look_in, view_up);
+        PerspectiveCamera {
+            lens_radius: aperture / 2.0,
+            lower_left_corner: look_from
+                - (half_width * focus_distance * u)
+                - (half_height * focus_distance * v)
+                - (focus_distance * w),
+            horizontal: 2.0 * half_width * focus_distance * u,
+            vertical: 2.0 * half_height * focus_distance * v,
+            origin: look_from,
+            u,
+            v,
+            w,
+            aspect_ratio,
+            focus_distance,
+            shutter_open_time,
+            shutter_close_time,
+            exposure: 1.0,
+            vignetting: false,
+            aperture_shape: Arc::new(CircularAperture),
+            cats_eye: 0.0,
+        }
+    }
+
+    /// Creates a new perspective camera from the settings of a real camera. The shutter
+    /// speed determines both the exposure and the duration of the motion blur.
+    /// #### Arguments:
+    /// - `look_from`: The point from which to center the camera
+    /// - `look_in`: The *direction* in which the camera is aimed
+    /// - `view_up`: The "up" direction of the camera
+    /// - `aspect_ratio`: Aspect ratio to use for the camera
+    /// - `focus_distance`: The distance from the lens to the focal point
+    /// - `shutter_open_time`: Point of time at which the camera shutter opens
+    /// - `settings`: The settings of the camera body and lens
+    pub fn photographic(
+        look_from: Vec3,
+        look_in: Vec3,
+        view_up: Vec3,
+        aspect_ratio: f64,
+        focus_distance: f64,
+        shutter_open_time: f64,
+        settings: &PhotographicSettings,
+    ) -> Self {
+        let mut camera = PerspectiveCamera::new(
+            look_from,
+            look_in,
+            view_up,
+            settings.vertical_fov(aspect_ratio),
+            aspect_ratio,
+            settings.aperture(),
+            focus_distance,
+            shutter_open_time,
+            shutter_open_time + settings.shutter_speed,
+        );
+        camera.exposure = settings.exposure();
+        camera.vignetting = settings.vignetting;
+        camera
+    }
+
+    /// Creates a new perspective camera aimed at (and focused on) a target point.
+    /// #### Arguments:
+    /// - `look_from`: The point from which to center the camera
+    /// - `look_at`: The point at the center of the image
+    /// - `view_up`: The "up" direction of the camera
+    /// - `vert_fov`: The vertical field of view in degrees
+    /// - `aspect_ratio`: Aspect ratio to use for the camera
+    /// - `aperture`: The simulated aperture of the camera lens
+    /// - `shutter_open_time`: Point of time at which the camera shutter opens
+    /// - `shutter_close_time`: Point of time at which the camera shutter closes
+    pub fn look_at(
+        look_from: Vec3,
+        look_at: Vec3,
+        view_up: Vec3,
+        vert_fov: f64,
+        aspect_ratio: f64,
+        aperture: f64,
+        shutter_open_time: f64,
+        shutter_close_time: f64,
+    ) -> Self {
+        PerspectiveCamera::new(
+            look_from,
+            look_at - look_from,
+            view_up,
+            vert_fov,
+            aspect_ratio,
+            aperture,
+            (look_at - look_from).length(),
+            shutter_open_time,
+            shutter_close_time,
+        )
+    }
+
+    /// Creates a new perspective camera, looking in the given direction, positioned
+    /// such that the bounding box of `object` exactly fits within its field of view.
+    /// The camera is focused on the center of the bounding box.
+    /// #### Arguments:
+    /// - `object`: The object to frame
+    /// - `look_in`: The *direction* in which the camera is aimed
+    /// - `view_up`: The "up" direction of the camera
+    /// - `vert_fov`: The vertical field of view in degrees
+    /// - `aspect_ratio`: Aspect ratio to use for the camera
+    /// - `aperture`: The simulated aperture of the camera lens
+    /// - `shutter_open_time`: Point of time at which the camera shutter opens
+    /// - `shutter_close_time`: Point of time at which the camera shutter closes
+    ///
+    /// #### Returns:
+    /// - The camera, or None if the object has no bounding box
+    pub fn frame_object(
+        object: &Hitable,
+        look_in: Vec3,
+        view_up: Vec3,
+        vert_fov: f64,
+        aspect_ratio: f64,
+        aperture: f64,
+        shutter_open_time: f64,
+        shutter_close_time: f64,
+    ) -> Option<Self> {
+        let bbox = object.bounding_box(shutter_open_time, shutter_close_time)?;
+        let center = 0.5 * (bbox.min_bound + bbox.max_bound);
+        // Fit the sphere enclosing the box within the narrower of the two fields of view
+        let radius = 0.5 * (bbox.max_bound - bbox.min_bound).length();
+        let half_height = (vert_fov * PI / 360.0).tan();
+        let half_fov = half_height.atan().min((aspect_ratio * half_height).atan());
+        let distance = radius / half_fov.sin();
+        Some(PerspectiveCamera::look_at(
+            center - distance * unit_vector(look_in),
+            center,
+            view_up,
+            vert_fov,
+            aspect_ratio,
+            aperture,
+            shutter_open_time,
+            shutter_close_time,
+        ))
+    }
+
+    /// Moves the plane of focus to the given distance from the lens
+    pub fn set_focus_distance(&mut self, focus_distance: f64) {
+        let scale = focus_distance / self.focus_distance;
+        self.lower_left_corner = self.origin + scale * (self.lower_left_corner - self.origin);
+        self.horizontal = scale * self.horizontal;
+        self.vertical = scale * self.vertical;
+        self.focus_distance = focus_distance;
+    }
+
+    /// Focuses the camera on whatever is visible at the given point on the film, by
+    /// tracing a probe ray through the center of the lens.
+    /// #### Arguments:
+    /// - `world`: The scene against which the probe ray is traced
+    /// - `x`: The horizontal film coordinate, in [0, 1] from left to right
+    /// - `y`: The vertical film coordinate, in [0, 1] from bottom to top
+    /// - `sampler`: Provides any sample values needed to trace the probe ray
+    ///
+    /// #### Returns:
+    /// - bool: Whether the probe ray hit anything (otherwise the focus is left unchanged)
+    pub fn auto_focus(&mut self, world: &Hitable, x: f64, y: f64, sampler: &mut Sampler) -> bool {
+        let probe = Ray::new(
+            self.origin,
+            self.lower_left_corner + (x * self.horizontal) + (y * self.vertical) - self.origin,
+            self.shutter_open_time,
+        );
+        let mut rec = HitRecord::new();
+        if !world.hit(&probe, 0.00001, FLOAT_MAX, &mut rec, sampler) {
+            return false;
+        }
+        // The plane of focus is perpendicular to the view direction
+        let focus_distance = -dot(&(rec.hit_point - self.origin), &self.w);
+        if focus_distance <= 0.0 {
+            return false;
+        }
+        self.set_focus_distance(focus_distance);
+        true
+    }
+
+    /// Creates the ray passing through the given point on the film, from a given point on
+    /// the lens and at a given time (rather than sampling them)
+    /// #### Arguments:
+    /// - `x`: The horizontal film coordinate, in [0, 1] from left to right
+    /// - `y`: The vertical film coordinate, in [0, 1] from bottom to top
+    /// - `lens_point`: The point within the aperture shape (in the unit disk's coordinates)
+    /// - `time`: The time at which the ray is cast
+    pub fn create_ray_at(&self, x: f64, y: f64, lens_point: Vec3, time: f64) -> Option<Ray> {
+        if self.cats_eye > 0.0 {
+            // The lens barrel is modelled as a second disk the size of the aperture, which
+            // is shifted further from the optical axis the further the point is from the
+            // center of the image. Only points within both are able to pass light.
+            let diagonal = (self.aspect_ratio * self.aspect_ratio + 1.0).sqrt();
+            let shift = Vec3::new(
+                self.cats_eye * (2.0 * x - 1.0) * self.aspect_ratio / diagonal,
+                self.cats_eye * (2.0 * y - 1.0) / diagonal,
+                0.0,
+            );
+            if (lens_point - shift).length() > 1.0 {
+                return None;
+            }
+        }
+        let rand_point = self.lens_radius * lens_point;
+        let offset = (self.u * rand_point.x()) + (self.v * rand_point.y());
+        Some(Ray::new(
+            self.origin + offset,
+            self.lower_left_corner + (x * self.horizontal) + (y * self.vertical)
+                - self.origin
+                - offset,
+            time,
+        ))
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn create_ray(&self, x: f64, y: f64, sampler: &mut Sampler) -> Option<Ray> {
+        let lens_point = self.aperture_shape.sample(sampler);
+        let time = sample_time(self.shutter_open_time, self.shutter_close_time, sampler);
+        self.create_ray_at(x, y, lens_point, time)
+    }
+
+    fn exposure(&self, x: f64, y: f64) -> f64 {
+        if !self.vignetting {
+            return self.exposure;
+        }
+        let direction =
+            self.lower_left_corner + (x * self.horizontal) + (y * self.vertical) - self.origin;
+        let cos_theta = -dot(&unit_vector(direction), &self.w);
+        self.exposure * cos_theta.powi(4)
+    }
+}
+
+/// An orthographic camera, which casts parallel rays from a rectangular
+/// film so that objects do not shrink with distance.
+#[derive(Copy, Clone)]
+pub struct OrthographicCamera {
+    pub lower_left_corner: Vec3,
+    pub horizontal: Vec3,
+    pub vertical: Vec3,
+    pub direction: Vec3,
+    pub shutter_open_time: f64,
+    pub shutter_close_time: f64,
+}
+
+impl OrthographicCamera {
+    /// Creates a new orthographic camera.
+    /// #### Arguments:
+    /// - `look_from`: The center of the camera's film
+    /// - `look_in`: The *direction* in which the camera is aimed
+    /// - `view_up`: The "up" direction of the camera
+    /// - `view_height`: The height of the region of the scene captured by the film
+    /// - `aspect_ratio`: Aspect ratio to use for the camera
+    /// - `shutter_open_time`: Point of time at which the camera shutter opens
+    /// - `shutter_close_time`: Point of time at which the camera shutter closes
+    pub fn new(
+        look_from: Vec3,
+        look_in: Vec3,
+        view_up: Vec3,
+        view_height: f64,
+        aspect_ratio: f64,
+        shutter_open_time: f64,
+        shutter_close_time: f64,
+    ) -> Self {
+        let (u, v, w) = camera_basis(look_in, view_up);
+        let horizontal = view_height * aspect_ratio * u;
+        let vertical = view_height * v;
+        OrthographicCamera {
+            lower_left_corner: look_from - (0.5 * horizontal) - (0.5 * vertical),
+            horizontal,
+            vertical,
+            direction: -w,
+            shutter_open_time,
+            shutter_close_time,
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn create_ray(&self, x: f64, y: f64, sampler: &mut Sampler) -> Option<Ray> {
+        let time = sample_time(self.shutter_open_time, self.shutter_close_time, sampler);
+        Some(Ray::new(
+            self.lower_left_corner + (x * self.horizontal) + (y * self.vertical),
+            self.direction,
+            time,
+        ))
+    }
+}
+
+/// A 360° panoramic camera using the equirectangular projection, where the horizontal
+/// film axis maps to longitude and the vertical film axis maps to latitude.
+///
+/// The center of the image faces the view direction; an aspect ratio of 2:1 gives square texels.
+#[derive(Copy, Clone)]
+pub struct EquirectangularCamera {
+    pub origin: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+    pub shutter_open_time: f64,
+    pub shutter_close_time: f64,
+}
+
+impl EquirectangularCamera {
+    /// Creates a new equirectangular camera.
+    /// #### Arguments:
+    /// - `look_from`: The point from which rays are cast in every direction
+    /// - `look_in`: The *direction* shown at the center of the image
+    /// - `view_up`: The "up" direction of the camera (i.e. the direction of the north pole)
+    /// - `shutter_open_time`: Point of time at which the camera shutter opens
+    /// - `shutter_close_time`: Point of time at which the camera shutter closes
+    pub fn new(
+        look_from: Vec3,
+        look_in: Vec3,
+        view_up: Vec3,
+        shutter_open_time: f64,
+        shutter_close_time: f64,
+    ) -> Self {
+        let (u, v, w) = camera_basis(look_in, view_up);
+        EquirectangularCamera {
+            origin: look_from,
+            u,
+            v,
+            w,
+            shutter_open_time,
+            shutter_close_time,
+        }
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn create_ray(&self, x: f64, y: f64, sampler: &mut Sampler) -> Option<Ray> {
+        let time = sample_time(self.shutter_open_time, self.shutter_close_time, sampler);
+        let longitude = (x - 0.5) * 2.0 * PI;
+        let latitude = (y - 0.5) * PI;
+        let direction = latitude.cos() * (longitude.sin() * self.u - longitude.cos() * self.w)
+            + latitude.sin() * self.v;
+        Some(Ray::new(self.origin, direction, time))
+    }
+}
+
+/// A fisheye camera using the equidistant projection, where the distance of a point
+/// from the center of the image is proportional to the angle of its ray from the view direction.
+///
+/// The image circle spans the height of the film; points outside it receive no light.
+#[derive(Copy, Clone)]
+pub struct FisheyeCamera {
+    pub origin: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+    /// The field of view across the diameter of the image circle (in radians)
+    pub fov: f64,
+    pub aspect_ratio: f64,
+    pub shutter_open_time: f64,
+    pub shutter_close_time: f64,
+}
+
+impl FisheyeCamera {
+    /// Creates a new fisheye camera.
+    /// #### Arguments:
+    /// - `look_from`: The point from which to center the camera
+    /// - `look_in`: The *direction* in which the camera is aimed
+    /// - `view_up`: The "up" direction of the camera
+    /// - `fov`: The field of view across the image circle in degrees (up to 360)
+    /// - `aspect_ratio`: Aspect ratio to use for the camera
+    /// - `shutter_open_time`: Point of time at which the camera shutter opens
+    /// - `shutter_close_time`: Point of time at which the camera shutter closes
+    pub fn new(
+        look_from: Vec3,
+        look_in: Vec3,
+        view_up: Vec3,
+        fov: f64,
+        aspect_ratio: f64,
+        shutter_open_time: f64,
+        shutter_close_time: f64,
+    ) -> Self {
+        let (u, v, w) = camera_basis(look_in, view_up);
+        FisheyeCamera {
+            origin: look_from,
+            u,
+            v,
+            w,
+            fov: fov.min(360.0) * PI / 180.0,
+            aspect_ratio,
+            shutter_open_time,
+            shutter_close_time,
+        }
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn create_ray(&self, x: f64, y: f64, sampler: &mut Sampler) -> Option<Ray> {
+        let time = sample_time(self.shutter_open_time, self.shutter_close_time, sampler);
+        let (image_x, image_y) = ((2.0 * x - 1.0) * self.aspect_ratio, 2.0 * y - 1.0);
+        let radius = (image_x * image_x + image_y * image_y).sqrt();
+        if radius > 1.0 {
+            return None;
+        }
+        let theta = radius * self.fov / 2.0;
+        let (cos_phi, sin_phi) = if radius > 0.0 {
+            (image_x / radius, image_y / radius)
+        } else {
+            (1.0, 0.0)
+        };
+        let direction = theta.sin() * (cos_phi * self.u + sin_phi * self.v) - theta.cos() * self.w;
+        Some(Ray::new(self.origin, direction, time))
+    }
+}
+
+/// A cylindrical panoramic camera, which projects the scene onto a cylinder around the
+/// "up" axis. The horizontal film axis maps to the angle around the cylinder, while
+/// the vertical film axis is a perspective projection, so vertical lines remain straight.
+#[derive(Copy, Clone)]
+pub struct CylindricalCamera {
+    pub origin: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+    /// The horizontal field of view (in radians)
+    pub horizontal_fov: f64,
+    /// Half the height of the cylinder at unit distance from its axis
+    pub half_height: f64,
+    pub shutter_open_time: f64,
+    pub shutter_close_time: f64,
+}
+
+impl CylindricalCamera {
+    /// Creates a new cylindrical camera.
+    /// #### Arguments:
+    /// - `look_from`: The point from which to center the camera
+    /// - `look_in`: The *direction* shown at the center of the image
+    /// - `view_up`: The "up" direction of the camera (i.e. the cylinder's axis)
+    /// - `horizontal_fov`: The horizontal field of view in degrees (up to 360)
+    /// - `vert_fov`: The vertical field of view in degrees (less than 180)
+    /// - `shutter_open_time`: Point of time at which the camera shutter opens
+    /// - `shutter_close_time`: Point of time at which the camera shutter closes
+    pub fn new(
+        look_from: Vec3,
+        look_in: Vec3,
+        view_up: Vec3,
+        horizontal_fov: f64,
+        vert_fov: f64,
+        shutter_open_time: f64,
+        shutter_close_time: f64,
+    ) -> Self {
+        let (u, v, w) = camera_basis(look_in, view_up);
+        CylindricalCamera {
+            origin: look_from,
+            u,
+            v,
+            w,
+            horizontal_fov: horizontal_fov.min(360.0) * PI / 180.0,
+            half_height: (vert_fov * PI / 360.0).tan(),
+            shutter_open_time,
+            shutter_close_time,
+        }
+    }
+}
+
+impl Camera for CylindricalCamera {
+    fn create_ray(&self, x: f64, y: f64, sampler: &mut Sampler) -> Option<Ray> {
+        let time = sample_time(self.shutter_open_time, self.shutter_close_time, sampler);
+        let phi = (x - 0.5) * self.horizontal_fov;
+        let height = (2.0 * y - 1.0) * self.half_height;
+        let direction = phi.sin() * self.u - phi.cos() * self.w + height * self.v;
+        Some(Ray::new(self.origin, direction, time))
+    }
+}
@@ -0,0 +1,4 @@
+pub mod aperture;
+pub mod apertures;
+pub mod camera;
+pub mod cameras;
@@ -0,0 +1,246 @@
+use vec3::Vec3;
+
+/// The smallest mean luminance used when computing a pixel's relative error, which
+/// prevents nearly black pixels from being considered noisy indefinitely
+const MIN_LUMINANCE: f64 = 0.01;
+
+/// Settings controlling how many samples are spent on each pixel.
+///
+/// Every pixel first receives `min_samples` samples, after which further batches
+/// of `batch_size` samples are only taken while the pixel's estimated relative error
+/// remains above `noise_threshold`, up to a total of `max_samples`.
+#[derive(Copy, Clone, Debug)]
+pub struct AdaptiveSampling {
+    pub min_samples: usize,
+    pub max_samples: usize,
+    pub batch_size: usize,
+    /// The relative standard error of a pixel's mean luminance below which
+    /// the pixel is considered converged
+    pub noise_threshold: f64,
+}
+
+impl AdaptiveSampling {
+    /// Constructs a new AdaptiveSampling configuration
+    /// #### Arguments:
+    /// - `min_samples`: The number of samples taken before a pixel's error is first estimated
+    /// - `max_samples`: The maximum number of samples taken by any pixel. When using a
+    /// stratified sampler, this should not exceed the sampler's samples per pixel.
+    /// - `batch_size`: The number of samples taken between successive error estimates
+    /// - `noise_threshold`: The relative error at which a pixel stops receiving samples
+    pub fn new(
+        min_samples: usize,
+        max_samples: usize,
+        batch_size: usize,
+        noise_threshold: f64,
+    ) -> Self {
+        AdaptiveSampling {
+            min_samples: min_samples.max(2).min(max_samples),
+            max_samples,
+            batch_size: batch_size.max(1),
+            noise_threshold,
+        }
+    }
+
+    /// Constructs a configuration which disables adaptive sampling,
+    /// taking exactly `num_samples` samples for every pixel
+    pub fn fixed(num_samples: usize) -> Self {
+        AdaptiveSampling {
+            min_samples: num_samples,
+            max_samples: num_samples,
+            batch_size: num_samples.max(1),
+            noise_threshold: 0.0,
+        }
+    }
+
+    /// Determines the number of samples the pixel should take next
+    /// #### Arguments:
+    /// - `variance`: The estimate accumulated from the pixel's samples so far
+    ///
+    /// #### Returns:
+    /// - The size of the next batch of samples, or 0 if the pixel is finished
+    pub fn next_batch(&self, variance: &PixelVariance) -> usize {
+        let num_samples = variance.num_samples;
+        if num_samples >= self.max_samples {
+            0
+        } else if num_samples < self.min_samples {
+            self.min_samples - num_samples
+        } else if variance.relative_error() <= self.noise_threshold {
+            0
+        } else {
+            self.batch_size.min(self.max_samples - num_samples)
+        }
+    }
+}
+
+/// A running estimate of the mean and variance of a pixel's sample luminance,
+/// computed using Welford's online algorithm
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PixelVariance {
+    pub num_samples: usize,
+    mean: f64,
+    squared_deviation_sum: f64,
+}
+
+impl PixelVariance {
+    pub fn new() -> Self {
+        PixelVariance {
+            num_samples: 0,
+            mean: 0.0,
+            squared_deviation_sum: 0.0,
+        }
+    }
+
+    /// Adds a sample's color to the estimate
+    pub fn add(&mut self, color: &Vec3) {
+        let value = luminance(color);
+        self.num_samples += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.num_samples as f64;
+        self.squared_deviation_sum += delta * (value - self.mean);
+    }
+
+    /// Computes the (unbiased) sample variance of the luminance
+    pub fn variance(&self) -> f64 {
+        if self.num_samples < 2 {
+            return 0.0;
+        }
+        self.squared_deviation_sum / (self.num_samples - 1) as f64
+    }
+
+    /// Computes the standard error of the mean luminance, relative to the mean itself
+    pub fn relative_error(&self) -> f64 {
+        if self.num_samples < 2 {
+            return std::f64::INFINITY;
+        }
+        (self.variance() / self.num_samples as f64).sqrt() / self.mean.max(MIN_LUMINANCE)
+    }
+}
+
+/// Maps the number of samples spent on a pixel to a heatmap color,
+/// ranging from blue (`min_samples`) through green to red (`max_samples`)
+pub fn heatmap_color(num_samples: usize, settings: &AdaptiveSampling) -> Vec3 {
+    let range = (settings.max_samples - settings.min_samples).max(1) as f64;
+    let t = ((num_samples as f64 - settings.min_samples as f64) / range)
+        .max(0.0)
+        .min(1.0);
+    if t < 0.5 {
+        Vec3::new(0.0, 2.0 * t, 1.0 - 2.0 * t)
+    } else {
+        Vec3::new(2.0 * t - 1.0, 2.0 - 2.0 * t, 0.0)
+    }
+}
+
+/// Computes the luminance of a linear RGB color (using the Rec. 709 weights)
+fn luminance(color: &Vec3) -> f64 {
+    0.2126 * color.r() + 0.7152 * color.g() + 0.0722 * color.b()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(value: f64) -> Vec3 {
+        Vec3::new(value, value, value)
+    }
+
+    #[test]
+    fn welford_matches_direct_computation() {
+        let values = [0.3, 1.7, 0.0, 4.2, 2.5, 0.9, 3.3];
+        let mut variance = PixelVariance::new();
+        for &value in values.iter() {
+            variance.add(&gray(value));
+        }
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let sample_variance =
+            values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.0);
+        assert_eq!(variance.num_samples, values.len());
+        assert!((variance.mean - mean).abs() < 1e-12);
+        assert!((variance.variance() - sample_variance).abs() < 1e-12);
+        assert!((variance.relative_error() - (sample_variance / n).sqrt() / mean).abs() < 1e-12);
+    }
+
+    #[test]
+    fn luminance_uses_rec_709_weights() {
+        let mut variance = PixelVariance::new();
+        variance.add(&Vec3::new(1.0, 0.0, 0.0));
+        variance.add(&Vec3::new(0.0, 1.0, 0.0));
+        variance.add(&Vec3::new(0.0, 0.0, 1.0));
+        assert!((variance.mean - 1.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn too_few_samples_have_unknown_error() {
+        let mut variance = PixelVariance::default();
+        assert_eq!(variance.variance(), 0.0);
+        assert!(variance.relative_error().is_infinite());
+        variance.add(&gray(0.5));
+        assert!(variance.relative_error().is_infinite());
+    }
+
+    #[test]
+    fn takes_minimum_samples_first() {
+        let settings = AdaptiveSampling::new(16, 64, 8, 0.05);
+        let mut variance = PixelVariance::new();
+        assert_eq!(settings.next_batch(&variance), 16);
+        for _ in 0..5 {
+            variance.add(&gray(1.0));
+        }
+        assert_eq!(settings.next_batch(&variance), 11);
+    }
+
+    #[test]
+    fn stops_once_converged() {
+        let settings = AdaptiveSampling::new(16, 64, 8, 0.05);
+        let mut variance = PixelVariance::new();
+        for _ in 0..16 {
+            variance.add(&gray(0.5));
+        }
+        // A pixel whose samples are all equal has no error
+        assert_eq!(settings.next_batch(&variance), 0);
+    }
+
+    #[test]
+    fn continues_while_noisy_up_to_maximum() {
+        let settings = AdaptiveSampling::new(16, 60, 8, 0.05);
+        let mut variance = PixelVariance::new();
+        let mut batches = vec![];
+        loop {
+            let batch_size = settings.next_batch(&variance);
+            if batch_size == 0 {
+                break;
+            }
+            batches.push(batch_size);
+            for _ in 0..batch_size {
+                let value = if variance.num_samples % 2 == 0 {
+                    0.0
+                } else {
+                    10.0
+                };
+                variance.add(&gray(value));
+            }
+        }
+        // Noisy pixels take batches until the last, partial, batch reaches the maximum
+        assert_eq!(batches, vec![16, 8, 8, 8, 8, 8, 4]);
+        assert_eq!(variance.num_samples, 60);
+    }
+
+    #[test]
+    fn fixed_sampling_takes_exactly_the_given_samples() {
+        let settings = AdaptiveSampling::fixed(32);
+        let mut variance = PixelVariance::new();
+        assert_eq!(settings.next_batch(&variance), 32);
+        for i in 0..32 {
+            variance.add(&gray(i as f64));
+        }
+        assert_eq!(settings.next_batch(&variance), 0);
+    }
+
+    #[test]
+    fn minimum_samples_are_clamped() {
+        let settings = AdaptiveSampling::new(0, 8, 0, 0.1);
+        assert_eq!(settings.min_samples, 2);
+        assert_eq!(settings.batch_size, 1);
+        assert_eq!(AdaptiveSampling::new(100, 8, 4, 0.1).min_samples, 8);
+    }
+}
@@ -0,0 +1,254 @@
+use film::exr::{self, ExrChannel};
+use hitable::hit_record::HitRecord;
+use material::material::Material;
+use std::collections::HashMap;
+use std::f64::INFINITY;
+use std::io;
+use std::path::Path;
+use vec3::Vec3;
+
+/// The Arbitrary Output Variables (AOVs) produced by a single camera sample.
+///
+/// The geometric values are gathered from the first intersection of the camera ray,
+/// while the lighting values decompose the full path's contribution by bounce.
+#[derive(Copy, Clone, Debug)]
+pub struct AovSample {
+    /// Whether the camera ray hit anything at all
+    pub hit: bool,
+    /// The ray parameter `t` of the first intersection
+    pub depth: f64,
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub uv: (f64, f64),
+    pub albedo: Vec3,
+    /// A key identifying the material of the first intersection (0 if there is none)
+    pub material_key: usize,
+    /// The ID of the tagged object containing the first intersection (0 if untagged)
+    pub object_id: usize,
+    /// Light emitted directly by the first intersected surface
+    pub emission: Vec3,
+    /// Light reaching the camera after exactly one bounce
+    pub direct: Vec3,
+    /// Light reaching the camera after two or more bounces
+    pub indirect: Vec3,
+}
+
+impl AovSample {
+    pub fn new() -> Self {
+        AovSample {
+            hit: false,
+            depth: INFINITY,
+            position: Vec3::new(0.0, 0.0, 0.0),
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            uv: (0.0, 0.0),
+            albedo: Vec3::new(0.0, 0.0, 0.0),
+            material_key: 0,
+            object_id: 0,
+            emission: Vec3::new(0.0, 0.0, 0.0),
+            direct: Vec3::new(0.0, 0.0, 0.0),
+            indirect: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Records the geometric values of the camera ray's first intersection
+    pub fn record_hit(&mut self, rec: &HitRecord) {
+        self.hit = true;
+        self.depth = rec.t;
+        self.position = rec.hit_point;
+        self.normal = rec.normal;
+        self.uv = (rec.u, rec.v);
+        self.object_id = rec.object_id;
+        if let Some(ref mat) = rec.material {
+            self.albedo = mat.albedo(rec);
+            // The address of the shared material uniquely identifies it for the
+            // duration of the render; it is remapped to a compact ID on output.
+            self.material_key = &**mat as *const Material as *const u8 as usize;
+        }
+    }
+
+    /// Adds light reaching the camera to the lighting value of the path's bounce count
+    /// #### Arguments:
+    /// - `bounces`: The number of times the path bounced before the light was emitted
+    /// - `contribution`: The light reaching the camera
+    pub fn record_light(&mut self, bounces: i32, contribution: Vec3) {
+        match bounces {
+            0 => self.emission += contribution,
+            1 => self.direct += contribution,
+            _ => self.indirect += contribution,
+        }
+    }
+
+    /// Scales the lighting values by the camera's exposure
+    pub fn scale_lighting(&mut self, exposure: f64) {
+        self.emission = exposure * self.emission;
+        self.direct = exposure * self.direct;
+        self.indirect = exposure * self.indirect;
+    }
+}
+
+/// The accumulated AOVs of a single pixel
+#[derive(Copy, Clone, Debug)]
+pub struct AovPixel {
+    pub num_samples: u32,
+    pub num_hits: u32,
+    /// The depth of the closest first intersection across all samples
+    pub depth: f64,
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub uv: (f64, f64),
+    pub albedo: Vec3,
+    pub material_key: usize,
+    pub object_id: usize,
+    pub emission: Vec3,
+    pub direct: Vec3,
+    pub indirect: Vec3,
+}
+
+impl AovPixel {
+    pub fn new() -> Self {
+        AovPixel {
+            num_samples: 0,
+            num_hits: 0,
+            depth: INFINITY,
+            position: Vec3::new(0.0, 0.0, 0.0),
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            uv: (0.0, 0.0),
+            albedo: Vec3::new(0.0, 0.0, 0.0),
+            material_key: 0,
+            object_id: 0,
+            emission: Vec3::new(0.0, 0.0, 0.0),
+            direct: Vec3::new(0.0, 0.0, 0.0),
+            indirect: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Adds a single sample to the pixel.
+    ///
+    /// Continuous values are summed (and averaged on output), whereas the IDs
+    /// are taken from the first sample to hit anything, as blending IDs is meaningless.
+    pub fn add(&mut self, sample: &AovSample) {
+        self.num_samples += 1;
+        self.emission += sample.emission;
+        self.direct += sample.direct;
+        self.indirect += sample.indirect;
+        if !sample.hit {
+            return;
+        }
+        if self.num_hits == 0 {
+            self.material_key = sample.material_key;
+            self.object_id = sample.object_id;
+        }
+        self.num_hits += 1;
+        self.depth = self.depth.min(sample.depth);
+        self.position += sample.position;
+        self.normal += sample.normal;
+        self.uv = (self.uv.0 + sample.uv.0, self.uv.1 + sample.uv.1);
+        self.albedo += sample.albedo;
+    }
+
+    /// Merges the samples accumulated in `other` into this pixel
+    pub fn merge(&mut self, other: &AovPixel) {
+        self.num_samples += other.num_samples;
+        self.emission += other.emission;
+        self.direct += other.direct;
+        self.indirect += other.indirect;
+        if other.num_hits == 0 {
+            return;
+        }
+        if self.num_hits == 0 {
+            self.material_key = other.material_key;
+            self.object_id = other.object_id;
+        }
+        self.num_hits += other.num_hits;
+        self.depth = self.depth.min(other.depth);
+        self.position += other.position;
+        self.normal += other.normal;
+        self.uv = (self.uv.0 + other.uv.0, self.uv.1 + other.uv.1);
+        self.albedo += other.albedo;
+    }
+}
+
+/// Stores the AOVs of every pixel in an image
+pub struct AovBuffer {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<AovPixel>,
+}
+
+impl AovBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        AovBuffer {
+            width,
+            height,
+            pixels: vec![AovPixel::new(); width as usize * height as usize],
+        }
+    }
+
+    /// Stores the accumulated AOVs of the pixel at the image coordinates (`x`, `y`),
+    /// where `y` increases downwards
+    pub fn put_pixel(&mut self, x: u32, y: u32, pixel: AovPixel) {
+        self.pixels[y as usize * self.width as usize + x as usize] = pixel;
+    }
+
+    /// Writes every pass to `path` as the layers of a multi-layer OpenEXR file.
+    ///
+    /// Material keys are remapped to compact IDs (starting from 1) in the order in
+    /// which the materials are first encountered, scanning from the top of the image.
+    pub fn write_exr(&self, path: &Path) -> io::Result<()> {
+        let mut material_ids: HashMap<usize, usize> = HashMap::new();
+        let layers: Vec<(&str, Vec<&str>)> = vec![
+            ("depth", vec!["Z"]),
+            ("position", vec!["X", "Y", "Z"]),
+            ("normal", vec!["X", "Y", "Z"]),
+            ("uv", vec!["U", "V"]),
+            ("albedo", vec!["R", "G", "B"]),
+            ("material_id", vec!["ID"]),
+            ("object_id", vec!["ID"]),
+            ("emission", vec!["R", "G", "B"]),
+            ("direct", vec!["R", "G", "B"]),
+            ("indirect", vec!["R", "G", "B"]),
+        ];
+        let mut channels: Vec<ExrChannel> = vec![];
+        for &(layer, ref names) in layers.iter() {
+            for (idx, name) in names.iter().enumerate() {
+                let data = self
+                    .pixels
+                    .iter()
+                    .map(|pixel| {
+                        let samples = pixel.num_samples.max(1) as f64;
+                        let hits = pixel.num_hits.max(1) as f64;
+                        let value = match layer {
+                            "depth" => pixel.depth,
+                            "position" => pixel.position[idx] / hits,
+                            "normal" => pixel.normal[idx] / hits,
+                            "uv" => {
+                                if idx == 0 {
+                                    pixel.uv.0 / hits
+                                } else {
+                                    pixel.uv.1 / hits
+                                }
+                            }
+                            "albedo" => pixel.albedo[idx] / hits,
+                            "material_id" => {
+                                if pixel.material_key == 0 {
+                                    0.0
+                                } else {
+                                    let next_id = material_ids.len() + 1;
+                                    *material_ids.entry(pixel.material_key).or_insert(next_id)
+                                        as f64
+                                }
+                            }
+                            "object_id" => pixel.object_id as f64,
+                            "emission" => pixel.emission[idx] / samples,
+                            "direct" => pixel.direct[idx] / samples,
+                            _ => pixel.indirect[idx] / samples,
+                        };
+                        value as f32
+                    })
+                    .collect();
+                channels.push(ExrChannel::new(&format!("{}.{}", layer, name), data));
+            }
+        }
+        exr::write_exr(path, self.width, self.height, &mut channels)
+    }
+}
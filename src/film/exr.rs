@@ -0,0 +1,222 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// A single named channel of 32-bit float pixel data, stored row by row from the top
+/// of the image downwards.
+///
+/// Channel names follow the OpenEXR layer convention of `layer.channel`
+/// (e.g. `normal.X`), which compositing packages present as separate layers.
+pub struct ExrChannel {
+    pub name: String,
+    pub data: Vec<f32>,
+}
+
+impl ExrChannel {
+    pub fn new(name: &str, data: Vec<f32>) -> Self {
+        ExrChannel {
+            name: name.to_string(),
+            data,
+        }
+    }
+}
+
+/// Writes the given channels to an uncompressed, single-part, scanline OpenEXR file.
+/// #### Arguments:
+/// - `path`: The location to which the file will be written
+/// - `width`: The width of the image in pixels
+/// - `height`: The height of the image in pixels
+/// - `channels`: The channels to write; each must contain exactly `width * height` values
+pub fn write_exr(
+    path: &Path,
+    width: u32,
+    height: u32,
+    channels: &mut [ExrChannel],
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_exr_to(&mut writer, width, height, channels)?;
+    writer.flush()
+}
+
+/// Writes the given channels as an uncompressed, single-part, scanline OpenEXR image
+/// to any writer (see `write_exr`)
+pub fn write_exr_to<W: Write>(
+    writer: &mut W,
+    width: u32,
+    height: u32,
+    channels: &mut [ExrChannel],
+) -> io::Result<()> {
+    let num_pixels = width as usize * height as usize;
+    if channels.iter().any(|c| c.data.len() != num_pixels) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "EXR channel size does not match the image dimensions",
+        ));
+    }
+    // The EXR specification requires channels to be stored in alphabetical order
+    channels.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut header: Vec<u8> = vec![];
+    // Magic number, followed by the version field (2 = single-part scanline)
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]);
+    header.extend_from_slice(&2i32.to_le_bytes());
+
+    let mut channel_list: Vec<u8> = vec![];
+    for channel in channels.iter() {
+        channel_list.extend_from_slice(channel.name.as_bytes());
+        channel_list.push(0);
+        channel_list.extend_from_slice(&2i32.to_le_bytes()); // pixel type: FLOAT
+        channel_list.extend_from_slice(&[0, 0, 0, 0]); // pLinear + reserved
+        channel_list.extend_from_slice(&1i32.to_le_bytes()); // x sampling
+        channel_list.extend_from_slice(&1i32.to_le_bytes()); // y sampling
+    }
+    channel_list.push(0);
+    write_attribute(&mut header, "channels", "chlist", &channel_list);
+    write_attribute(&mut header, "compression", "compression", &[0]);
+
+    let mut window: Vec<u8> = vec![];
+    for value in &[0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&value.to_le_bytes());
+    }
+    write_attribute(&mut header, "dataWindow", "box2i", &window);
+    write_attribute(&mut header, "displayWindow", "box2i", &window);
+    write_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    write_attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    let mut window_center: Vec<u8> = vec![];
+    window_center.extend_from_slice(&0f32.to_le_bytes());
+    window_center.extend_from_slice(&0f32.to_le_bytes());
+    write_attribute(&mut header, "screenWindowCenter", "v2f", &window_center);
+    write_attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    // Without compression, every scanline is stored in its own block
+    let line_size = 4 * width as usize * channels.len();
+    let block_size = 8 + line_size;
+    let table_end = header.len() + 8 * height as usize;
+
+    writer.write_all(&header)?;
+    for y in 0..height as usize {
+        writer.write_all(&((table_end + y * block_size) as u64).to_le_bytes())?;
+    }
+    for y in 0..height as usize {
+        writer.write_all(&(y as i32).to_le_bytes())?;
+        writer.write_all(&(line_size as i32).to_le_bytes())?;
+        for channel in channels.iter() {
+            let row = &channel.data[y * width as usize..(y + 1) * width as usize];
+            for value in row {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Appends a single header attribute
+fn write_attribute(header: &mut Vec<u8>, name: &str, attr_type: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(attr_type.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from(bytes[offset])
+            | u32::from(bytes[offset + 1]) << 8
+            | u32::from(bytes[offset + 2]) << 16
+            | u32::from(bytes[offset + 3]) << 24
+    }
+
+    fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+        u64::from(read_u32(bytes, offset)) | u64::from(read_u32(bytes, offset + 4)) << 32
+    }
+
+    /// Finds the end of the header, which is terminated by an empty attribute name
+    fn header_end(bytes: &[u8]) -> usize {
+        let mut offset = 8;
+        while bytes[offset] != 0 {
+            // Skip the attribute's name and type, then its sized value
+            for _ in 0..2 {
+                offset += bytes[offset..].iter().position(|&b| b == 0).unwrap() + 1;
+            }
+            offset += 4 + read_u32(bytes, offset) as usize;
+        }
+        offset + 1
+    }
+
+    #[test]
+    fn writes_multi_channel_image() {
+        let (width, height) = (3, 2);
+        let mut channels = vec![
+            ExrChannel::new("normal.Y", (0..6).map(|i| 10.0 + i as f32).collect()),
+            ExrChannel::new("depth.Z", (0..6).map(|i| 20.0 + i as f32).collect()),
+            ExrChannel::new("normal.X", (0..6).map(|i| 30.0 + i as f32).collect()),
+        ];
+        let mut bytes = vec![];
+        write_exr_to(&mut bytes, width, height, &mut channels).unwrap();
+
+        // The magic number, and version 2 without any flags
+        assert_eq!(&bytes[0..4], &[0x76, 0x2f, 0x31, 0x01]);
+        assert_eq!(read_u32(&bytes, 4), 2);
+
+        // The channel list is stored alphabetically
+        let names: Vec<&str> = channels.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["depth.Z", "normal.X", "normal.Y"]);
+        let channel_list = b"channels\0chlist\0";
+        let list_start = bytes
+            .windows(channel_list.len())
+            .position(|w| w == channel_list)
+            .unwrap()
+            + channel_list.len()
+            + 4;
+        assert!(bytes[list_start..].starts_with(b"depth.Z\0"));
+        assert!(bytes[list_start + 8 + 16..].starts_with(b"normal.X\0"));
+        assert!(bytes[list_start + 2 * (8 + 16) + 1..].starts_with(b"normal.Y\0"));
+
+        // The offset table points at each scanline block in turn
+        let table_start = header_end(&bytes);
+        let line_size = 4 * 3 * 3;
+        let first_block = table_start + 8 * height as usize;
+        for y in 0..height as usize {
+            assert_eq!(
+                read_u64(&bytes, table_start + 8 * y) as usize,
+                first_block + y * (8 + line_size)
+            );
+        }
+        assert_eq!(bytes.len(), first_block + 2 * (8 + line_size));
+
+        // Each block holds its scanline's channels in turn, in alphabetical order
+        for y in 0..height as usize {
+            let block = first_block + y * (8 + line_size);
+            assert_eq!(read_u32(&bytes, block) as usize, y);
+            assert_eq!(read_u32(&bytes, block + 4) as usize, line_size);
+            for (c, base) in [20.0, 30.0, 10.0].iter().enumerate() {
+                for x in 0..width as usize {
+                    let value = f32::from_bits(read_u32(&bytes, block + 8 + 4 * (3 * c + x)));
+                    assert_eq!(value, base + (3 * y + x) as f32);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_mismatched_channel_sizes() {
+        let mut channels = vec![ExrChannel::new("Y", vec![0.0; 5])];
+        assert!(write_exr_to(&mut vec![], 3, 2, &mut channels).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+use film::filter::Filter;
+use std::sync::Arc;
+use vec3::Vec3;
+
+/// A single radiance sample, positioned in continuous raster coordinates
+/// (i.e. pixel (x, y) covers the area [x, x + 1) x [y, y + 1))
+#[derive(Copy, Clone, Debug)]
+pub struct FilmSample {
+    pub x: f64,
+    pub y: f64,
+    pub color: Vec3,
+}
+
+/// The filter-weighted sum of every sample contributing to a pixel
+#[derive(Copy, Clone, Debug)]
+struct FilmPixel {
+    color_sum: Vec3,
+    weight_sum: f64,
+}
+
+/// Accumulates radiance samples into pixels, splatting each sample into all of
+/// the pixels whose centers lie within the reconstruction filter's radius.
+pub struct Film {
+    pub width: u32,
+    pub height: u32,
+    filter: Arc<Filter>,
+    pixels: Vec<FilmPixel>,
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32, filter: Arc<Filter>) -> Self {
+        Film {
+            width,
+            height,
+            filter,
+            pixels: vec![
+                FilmPixel {
+                    color_sum: Vec3::new(0.0, 0.0, 0.0),
+                    weight_sum: 0.0,
+                };
+                width as usize * height as usize
+            ],
+        }
+    }
+
+    /// Adds a sample to every pixel within the filter's radius
+    pub fn add_sample(&mut self, sample: &FilmSample) {
+        let radius = self.filter.radius();
+        // Pixel centers lie at half-integer coordinates
+        let x_0 = (sample.x - 0.5 - radius).ceil().max(0.0) as i64;
+        let x_1 = (sample.x - 0.5 + radius)
+            .floor()
+            .min(self.width as f64 - 1.0) as i64;
+        let y_0 = (sample.y - 0.5 - radius).ceil().max(0.0) as i64;
+        let y_1 = (sample.y - 0.5 + radius)
+            .floor()
+            .min(self.height as f64 - 1.0) as i64;
+        for y in y_0..=y_1 {
+            for x in x_0..=x_1 {
+                let weight = self
+                    .filter
+                    .evaluate(sample.x - (x as f64 + 0.5), sample.y - (y as f64 + 0.5));
+                if weight == 0.0 {
+                    continue;
+                }
+                let pixel = &mut self.pixels[(y as u32 * self.width + x as u32) as usize];
+                pixel.color_sum += weight * sample.color;
+                pixel.weight_sum += weight;
+            }
+        }
+    }
+
+    /// Calculates the reconstructed color of the pixel at (`x`, `y`).
+    ///
+    /// Filters with negative lobes can produce negative values, which are clamped to zero.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Vec3 {
+        let pixel = &self.pixels[(y * self.width + x) as usize];
+        if pixel.weight_sum == 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let color = pixel.color_sum / pixel.weight_sum;
+        Vec3::new(color.r().max(0.0), color.g().max(0.0), color.b().max(0.0))
+    }
+}
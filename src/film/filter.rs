@@ -0,0 +1,14 @@
+use std::marker::{Send, Sync};
+
+/// A pixel reconstruction filter, used to weight the contribution of a
+/// sample to the pixels surrounding it.
+pub trait Filter: Send + Sync {
+    /// The distance from the filter's center beyond which its weight is zero,
+    /// along both the X and Y axes (in pixels)
+    fn radius(&self) -> f64;
+    /// Calculates the weight of a sample at the given offset from a pixel's center.
+    /// #### Arguments
+    /// - `x`: The horizontal offset of the sample (in pixels)
+    /// - `y`: The vertical offset of the sample (in pixels)
+    fn evaluate(&self, x: f64, y: f64) -> f64;
+}
@@ -0,0 +1,179 @@
+use film::filter::Filter;
+use std::f64::consts::PI;
+
+/// A filter that weights every sample within its radius equally.
+///
+/// A radius of 0.5 restricts each sample to the pixel in which it was taken.
+#[derive(Copy, Clone, Debug)]
+pub struct BoxFilter {
+    pub radius: f64,
+}
+
+impl BoxFilter {
+    pub fn new(radius: f64) -> Self {
+        BoxFilter { radius }
+    }
+}
+
+impl Filter for BoxFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        if x.abs() <= self.radius && y.abs() <= self.radius {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A filter whose weight falls off linearly from its center (i.e. a triangle filter)
+#[derive(Copy, Clone, Debug)]
+pub struct TentFilter {
+    pub radius: f64,
+}
+
+impl TentFilter {
+    pub fn new(radius: f64) -> Self {
+        TentFilter { radius }
+    }
+}
+
+impl Filter for TentFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        (self.radius - x.abs()).max(0.0) * (self.radius - y.abs()).max(0.0)
+    }
+}
+
+/// A Gaussian filter, offset so that it falls to zero at its radius
+#[derive(Copy, Clone, Debug)]
+pub struct GaussianFilter {
+    pub radius: f64,
+    /// Controls the rate of the filter's falloff; larger values give a sharper result
+    pub alpha: f64,
+    /// The value of the (un-offset) Gaussian at the filter's radius
+    edge_value: f64,
+}
+
+impl GaussianFilter {
+    pub fn new(radius: f64, alpha: f64) -> Self {
+        GaussianFilter {
+            radius,
+            alpha,
+            edge_value: (-alpha * radius * radius).exp(),
+        }
+    }
+
+    fn gaussian(&self, d: f64) -> f64 {
+        ((-self.alpha * d * d).exp() - self.edge_value).max(0.0)
+    }
+}
+
+impl Filter for GaussianFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.gaussian(x) * self.gaussian(y)
+    }
+}
+
+/// The Mitchell-Netravali filter, a family of cubic filters that trade off between
+/// blurring (B) and ringing (C).
+///
+/// Source: Mitchell and Netravali, `Reconstruction Filters in Computer Graphics`
+#[derive(Copy, Clone, Debug)]
+pub struct MitchellFilter {
+    pub radius: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl MitchellFilter {
+    /// Constructs a new MitchellFilter
+    /// #### Arguments:
+    /// - `radius`: The radius of the filter (in pixels)
+    /// - `b`, `c`: The filter's parameters; B = C = 1/3 is the commonly recommended choice
+    pub fn new(radius: f64, b: f64, c: f64) -> Self {
+        MitchellFilter { radius, b, c }
+    }
+
+    /// Evaluates the filter along one axis, where `x` has been scaled to [-2, 2]
+    fn mitchell(&self, x: f64) -> f64 {
+        let (b, c) = (self.b, self.c);
+        let x = x.abs();
+        if x > 2.0 {
+            0.0
+        } else if x > 1.0 {
+            ((-b - 6.0 * c) * x * x * x
+                + (6.0 * b + 30.0 * c) * x * x
+                + (-12.0 * b - 48.0 * c) * x
+                + (8.0 * b + 24.0 * c))
+                / 6.0
+        } else {
+            ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                + (6.0 - 2.0 * b))
+                / 6.0
+        }
+    }
+}
+
+impl Filter for MitchellFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.mitchell(2.0 * x / self.radius) * self.mitchell(2.0 * y / self.radius)
+    }
+}
+
+/// A sinc filter windowed by a wider Lanczos (sinc) lobe
+#[derive(Copy, Clone, Debug)]
+pub struct LanczosFilter {
+    pub radius: f64,
+    /// The number of sinc cycles that pass before the window reaches zero
+    pub tau: f64,
+}
+
+impl LanczosFilter {
+    pub fn new(radius: f64, tau: f64) -> Self {
+        LanczosFilter { radius, tau }
+    }
+
+    fn windowed_sinc(&self, x: f64) -> f64 {
+        if x.abs() > self.radius {
+            0.0
+        } else {
+            sinc(x) * sinc(x / self.tau)
+        }
+    }
+}
+
+impl Filter for LanczosFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.windowed_sinc(x) * self.windowed_sinc(y)
+    }
+}
+
+/// The normalized sinc function (i.e. sin(PI * x) / (PI * x))
+fn sinc(x: f64) -> f64 {
+    let x = x.abs();
+    if x < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
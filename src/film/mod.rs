@@ -0,0 +1,6 @@
+pub mod adaptive;
+pub mod aov;
+pub mod exr;
+pub mod film;
+pub mod filter;
+pub mod filters;
@@ -0,0 +1,188 @@
+use animation::track::Track;
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::MAX as FLOAT_MAX;
+use std::f64::MIN as FLOAT_MIN;
+use std::sync::Arc;
+use vec3::{unit_vector, Vec3};
+
+/// Wrapper struct that wraps a Hitable and scales, rotates and translates it (in that order)
+/// by keyframed amounts. The transform is evaluated at the time of each ray, so the motion
+/// of the Hitable is blurred while the camera shutter is open.
+#[derive(Clone)]
+pub struct AnimatedTransform {
+    pub hitable: Arc<Hitable>,
+    pub translation: Track<Vec3>,
+    /// The rotations (in degrees) about the X, Y and Z axes, applied in that order
+    pub rotation: Track<Vec3>,
+    pub scale: Track<Vec3>,
+}
+
+impl AnimatedTransform {
+    /// Arguments:
+    /// - `hitable`: The Hitable to be transformed
+    /// - `translation`: The offset of the Hitable over time
+    /// - `rotation`: The rotation (in degrees) of the Hitable about each axis over time
+    /// - `scale`: The scale of the Hitable along each axis over time
+    pub fn new(
+        hitable: Arc<Hitable>,
+        translation: Track<Vec3>,
+        rotation: Track<Vec3>,
+        scale: Track<Vec3>,
+    ) -> Self {
+        AnimatedTransform {
+            hitable,
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    /// Creates a transform which only moves the Hitable
+    pub fn translated(hitable: Arc<Hitable>, translation: Track<Vec3>) -> Self {
+        AnimatedTransform::new(
+            hitable,
+            translation,
+            Track::constant(Vec3::new(0.0, 0.0, 0.0)),
+            Track::constant(Vec3::new(1.0, 1.0, 1.0)),
+        )
+    }
+
+    /// Evaluates the translation, rotation (in radians) and scale at the given time
+    fn evaluate(&self, time: f64) -> (Vec3, Vec3, Vec3) {
+        let rotation = self.rotation.evaluate(time);
+        (
+            self.translation.evaluate(time),
+            Vec3::new(
+                rotation.x().to_radians(),
+                rotation.y().to_radians(),
+                rotation.z().to_radians(),
+            ),
+            self.scale.evaluate(time),
+        )
+    }
+}
+
+/// Rotates `v` about the X, Y and Z axes (in that order) by the given angles (in radians)
+fn rotate(v: Vec3, angles: &Vec3) -> Vec3 {
+    let v = rotate_about_axis(v, 0, angles.x());
+    let v = rotate_about_axis(v, 1, angles.y());
+    rotate_about_axis(v, 2, angles.z())
+}
+
+/// Undoes the rotation performed by `rotate`
+fn inverse_rotate(v: Vec3, angles: &Vec3) -> Vec3 {
+    let v = rotate_about_axis(v, 2, -angles.z());
+    let v = rotate_about_axis(v, 1, -angles.y());
+    rotate_about_axis(v, 0, -angles.x())
+}
+
+/// Rotates `v` counter-clockwise about a single axis (0 = X, 1 = Y, 2 = Z)
+fn rotate_about_axis(v: Vec3, axis: usize, theta: f64) -> Vec3 {
+    let (sin_theta, cos_theta) = theta.sin_cos();
+    let (i, j) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut rotated = v;
+    rotated[i] = cos_theta * v[i] - sin_theta * v[j];
+    rotated[j] = sin_theta * v[i] + cos_theta * v[j];
+    rotated
+}
+
+impl Hitable for AnimatedTransform {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let (translation, rotation, scale) = self.evaluate(ray.time);
+        // Transform the ray into the Hitable's local space. As the transform is affine,
+        // the ray parameter `t` of any intersection is unchanged.
+        let origin = inverse_rotate(ray.origin - translation, &rotation) / scale;
+        let direction = inverse_rotate(ray.direction, &rotation) / scale;
+        let local_ray = Ray::new(origin, direction, ray.time);
+        if self.hitable.hit(&local_ray, t_min, t_max, rec, sampler) {
+            rec.hit_point = rotate(scale * rec.hit_point, &rotation) + translation;
+            // Normals are transformed by the inverse transpose of the transform
+            rec.normal = unit_vector(rotate(rec.normal / scale, &rotation));
+            // Tangents are directions along the surface, so are transformed by the transform itself
+            if rec.tangent.length() > 0.0 {
+                rec.tangent = unit_vector(rotate(scale * rec.tangent, &rotation));
+            }
+            return true;
+        }
+        false
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        let local_box = self.hitable.bounding_box(start_time, end_time)?;
+        // Bound the scaled box, over every scale the tracks can reach in the interval
+        let (min_scale, max_scale) = bounds(&self.scale.hull_values(start_time, end_time));
+        let mut min_bound = Vec3::new(FLOAT_MAX, FLOAT_MAX, FLOAT_MAX);
+        let mut max_bound = Vec3::new(FLOAT_MIN, FLOAT_MIN, FLOAT_MIN);
+        for axis in 0..3 {
+            for &scale in &[min_scale[axis], max_scale[axis]] {
+                for bound in local_box.bounds.iter() {
+                    min_bound[axis] = min_bound[axis].min(scale * bound[axis]);
+                    max_bound[axis] = max_bound[axis].max(scale * bound[axis]);
+                }
+            }
+        }
+
+        // Rotate the scaled box. If the rotation changes during the interval, the box is
+        // bounded by the sphere about the pivot which contains it in every orientation.
+        let (min_rotation, max_rotation) = bounds(&self.rotation.hull_values(start_time, end_time));
+        let scaled_bounds = [min_bound, max_bound];
+        let corners: Vec<Vec3> = (0..8)
+            .map(|corner| {
+                Vec3::new(
+                    scaled_bounds[corner & 1].x(),
+                    scaled_bounds[(corner >> 1) & 1].y(),
+                    scaled_bounds[(corner >> 2) & 1].z(),
+                )
+            })
+            .collect();
+        let (min_bound, max_bound) = if (max_rotation - min_rotation).length() == 0.0 {
+            let angles = Vec3::new(
+                min_rotation.x().to_radians(),
+                min_rotation.y().to_radians(),
+                min_rotation.z().to_radians(),
+            );
+            let rotated: Vec<Vec3> = corners.iter().map(|&c| rotate(c, &angles)).collect();
+            bounds(&rotated)
+        } else {
+            let radius = corners
+                .iter()
+                .fold(0.0, |radius: f64, c| radius.max(c.length()));
+            (
+                Vec3::new(-radius, -radius, -radius),
+                Vec3::new(radius, radius, radius),
+            )
+        };
+
+        // Sweep the rotated box along the translation
+        let (min_translation, max_translation) =
+            bounds(&self.translation.hull_values(start_time, end_time));
+        Some(AxisAlignedBoundingBox::new(
+            min_bound + min_translation,
+            max_bound + max_translation,
+        ))
+    }
+}
+
+/// Finds the smallest and largest component of some vectors along each axis
+fn bounds(vectors: &[Vec3]) -> (Vec3, Vec3) {
+    let mut min_bound = Vec3::new(FLOAT_MAX, FLOAT_MAX, FLOAT_MAX);
+    let mut max_bound = Vec3::new(FLOAT_MIN, FLOAT_MIN, FLOAT_MIN);
+    for vector in vectors.iter() {
+        for axis in 0..3 {
+            min_bound[axis] = min_bound[axis].min(vector[axis]);
+            max_bound[axis] = max_bound[axis].max(vector[axis]);
+        }
+    }
+    (min_bound, max_bound)
+}
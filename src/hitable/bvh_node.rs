@@ -3,8 +3,9 @@ use bounding_boxes::utils;
 use hitable::hit_record::HitRecord;
 use hitable::hitable::Hitable;
 use hitable::hitable_list::HitableList;
-use rand::Rng;
 use ray::Ray;
+use sampler::rng;
+use sampler::sampler::Sampler;
 use std::sync::Arc;
 
 /// Represents a Bounding Volume Hierarchy
@@ -19,14 +20,15 @@ impl BvhNode {
     /// Creates a new Bounding Volume Hierarchy Node containing the
     /// elements of the provided HitableList
     pub fn new(hitable_list: &mut HitableList, start_time: f64, end_time: f64) -> Self {
-        // Sort the hitable list by a randomly chosen axis
-        let rand_axis = (rand::thread_rng().gen::<f64>() * 3.0) as u8;
+        // Sort the hitable list by a randomly chosen axis, using bounds which
+        // cover the motion of each element over the entire time interval
+        let rand_axis = (rng::scene_random() * 3.0) as u8;
         let sort_ord = |a: &Arc<Hitable>, b: &Arc<Hitable>| {
             let a_box = a
-                .bounding_box(0.0, 0.0)
+                .bounding_box(start_time, end_time)
                 .expect("No bounding box for left child!");
             let b_box = b
-                .bounding_box(0.0, 0.0)
+                .bounding_box(start_time, end_time)
                 .expect("No bounding box for right child!");
             let (a_min_bound, b_min_bound) = match rand_axis {
                 0 => (a_box.min_bound.x(), b_box.min_bound.x()),
@@ -82,14 +84,21 @@ impl BvhNode {
 }
 
 impl Hitable for BvhNode {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
         if !self.bounding_box.hit(ray, t_min, t_max) {
             return false;
         }
         let ref mut left_rec = HitRecord::new();
         let ref mut right_rec = HitRecord::new();
-        let left_hit = self.left.hit(ray, t_min, t_max, left_rec);
-        let right_hit = self.right.hit(ray, t_min, t_max, right_rec);
+        let left_hit = self.left.hit(ray, t_min, t_max, left_rec, sampler);
+        let right_hit = self.right.hit(ray, t_min, t_max, right_rec, sampler);
         return if left_hit && right_hit {
             if left_rec.t < right_rec.t {
                 rec.from(left_rec);
@@ -0,0 +1,319 @@
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use bounding_boxes::utils;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::MAX as FLOAT_MAX;
+use std::sync::Arc;
+use vec3::{dot, Vec3};
+
+/// The maximum number of surfaces of a single operand that are crossed along a ray
+const MAX_CROSSINGS: usize = 64;
+/// The distance a ray is advanced past a surface before searching for the next one
+const CROSSING_EPSILON: f64 = 0.0001;
+
+/// The boolean operation a CSG node applies to the volumes of its operands
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CsgOperation {
+    /// The volume inside either operand
+    Union,
+    /// The volume inside both operands
+    Intersection,
+    /// The volume inside the left operand, but not the right
+    Difference,
+}
+
+impl CsgOperation {
+    /// Whether a point is inside the combined volume, given whether it is inside each operand
+    fn contains(&self, inside_left: bool, inside_right: bool) -> bool {
+        match *self {
+            CsgOperation::Union => inside_left || inside_right,
+            CsgOperation::Intersection => inside_left && inside_right,
+            CsgOperation::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+/// A point at which a ray crosses the surface of an operand
+struct Crossing {
+    rec: HitRecord,
+    /// Whether the ray is entering (rather than leaving) the operand's volume
+    entering: bool,
+    /// Whether the surface belongs to the right operand
+    is_right: bool,
+}
+
+/// Combines the volumes of two Hitables with a boolean operation
+/// (i.e. Constructive Solid Geometry).
+///
+/// Both operands must be closed surfaces whose normals face outwards (as with
+/// spheres, blocks and closed meshes), so that the surfaces crossed along a ray
+/// can be classified as entering or leaving their volume. CSG nodes can be
+/// nested to build more complex shapes.
+#[derive(Clone)]
+pub struct Csg {
+    pub left: Arc<Hitable>,
+    pub right: Arc<Hitable>,
+    pub operation: CsgOperation,
+}
+
+impl Csg {
+    pub fn new(left: Arc<Hitable>, right: Arc<Hitable>, operation: CsgOperation) -> Self {
+        Csg {
+            left,
+            right,
+            operation,
+        }
+    }
+
+    /// The volume inside either Hitable
+    pub fn union(left: Arc<Hitable>, right: Arc<Hitable>) -> Self {
+        Csg::new(left, right, CsgOperation::Union)
+    }
+
+    /// The volume inside both Hitables
+    pub fn intersection(left: Arc<Hitable>, right: Arc<Hitable>) -> Self {
+        Csg::new(left, right, CsgOperation::Intersection)
+    }
+
+    /// The volume inside the `left` Hitable, with the volume of the `right` Hitable cut away
+    pub fn difference(left: Arc<Hitable>, right: Arc<Hitable>) -> Self {
+        Csg::new(left, right, CsgOperation::Difference)
+    }
+}
+
+/// Collects the surfaces of a Hitable crossed along a ray, in order, beginning at `t_min`.
+///
+/// Crossings are collected up to `t_max`, along with the first crossing beyond it
+/// (which determines whether the ray ends inside the Hitable).
+fn collect_crossings(
+    hitable: &Hitable,
+    is_right: bool,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+    sampler: &mut Sampler,
+) -> Vec<Crossing> {
+    let mut crossings = vec![];
+    let mut t = t_min;
+    while crossings.len() < MAX_CROSSINGS {
+        let mut rec = HitRecord::new();
+        // Nothing beyond a non-finite hit can be found, as the search can't advance past it
+        if !hitable.hit(ray, t, FLOAT_MAX, &mut rec, sampler) || !rec.t.is_finite() {
+            break;
+        }
+        t = rec.t + CROSSING_EPSILON;
+        let entering = dot(&rec.normal, &ray.direction) < 0.0;
+        let beyond_range = rec.t > t_max;
+        crossings.push(Crossing {
+            rec,
+            entering,
+            is_right,
+        });
+        if beyond_range {
+            break;
+        }
+    }
+    crossings
+}
+
+impl Hitable for Csg {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let left = collect_crossings(&*self.left, false, ray, t_min, t_max, sampler);
+        if left.is_empty() && self.operation != CsgOperation::Union {
+            return false;
+        }
+        let right = collect_crossings(&*self.right, true, ray, t_min, t_max, sampler);
+
+        // A ray starts inside an operand if the first surface of it that it crosses is an exit
+        let mut inside_left = left.first().map_or(false, |crossing| !crossing.entering);
+        let mut inside_right = right.first().map_or(false, |crossing| !crossing.entering);
+        let mut inside = self.operation.contains(inside_left, inside_right);
+
+        let mut crossings: Vec<Crossing> = left.into_iter().chain(right).collect();
+        crossings.sort_by(|a, b| a.rec.t.total_cmp(&b.rec.t));
+        for mut crossing in crossings {
+            if crossing.rec.t > t_max {
+                break;
+            }
+            if crossing.is_right {
+                inside_right = crossing.entering;
+            } else {
+                inside_left = crossing.entering;
+            }
+            let was_inside = inside;
+            inside = self.operation.contains(inside_left, inside_right);
+            if inside != was_inside {
+                // Surfaces of the subtracted operand bound the result from the outside
+                // of that operand, so their normals are reversed
+                if crossing.is_right && self.operation == CsgOperation::Difference {
+                    crossing.rec.normal = -crossing.rec.normal;
+                }
+                rec.from(&mut crossing.rec);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        let left = self.left.bounding_box(start_time, end_time);
+        let right = self.right.bounding_box(start_time, end_time);
+        match self.operation {
+            CsgOperation::Union => match (left, right) {
+                (Some(left), Some(right)) => Some(utils::calc_surrounding_box(&left, &right)),
+                _ => None,
+            },
+            CsgOperation::Intersection => match (left, right) {
+                (Some(left), Some(right)) => {
+                    let min_bound = Vec3::new(
+                        left.min_bound.x().max(right.min_bound.x()),
+                        left.min_bound.y().max(right.min_bound.y()),
+                        left.min_bound.z().max(right.min_bound.z()),
+                    );
+                    // Operands whose boxes don't overlap have an empty intersection, which is
+                    // given a degenerate box (rather than none, as a BVH requires one)
+                    let max_bound = Vec3::new(
+                        left.max_bound
+                            .x()
+                            .min(right.max_bound.x())
+                            .max(min_bound.x()),
+                        left.max_bound
+                            .y()
+                            .min(right.max_bound.y())
+                            .max(min_bound.y()),
+                        left.max_bound
+                            .z()
+                            .min(right.max_bound.z())
+                            .max(min_bound.z()),
+                    );
+                    Some(AxisAlignedBoundingBox::new(min_bound, max_bound))
+                }
+                (left, None) => left,
+                (None, right) => right,
+            },
+            CsgOperation::Difference => left,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hitable::sphere::Sphere;
+    use material::materials::Lambertian;
+    use sampler::samplers::IndependentSampler;
+    use texture::textures::ConstantTexture;
+
+    fn sphere(x: f64, radius: f64) -> Arc<Hitable> {
+        Arc::new(Sphere {
+            center: Vec3::new(x, 0.0, 0.0),
+            radius,
+            material: Arc::new(Lambertian {
+                albedo: Arc::new(ConstantTexture::new(Vec3::new(0.5, 0.5, 0.5))),
+            }),
+        })
+    }
+
+    /// Finds the parameter and normal of the first surface hit by a ray along +X
+    fn hit_along_x(hitable: &Hitable, start: f64) -> Option<(f64, Vec3)> {
+        let mut sampler = IndependentSampler::new(1, 0);
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Vec3::new(start, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        if hitable.hit(&ray, 0.001, FLOAT_MAX, &mut rec, &mut sampler) {
+            Some((rec.t, rec.normal))
+        } else {
+            None
+        }
+    }
+
+    /// Finds the positions along the X axis of every surface crossed by a ray from `start`
+    fn crossings_along_x(hitable: &Hitable, start: f64) -> Vec<f64> {
+        let mut positions = vec![];
+        let mut t_min = 0.001;
+        let mut sampler = IndependentSampler::new(1, 0);
+        let ray = Ray::new(Vec3::new(start, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::new();
+        while hitable.hit(&ray, t_min, FLOAT_MAX, &mut rec, &mut sampler) {
+            positions.push(start + rec.t);
+            t_min = rec.t + 0.001;
+        }
+        positions
+    }
+
+    fn assert_positions(found: &[f64], expected: &[f64]) {
+        assert_eq!(found.len(), expected.len(), "{:?} != {:?}", found, expected);
+        for (found, expected) in found.iter().zip(expected) {
+            assert!((found - expected).abs() < 1e-6, "{} != {}", found, expected);
+        }
+    }
+
+    // The spheres along the X axis span [-2, 0] and [-1, 1]
+
+    #[test]
+    fn union_hits_outer_surfaces() {
+        let union = Csg::union(sphere(-1.0, 1.0), sphere(0.0, 1.0));
+        assert_positions(&crossings_along_x(&union, -5.0), &[-2.0, 1.0]);
+        let (t, normal) = hit_along_x(&union, -5.0).unwrap();
+        assert!((t - 3.0).abs() < 1e-6);
+        assert!((normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn intersection_hits_shared_volume() {
+        let intersection = Csg::intersection(sphere(-1.0, 1.0), sphere(0.0, 1.0));
+        assert_positions(&crossings_along_x(&intersection, -5.0), &[-1.0, 0.0]);
+        let (_, normal) = hit_along_x(&intersection, -5.0).unwrap();
+        assert!((normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn difference_cuts_away_right_operand() {
+        let difference = Csg::difference(sphere(-1.0, 1.0), sphere(0.0, 1.0));
+        assert_positions(&crossings_along_x(&difference, -5.0), &[-2.0, -1.0]);
+        // The cut surface belongs to the right operand, but faces out of the result
+        let (_, normal) = hit_along_x(&difference, -1.5).unwrap();
+        assert!((normal - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn rays_starting_inside_an_operand() {
+        // Starting inside the left sphere only, then inside both
+        let union = Csg::union(sphere(-1.0, 1.0), sphere(0.0, 1.0));
+        assert_positions(&crossings_along_x(&union, -1.5), &[1.0]);
+        let intersection = Csg::intersection(sphere(-1.0, 1.0), sphere(0.0, 1.0));
+        assert_positions(&crossings_along_x(&intersection, -1.5), &[-1.0, 0.0]);
+        assert_positions(&crossings_along_x(&intersection, -0.5), &[0.0]);
+        let difference = Csg::difference(sphere(-1.0, 1.0), sphere(0.0, 1.0));
+        assert_positions(&crossings_along_x(&difference, -1.5), &[-1.0]);
+        assert!(crossings_along_x(&difference, -0.5).is_empty());
+    }
+
+    #[test]
+    fn nested_operations() {
+        // A sphere spanning [-3, 3], hollowed out by [-2, 0], with [-1.5, -0.5] put back inside
+        let shell = Arc::new(Csg::difference(sphere(0.0, 3.0), sphere(-1.0, 1.0)));
+        let nested = Csg::union(shell, sphere(-1.0, 0.5));
+        assert_positions(
+            &crossings_along_x(&nested, -5.0),
+            &[-3.0, -2.0, -1.5, -0.5, 0.0, 3.0],
+        );
+    }
+
+    #[test]
+    fn disjoint_intersection_has_degenerate_bounding_box() {
+        let intersection = Csg::intersection(sphere(-3.0, 1.0), sphere(3.0, 1.0));
+        assert!(hit_along_x(&intersection, -5.0).is_none());
+        let bounding_box = intersection.bounding_box(0.0, 1.0).unwrap();
+        assert_eq!(bounding_box.min_bound.x(), bounding_box.max_bound.x());
+        assert!(bounding_box.min_bound.y() <= bounding_box.max_bound.y());
+    }
+}
@@ -0,0 +1,380 @@
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use hitable::bvh_node::BvhNode;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use hitable::hitable_list::HitableList;
+use hitable::utils::orthonormal_basis;
+use material::material::Material;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::consts::SQRT_2;
+use std::f64::MAX as FLOAT_MAX;
+use std::f64::MIN as FLOAT_MIN;
+use std::sync::Arc;
+use vec3::{cross, dot, unit_vector, Vec3};
+
+/// The basis in which the control points of a curve are given
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CurveBasis {
+    /// Cubic Bezier segments sharing their end points (i.e. 3n + 1 control points for n segments)
+    Bezier,
+    /// A uniform cubic B-spline (i.e. n + 3 control points for n segments), which is smooth
+    /// across segments but does not pass through its control points
+    BSpline,
+}
+
+/// How the cross-section of a curve is shaded
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CurveType {
+    /// A flat ribbon which always faces the ray (e.g. for blades of grass seen from afar)
+    Flat,
+    /// A round tube, whose normals curve around its width (e.g. for hair and cables)
+    Round,
+}
+
+/// A single cubic Bezier segment of a curve
+#[derive(Clone)]
+pub struct CurveSegment {
+    pub control_points: [Vec3; 4],
+    /// The width of the segment at its start and end
+    pub widths: (f64, f64),
+    /// The range of the strand's `u` texture coordinate covered by the segment
+    pub u_range: (f64, f64),
+    pub curve_type: CurveType,
+    pub material: Arc<Material>,
+}
+
+/// The closest intersection found with a curve segment (in the ray's coordinate system)
+struct CurveHit {
+    /// The point on the curve's center line nearest the ray
+    center: Vec3,
+    /// The derivative of the curve at `center`
+    derivative: Vec3,
+    /// The parameter of `center` along the segment
+    u: f64,
+    width: f64,
+}
+
+/// Evaluates a cubic Bezier curve and its derivative at `u` using de Casteljau's algorithm
+fn eval_bezier(cp: &[Vec3; 4], u: f64) -> (Vec3, Vec3) {
+    let lerp = |a: Vec3, b: Vec3| (1.0 - u) * a + u * b;
+    let cp_1 = [lerp(cp[0], cp[1]), lerp(cp[1], cp[2]), lerp(cp[2], cp[3])];
+    let cp_2 = [lerp(cp_1[0], cp_1[1]), lerp(cp_1[1], cp_1[2])];
+    (lerp(cp_2[0], cp_2[1]), 3.0 * (cp_2[1] - cp_2[0]))
+}
+
+/// Splits a cubic Bezier curve in half, returning the control points of both halves
+/// (which share their middle point)
+fn split_bezier(cp: &[Vec3; 4]) -> [Vec3; 7] {
+    [
+        cp[0],
+        0.5 * (cp[0] + cp[1]),
+        0.25 * (cp[0] + 2.0 * cp[1] + cp[2]),
+        0.125 * (cp[0] + 3.0 * cp[1] + 3.0 * cp[2] + cp[3]),
+        0.25 * (cp[1] + 2.0 * cp[2] + cp[3]),
+        0.5 * (cp[2] + cp[3]),
+        cp[3],
+    ]
+}
+
+impl CurveSegment {
+    /// The width of the segment at a parameter `u` along it
+    fn width_at(&self, u: f64) -> f64 {
+        (1.0 - u) * self.widths.0 + u * self.widths.1
+    }
+
+    /// Recursively splits the segment (given in the ray's coordinate system, in which the
+    /// ray starts at the origin and runs along +Z) until it is nearly straight, and then
+    /// intersects the ray with the flat, ray-facing ribbon swept by each straight piece
+    /// (Source: Nakamaru and Ohno, `Ray Tracing for Curves Primitive`, as described in
+    /// Pharr et al., `Physically Based Rendering`)
+    fn recursive_intersect(
+        &self,
+        cp: &[Vec3; 4],
+        u_0: f64,
+        u_1: f64,
+        depth: usize,
+        z_min: f64,
+        z_max: &mut f64,
+        hit: &mut Option<CurveHit>,
+    ) {
+        // Reject pieces whose (width-expanded) bounds do not contain the ray
+        let half_width = 0.5 * self.width_at(u_0).max(self.width_at(u_1));
+        for axis in 0..3 {
+            let min = cp.iter().fold(FLOAT_MAX, |min, p| min.min(p[axis])) - half_width;
+            let max = cp.iter().fold(FLOAT_MIN, |max, p| max.max(p[axis])) + half_width;
+            let (low, high) = if axis == 2 {
+                (z_min, *z_max)
+            } else {
+                (0.0, 0.0)
+            };
+            if max < low || min > high {
+                return;
+            }
+        }
+
+        if depth > 0 {
+            let split = split_bezier(cp);
+            let u_mid = 0.5 * (u_0 + u_1);
+            let first = [split[0], split[1], split[2], split[3]];
+            let second = [split[3], split[4], split[5], split[6]];
+            self.recursive_intersect(&first, u_0, u_mid, depth - 1, z_min, z_max, hit);
+            self.recursive_intersect(&second, u_mid, u_1, depth - 1, z_min, z_max, hit);
+            return;
+        }
+
+        // Test the ray against the lines perpendicular to the piece's tangents at its ends
+        let edge = (cp[1].y() - cp[0].y()) * -cp[0].y() + cp[0].x() * (cp[0].x() - cp[1].x());
+        if edge < 0.0 {
+            return;
+        }
+        let edge = (cp[2].y() - cp[3].y()) * -cp[3].y() + cp[3].x() * (cp[3].x() - cp[2].x());
+        if edge < 0.0 {
+            return;
+        }
+        // Find the closest point of the (nearly straight) piece to the ray
+        let segment = Vec3::new(cp[3].x() - cp[0].x(), cp[3].y() - cp[0].y(), 0.0);
+        let denominator = segment.squared_length();
+        if denominator == 0.0 {
+            return;
+        }
+        let w = (-cp[0].x() * segment.x() - cp[0].y() * segment.y()) / denominator;
+        let u = ((1.0 - w) * u_0 + w * u_1).max(u_0).min(u_1);
+        let width = self.width_at(u);
+        let (center, derivative) = eval_bezier(cp, w.max(0.0).min(1.0));
+        let distance_squared = center.x() * center.x() + center.y() * center.y();
+        if distance_squared > 0.25 * width * width || center.z() < z_min || center.z() > *z_max {
+            return;
+        }
+        // Rays scattered by a curve start on its surface, and should leave it rather than
+        // hitting it again (its material accounts for light passing through it)
+        if center.length() < width {
+            return;
+        }
+        *z_max = center.z();
+        *hit = Some(CurveHit {
+            center,
+            derivative,
+            u,
+            width,
+        });
+    }
+}
+
+impl Hitable for CurveSegment {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        // Transform the control points into a coordinate system in which the ray
+        // starts at the origin and runs along +Z at unit speed
+        let speed = ray.direction.length();
+        let z_axis = ray.direction / speed;
+        let (x_axis, y_axis) = orthonormal_basis(&z_axis);
+        let to_ray_space = |p: &Vec3| {
+            let offset = *p - ray.origin;
+            Vec3::new(
+                dot(&offset, &x_axis),
+                dot(&offset, &y_axis),
+                dot(&offset, &z_axis),
+            )
+        };
+        let cp = [
+            to_ray_space(&self.control_points[0]),
+            to_ray_space(&self.control_points[1]),
+            to_ray_space(&self.control_points[2]),
+            to_ray_space(&self.control_points[3]),
+        ];
+
+        // Choose how many times to split the segment, so that each piece deviates from a
+        // straight line by no more than a twentieth of the curve's width
+        let mut curvature: f64 = 0.0;
+        for i in 0..2 {
+            for axis in 0..3 {
+                curvature =
+                    curvature.max((cp[i][axis] - 2.0 * cp[i + 1][axis] + cp[i + 2][axis]).abs());
+            }
+        }
+        let epsilon = self.widths.0.max(self.widths.1) / 20.0;
+        let depth = if curvature > 0.0 {
+            ((SQRT_2 * 6.0 * curvature / (8.0 * epsilon)).log2() / 2.0)
+                .round()
+                .max(0.0)
+                .min(10.0) as usize
+        } else {
+            0
+        };
+
+        let mut z_max = t_max * speed;
+        let mut hit = None;
+        self.recursive_intersect(&cp, 0.0, 1.0, depth, t_min * speed, &mut z_max, &mut hit);
+        let hit = match hit {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        let t = hit.center.z() / speed;
+        let hit_point = ray.point_at_param(t);
+        let center = ray.origin
+            + hit.center.x() * x_axis
+            + hit.center.y() * y_axis
+            + hit.center.z() * z_axis;
+        let derivative =
+            hit.derivative.x() * x_axis + hit.derivative.y() * y_axis + hit.derivative.z() * z_axis;
+        let tangent = if derivative.length() > 0.0 {
+            unit_vector(derivative)
+        } else {
+            unit_vector(self.control_points[3] - self.control_points[0])
+        };
+        // The normal of the flat ribbon faces the ray
+        let facing = -z_axis + dot(&z_axis, &tangent) * tangent;
+        let facing = if facing.length() > 1e-9 {
+            unit_vector(facing)
+        } else {
+            -z_axis
+        };
+        // `offset` is the (signed) distance of the hit across the curve's width, between
+        // -1 on its right edge and 1 on its left edge (as seen along the ray, with the
+        // tangent pointing up)
+        let side = cross(&facing, &tangent);
+        let offset = dot(&(hit_point - center), &side) / (0.5 * hit.width);
+        let offset = offset.max(-1.0).min(1.0);
+
+        rec.t = t;
+        rec.hit_point = hit_point;
+        rec.normal = match self.curve_type {
+            CurveType::Flat => facing,
+            CurveType::Round => {
+                unit_vector((1.0 - offset * offset).max(0.0).sqrt() * facing + offset * side)
+            }
+        };
+        rec.tangent = tangent;
+        rec.material = Some(Arc::clone(&self.material));
+        rec.u = self.u_range.0 + hit.u * (self.u_range.1 - self.u_range.0);
+        rec.v = 0.5 * (1.0 + offset);
+        true
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        let half_width = 0.5 * self.widths.0.max(self.widths.1);
+        let mut min_bound = Vec3::new(FLOAT_MAX, FLOAT_MAX, FLOAT_MAX);
+        let mut max_bound = Vec3::new(FLOAT_MIN, FLOAT_MIN, FLOAT_MIN);
+        for point in self.control_points.iter() {
+            for axis in 0..3 {
+                min_bound[axis] = min_bound[axis].min(point[axis] - half_width);
+                max_bound[axis] = max_bound[axis].max(point[axis] + half_width);
+            }
+        }
+        Some(AxisAlignedBoundingBox::new(min_bound, max_bound))
+    }
+}
+
+/// A collection of curves (e.g. hairs, blades of grass or cables), whose
+/// segments are stored in their own BVH.
+///
+/// A curve's `u` texture coordinate runs along its length (from 0 at its root to 1 at
+/// its tip), and its `v` coordinate across its width (from 0 on its right edge to 1 on
+/// its left edge, as seen along the ray with the curve pointing up).
+#[derive(Clone)]
+pub struct Curves {
+    pub segments: BvhNode,
+}
+
+impl Curves {
+    /// Constructs a new collection of curves.
+    ///
+    /// Panics if there are no strands, or if a strand has fewer than 4 control points
+    /// (or, in the Bezier basis, a number of control points other than 3n + 1, as
+    /// consecutive segments share their end points).
+    /// #### Arguments:
+    /// - `strands`: The control points of each curve
+    /// - `basis`: The basis in which the control points are given
+    /// - `widths`: The width of every curve at its root and at its tip
+    /// - `curve_type`: How the cross-section of the curves is shaded
+    /// - `material`: The material of the curves
+    pub fn new(
+        strands: &[Vec<Vec3>],
+        basis: CurveBasis,
+        widths: (f64, f64),
+        curve_type: CurveType,
+        material: Arc<Material>,
+    ) -> Self {
+        assert!(
+            !strands.is_empty(),
+            "A collection of curves requires a strand"
+        );
+        let mut segments: Vec<Arc<Hitable>> = vec![];
+        for strand in strands.iter() {
+            assert!(
+                strand.len() >= 4,
+                "A curve requires at least 4 control points, but a strand has {}",
+                strand.len()
+            );
+            assert!(
+                basis != CurveBasis::Bezier || strand.len() % 3 == 1,
+                "A Bezier curve requires 3n + 1 control points, but a strand has {}",
+                strand.len()
+            );
+            let control_points: Vec<[Vec3; 4]> = match basis {
+                CurveBasis::Bezier => strand
+                    .windows(4)
+                    .step_by(3)
+                    .map(|p| [p[0], p[1], p[2], p[3]])
+                    .collect(),
+                // Convert each span of the B-spline to the equivalent Bezier segment
+                CurveBasis::BSpline => strand
+                    .windows(4)
+                    .map(|p| {
+                        [
+                            (p[0] + 4.0 * p[1] + p[2]) / 6.0,
+                            (2.0 * p[1] + p[2]) / 3.0,
+                            (p[1] + 2.0 * p[2]) / 3.0,
+                            (p[1] + 4.0 * p[2] + p[3]) / 6.0,
+                        ]
+                    })
+                    .collect(),
+            };
+            let num_segments = control_points.len() as f64;
+            for (index, points) in control_points.into_iter().enumerate() {
+                let u_range = (
+                    index as f64 / num_segments,
+                    (index + 1) as f64 / num_segments,
+                );
+                segments.push(Arc::new(CurveSegment {
+                    control_points: points,
+                    widths: (
+                        (1.0 - u_range.0) * widths.0 + u_range.0 * widths.1,
+                        (1.0 - u_range.1) * widths.0 + u_range.1 * widths.1,
+                    ),
+                    u_range,
+                    curve_type,
+                    material: Arc::clone(&material),
+                }));
+            }
+        }
+        Curves {
+            segments: BvhNode::new(&mut HitableList { list: segments }, 0.0, 0.0),
+        }
+    }
+}
+
+impl Hitable for Curves {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        self.segments.hit(ray, t_min, t_max, rec, sampler)
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        Some(self.segments.bounding_box)
+    }
+}
@@ -0,0 +1,355 @@
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use hitable::bvh_node::BvhNode;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use hitable::hitable_list::HitableList;
+use material::material::Material;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::MAX as FLOAT_MAX;
+use std::f64::MIN as FLOAT_MIN;
+use std::sync::Arc;
+use vec3::{cross, dot, unit_vector, Vec3};
+
+/// The vertex data of a triangle mesh whose vertices move over time.
+///
+/// The mesh's topology is fixed, while the positions (and optionally the normals)
+/// of its vertices are given at each keyframe and linearly interpolated between them.
+#[derive(Clone, Debug)]
+pub struct MeshKeyframes {
+    /// The times of the keyframes, in increasing order
+    pub times: Vec<f64>,
+    /// The positions of every vertex at each keyframe
+    pub positions: Vec<Vec<Vec3>>,
+    /// The normals of every vertex at each keyframe
+    pub normals: Option<Vec<Vec<Vec3>>>,
+    /// The texture coordinates of every vertex (which are the same at every keyframe)
+    pub texture_coords: Option<Vec<(f64, f64)>>,
+}
+
+impl MeshKeyframes {
+    /// Checks that the keyframes are consistent with each other, and with the triangles
+    /// which refer to their vertices
+    /// #### Returns
+    /// - A description of the first problem found, if any
+    pub fn validate(&self, indices: &[[usize; 3]]) -> Result<(), String> {
+        if self.times.is_empty() {
+            return Err("A deforming mesh requires at least one keyframe".to_string());
+        }
+        if self.times.iter().any(|time| !time.is_finite()) {
+            return Err("The times of a deforming mesh's keyframes must be finite".to_string());
+        }
+        if self.times.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err("The times of a deforming mesh's keyframes must increase".to_string());
+        }
+        let num_vertices = self
+            .positions
+            .first()
+            .map_or(0, |positions| positions.len());
+        let check_keyframes = |values: &[Vec<Vec3>], name: &str| {
+            if values.len() != self.times.len() {
+                return Err(format!(
+                    "A deforming mesh has {} keyframe times, but {} keyframes of {}",
+                    self.times.len(),
+                    values.len(),
+                    name
+                ));
+            }
+            match values
+                .iter()
+                .position(|values| values.len() != num_vertices)
+            {
+                Some(key) => Err(format!(
+                    "Keyframe {} of a deforming mesh has {} {}, rather than {}",
+                    key,
+                    values[key].len(),
+                    name,
+                    num_vertices
+                )),
+                None => Ok(()),
+            }
+        };
+        check_keyframes(&self.positions, "positions")?;
+        if let Some(ref normals) = self.normals {
+            check_keyframes(normals, "normals")?;
+        }
+        if let Some(ref texture_coords) = self.texture_coords {
+            if texture_coords.len() != num_vertices {
+                return Err(format!(
+                    "A deforming mesh has {} texture coordinates, rather than {}",
+                    texture_coords.len(),
+                    num_vertices
+                ));
+            }
+        }
+        if indices.is_empty() {
+            return Err("A deforming mesh requires at least one triangle".to_string());
+        }
+        if let Some(triangle) = indices
+            .iter()
+            .find(|triangle| triangle.iter().any(|&index| index >= num_vertices))
+        {
+            return Err(format!(
+                "A triangle of a deforming mesh ({:?}) refers to a missing vertex",
+                triangle
+            ));
+        }
+        Ok(())
+    }
+
+    /// Finds the keyframes surrounding the given time, and the interpolation
+    /// factor between them. Times outside the keyframes hold the nearest keyframe.
+    fn interval(&self, time: f64) -> (usize, usize, f64) {
+        let last = self.times.len() - 1;
+        if time <= self.times[0] {
+            return (0, 0, 0.0);
+        }
+        if time >= self.times[last] {
+            return (last, last, 0.0);
+        }
+        let next = self.times.partition_point(|&key_time| key_time <= time);
+        let t = (time - self.times[next - 1]) / (self.times[next] - self.times[next - 1]);
+        (next - 1, next, t)
+    }
+
+    /// Interpolates a single vertex attribute between the keyframes surrounding `time`
+    fn interpolate(values: &[Vec<Vec3>], interval: (usize, usize, f64), vertex: usize) -> Vec3 {
+        let (start, end, t) = interval;
+        (1.0 - t) * values[start][vertex] + t * values[end][vertex]
+    }
+}
+
+/// A triangle of a DeformingMesh
+#[derive(Clone)]
+pub struct DeformingTriangle {
+    mesh: Arc<MeshKeyframes>,
+    indices: [usize; 3],
+    material: Arc<Material>,
+}
+
+impl Hitable for DeformingTriangle {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        let interval = self.mesh.interval(ray.time);
+        let [i_a, i_b, i_c] = self.indices;
+        let a = MeshKeyframes::interpolate(&self.mesh.positions, interval, i_a);
+        let b = MeshKeyframes::interpolate(&self.mesh.positions, interval, i_b);
+        let c = MeshKeyframes::interpolate(&self.mesh.positions, interval, i_c);
+        // Source: Tomas Möller and Ben Trumbore, `Fast, Minimum Storage Ray/Triangle Intersection`
+        let edge_1 = b - a;
+        let edge_2 = c - a;
+        let p = cross(&ray.direction, &edge_2);
+        let determinant = dot(&edge_1, &p);
+        if determinant.abs() < 1e-12 {
+            return false;
+        }
+        let inv_determinant = 1.0 / determinant;
+        let s = ray.origin - a;
+        let u = dot(&s, &p) * inv_determinant;
+        if u < 0.0 || u > 1.0 {
+            return false;
+        }
+        let q = cross(&s, &edge_1);
+        let v = dot(&ray.direction, &q) * inv_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return false;
+        }
+        let t = dot(&edge_2, &q) * inv_determinant;
+        if t < t_min || t > t_max {
+            return false;
+        }
+        let w = 1.0 - u - v;
+        rec.t = t;
+        rec.hit_point = ray.point_at_param(t);
+        rec.normal = match self.mesh.normals {
+            Some(ref normals) => unit_vector(
+                w * MeshKeyframes::interpolate(normals, interval, i_a)
+                    + u * MeshKeyframes::interpolate(normals, interval, i_b)
+                    + v * MeshKeyframes::interpolate(normals, interval, i_c),
+            ),
+            None => unit_vector(cross(&edge_1, &edge_2)),
+        };
+        let (tex_u, tex_v) = match self.mesh.texture_coords {
+            Some(ref texcoords) => (
+                w * texcoords[i_a].0 + u * texcoords[i_b].0 + v * texcoords[i_c].0,
+                w * texcoords[i_a].1 + u * texcoords[i_b].1 + v * texcoords[i_c].1,
+            ),
+            None => (0.0, 0.0),
+        };
+        rec.u = tex_u;
+        rec.v = tex_v;
+        rec.material = Some(Arc::clone(&self.material));
+        true
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        // As vertices move linearly between keyframes, the triangle's positions at the ends
+        // of the interval and at every keyframe within it bound its entire motion.
+        let mut times = vec![start_time, end_time];
+        times.extend(
+            self.mesh
+                .times
+                .iter()
+                .filter(|&&time| time > start_time && time < end_time),
+        );
+        let mut min_bound = Vec3::new(FLOAT_MAX, FLOAT_MAX, FLOAT_MAX);
+        let mut max_bound = Vec3::new(FLOAT_MIN, FLOAT_MIN, FLOAT_MIN);
+        for time in times {
+            let interval = self.mesh.interval(time);
+            for &index in self.indices.iter() {
+                let vert = MeshKeyframes::interpolate(&self.mesh.positions, interval, index);
+                min_bound.e[0] = vert.x().min(min_bound.x());
+                min_bound.e[1] = vert.y().min(min_bound.y());
+                min_bound.e[2] = vert.z().min(min_bound.z());
+                max_bound.e[0] = vert.x().max(max_bound.x());
+                max_bound.e[1] = vert.y().max(max_bound.y());
+                max_bound.e[2] = vert.z().max(max_bound.z());
+            }
+        }
+        Some(AxisAlignedBoundingBox::new(min_bound, max_bound))
+    }
+}
+
+/// A triangle mesh whose vertices move over time, producing deformation motion blur
+#[derive(Clone)]
+pub struct DeformingMesh {
+    pub triangles: BvhNode,
+}
+
+impl DeformingMesh {
+    /// Constructs a new deforming mesh.
+    /// #### Arguments:
+    /// - `keyframes`: The vertex data of the mesh at each keyframe
+    /// - `indices`: The indices of the vertices of each triangle
+    /// - `material`: The material of the mesh
+    /// - `start_time`: The time at which the camera shutter opens
+    /// - `end_time`: The time at which the camera shutter closes
+    ///
+    /// #### Returns
+    /// - The mesh, or a description of why the keyframes or triangles are invalid
+    pub fn new(
+        keyframes: MeshKeyframes,
+        indices: &[[usize; 3]],
+        material: Arc<Material>,
+        start_time: f64,
+        end_time: f64,
+    ) -> Result<Self, String> {
+        keyframes.validate(indices)?;
+        let mesh = Arc::new(keyframes);
+        let triangles: Vec<Arc<Hitable>> = indices
+            .iter()
+            .map(|&indices| {
+                Arc::new(DeformingTriangle {
+                    mesh: Arc::clone(&mesh),
+                    indices,
+                    material: Arc::clone(&material),
+                }) as Arc<Hitable>
+            })
+            .collect();
+        Ok(DeformingMesh {
+            triangles: BvhNode::new(&mut HitableList { list: triangles }, start_time, end_time),
+        })
+    }
+}
+
+impl Hitable for DeformingMesh {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        self.triangles.hit(ray, t_min, t_max, rec, sampler)
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        Some(self.triangles.bounding_box)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use material::materials::Lambertian;
+    use sampler::samplers::IndependentSampler;
+    use texture::textures::ConstantTexture;
+
+    /// A triangle in the plane z = 0 at time 0, which moves to z = 2 at time 1
+    fn keyframes() -> MeshKeyframes {
+        let triangle = |z| {
+            vec![
+                Vec3::new(-1.0, -1.0, z),
+                Vec3::new(1.0, -1.0, z),
+                Vec3::new(0.0, 1.0, z),
+            ]
+        };
+        MeshKeyframes {
+            times: vec![0.0, 1.0],
+            positions: vec![triangle(0.0), triangle(2.0)],
+            normals: None,
+            texture_coords: Some(vec![(0.0, 0.0), (1.0, 0.0), (0.5, 1.0)]),
+        }
+    }
+
+    fn new_mesh(keyframes: MeshKeyframes, indices: &[[usize; 3]]) -> Result<DeformingMesh, String> {
+        let material = Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture::new(Vec3::new(0.5, 0.5, 0.5))),
+        });
+        DeformingMesh::new(keyframes, indices, material, 0.0, 1.0)
+    }
+
+    #[test]
+    fn interpolates_vertices_at_the_ray_time() {
+        let mesh = new_mesh(keyframes(), &[[0, 1, 2]]).unwrap();
+        let mut sampler = IndependentSampler::new(1, 0);
+        for &(time, z) in &[(0.0, 0.0), (0.25, 0.5), (1.0, 2.0), (3.0, 2.0)] {
+            let ray = Ray::new(Vec3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0), time);
+            let mut rec = HitRecord::new();
+            assert!(mesh.hit(&ray, 0.0, FLOAT_MAX, &mut rec, &mut sampler));
+            assert!((rec.hit_point.z() - z).abs() < 1e-12);
+        }
+        let bounds = mesh.bounding_box(0.0, 1.0).unwrap().bounds;
+        assert_eq!((bounds[0].z(), bounds[1].z()), (0.0, 2.0));
+    }
+
+    #[test]
+    fn rejects_invalid_keyframes() {
+        let mut empty = keyframes();
+        empty.times.clear();
+        empty.positions.clear();
+        let mut unordered = keyframes();
+        unordered.times = vec![1.0, 0.0];
+        let mut missing_positions = keyframes();
+        missing_positions.positions.pop();
+        let mut missing_vertex = keyframes();
+        missing_vertex.positions[1].pop();
+        let mut missing_normals = keyframes();
+        missing_normals.normals = Some(vec![vec![Vec3::new(0.0, 0.0, 1.0); 3]]);
+        let mut missing_texture_coords = keyframes();
+        missing_texture_coords.texture_coords = Some(vec![(0.0, 0.0)]);
+        for keyframes in vec![
+            empty,
+            unordered,
+            missing_positions,
+            missing_vertex,
+            missing_normals,
+            missing_texture_coords,
+        ] {
+            assert!(new_mesh(keyframes, &[[0, 1, 2]]).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_triangles() {
+        assert!(new_mesh(keyframes(), &[]).is_err());
+        assert!(new_mesh(keyframes(), &[[0, 1, 3]]).is_err());
+    }
+}
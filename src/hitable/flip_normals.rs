@@ -2,6 +2,7 @@ use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
 use hitable::hit_record::HitRecord;
 use hitable::hitable::Hitable;
 use ray::Ray;
+use sampler::sampler::Sampler;
 use std::sync::Arc;
 
 /// The only purpose this struct serves is to wrap
@@ -18,8 +19,15 @@ impl FlipNormals {
 }
 
 impl Hitable for FlipNormals {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
-        if self.hitable.hit(ray, t_min, t_max, rec) {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        if self.hitable.hit(ray, t_min, t_max, rec, sampler) {
             rec.normal = -rec.normal;
             return true;
         }
@@ -1,4 +1,5 @@
 use material::material::Material;
+use medium::medium_stack::MediumEntry;
 use std::sync::Arc;
 use vec3::Vec3;
 
@@ -6,9 +7,21 @@ pub struct HitRecord {
     pub t: f64,
     pub hit_point: Vec3,
     pub normal: Vec3,
+    /// The direction along the surface in which `u` increases (e.g. along a hair),
+    /// or zero if the surface does not define one
+    pub tangent: Vec3,
     pub material: Option<Arc<Material>>,
+    /// The medium enclosed by the surface that was hit, if the surface is the boundary of one
+    pub medium: Option<MediumEntry>,
     pub u: f64,
     pub v: f64,
+    /// The color attribute of the surface at the hit (e.g. the color of a point, or of a
+    /// mesh's vertices interpolated across a face), if the surface has one
+    pub color: Option<Vec3>,
+    /// The ID of the tagged object that was hit (0 if the object is untagged)
+    pub object_id: usize,
+    /// The time of the Ray that hit the surface, at which animated textures are evaluated
+    pub time: f64,
 }
 
 impl HitRecord {
@@ -17,9 +30,14 @@ impl HitRecord {
             t: -1.0,
             hit_point: Vec3::new(0.0, 0.0, 0.0),
             normal: Vec3::new(0.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, 0.0),
             material: None,
+            medium: None,
             u: 0.0,
             v: 0.0,
+            color: None,
+            object_id: 0,
+            time: 0.0,
         }
     }
 
@@ -28,8 +46,13 @@ impl HitRecord {
         self.t = other.t;
         self.hit_point = other.hit_point;
         self.normal = other.normal;
+        self.tangent = other.tangent;
         self.material = other.material.take();
+        self.medium = other.medium.take();
         self.u = other.u;
         self.v = other.v;
+        self.color = other.color;
+        self.object_id = other.object_id;
+        self.time = other.time;
     }
 }
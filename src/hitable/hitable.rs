@@ -1,6 +1,7 @@
 use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
 use hitable::hit_record::HitRecord;
 use ray::Ray;
+use sampler::sampler::Sampler;
 use std::marker::{Send, Sync};
 
 /// A trait declaring that an object can be hit by a ray.
@@ -11,10 +12,19 @@ pub trait Hitable: Send + Sync {
     /// - `t_min`: A lower bound on the Ray parameter `t`
     /// - `t_max`: An upper bound on the Ray parameter `t`
     /// - `rec`: A record of the ray's collision is written to this parameter
+    /// - `sampler`: Provides any sample values needed to determine the collision
+    ///   (e.g. the scattering distance within a participating medium)
     ///
     /// #### Returns:
     /// - bool: Whether the given ray successfully hit the object.
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool;
 
     /// Computes a bounding box for the object.
     /// #### Arguments:
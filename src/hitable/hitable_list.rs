@@ -3,6 +3,7 @@ use bounding_boxes::utils;
 use hitable::hit_record::HitRecord;
 use hitable::hitable::Hitable;
 use ray::Ray;
+use sampler::sampler::Sampler;
 use std::sync::Arc;
 
 /// Represents a list of Hitable objects.
@@ -19,13 +20,24 @@ impl HitableList {
 }
 
 impl Hitable for HitableList {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
         let mut hit = false;
         let mut current_closest = t_max;
         for obj in self.list.iter() {
-            if obj.hit(ray, t_min, current_closest, rec) {
+            // Each object is given a fresh record, so that attributes only set by some
+            // objects (e.g. a medium) are not left over from a farther hit
+            let ref mut obj_rec = HitRecord::new();
+            if obj.hit(ray, t_min, current_closest, obj_rec, sampler) {
                 hit = true;
-                current_closest = rec.t;
+                current_closest = obj_rec.t;
+                rec.from(obj_rec);
             }
         }
 
@@ -0,0 +1,155 @@
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use hitable::utils;
+use material::material::Material;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use sdf::distance_function::DistanceFunction;
+use std::sync::Arc;
+use vec3::{unit_vector, Vec3};
+
+/// The number of bisection steps used to refine a hit when a step passes through the surface
+const REFINEMENT_STEPS: usize = 16;
+
+/// A surface described implicitly by a signed distance function, rendered by sphere tracing.
+///
+/// The distance function is only evaluated within the given bounding box (which must contain
+/// the entire surface), allowing infinite or repeated shapes to be clipped to a finite region.
+#[derive(Clone)]
+pub struct ImplicitSurface {
+    pub function: Arc<DistanceFunction>,
+    pub bounding_box: AxisAlignedBoundingBox,
+    pub material: Arc<Material>,
+    /// The distance from the surface at which a ray is considered to have hit it
+    pub epsilon: f64,
+    /// The maximum number of steps taken along a ray
+    pub max_steps: usize,
+    /// The fraction of the distance to the surface stepped each time, which must be reduced
+    /// below 1 for distance functions which overestimate the distance (e.g. `Twist`)
+    pub step_scale: f64,
+}
+
+impl ImplicitSurface {
+    /// Constructs a new implicit surface
+    /// #### Arguments:
+    /// - `function`: The signed distance function describing the surface
+    /// - `bounding_box`: A box containing the entire surface
+    /// - `material`: The material of the surface
+    pub fn new(
+        function: Arc<DistanceFunction>,
+        bounding_box: AxisAlignedBoundingBox,
+        material: Arc<Material>,
+    ) -> Self {
+        ImplicitSurface {
+            function,
+            bounding_box,
+            material,
+            epsilon: 0.0001,
+            max_steps: 512,
+            step_scale: 1.0,
+        }
+    }
+
+    /// Calculates the (outward facing) normal at a point from the gradient of the distance function
+    fn normal(&self, point: &Vec3) -> Vec3 {
+        let h = self.epsilon;
+        let mut gradient = Vec3::new(0.0, 0.0, 0.0);
+        for axis in 0..3 {
+            let mut offset = Vec3::new(0.0, 0.0, 0.0);
+            offset[axis] = h;
+            gradient[axis] = self.function.distance(&(*point + offset))
+                - self.function.distance(&(*point - offset));
+        }
+        unit_vector(gradient)
+    }
+
+    /// Clips the interval `t_min..t_max` of a ray to the bounding box
+    fn clip_to_bounds(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let (mut near, mut far) = (t_min, t_max);
+        for axis in 0..3 {
+            let t_0 =
+                (self.bounding_box.min_bound[axis] - ray.origin[axis]) * ray.invert_direction[axis];
+            let t_1 =
+                (self.bounding_box.max_bound[axis] - ray.origin[axis]) * ray.invert_direction[axis];
+            near = near.max(t_0.min(t_1));
+            far = far.min(t_0.max(t_1));
+        }
+        if near <= far {
+            Some((near, far))
+        } else {
+            None
+        }
+    }
+}
+
+impl Hitable for ImplicitSurface {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        let (start, end) = match self.clip_to_bounds(ray, t_min, t_max) {
+            Some(interval) => interval,
+            None => return false,
+        };
+        // Distances are measured in world units, so steps are divided by the ray's speed
+        let speed = ray.direction.length();
+        let mut t = start;
+        let mut distance = self.function.distance(&ray.point_at_param(t));
+        // A ray leaving the surface (e.g. after refraction) must first move away from it
+        let mut leaving = start == t_min && distance.abs() < self.epsilon;
+        let mut hit_t = None;
+        for _ in 0..self.max_steps {
+            let step = (distance.abs() * self.step_scale).max(self.epsilon) / speed;
+            let next_t = t + step;
+            if next_t > end {
+                break;
+            }
+            let next_distance = self.function.distance(&ray.point_at_param(next_t));
+            if leaving {
+                leaving = next_distance.abs() < self.epsilon;
+            } else if next_distance.abs() < self.epsilon {
+                hit_t = Some(next_t);
+                break;
+            } else if (next_distance > 0.0) != (distance > 0.0) {
+                // The step passed through the surface, so the crossing is found by bisection
+                let (mut low, mut high) = (t, next_t);
+                for _ in 0..REFINEMENT_STEPS {
+                    let mid = 0.5 * (low + high);
+                    if (self.function.distance(&ray.point_at_param(mid)) > 0.0) == (distance > 0.0)
+                    {
+                        low = mid;
+                    } else {
+                        high = mid;
+                    }
+                }
+                hit_t = Some(high);
+                break;
+            }
+            t = next_t;
+            distance = next_distance;
+        }
+
+        match hit_t {
+            Some(t) => {
+                rec.t = t;
+                rec.hit_point = ray.point_at_param(t);
+                rec.normal = self.normal(&rec.hit_point);
+                rec.material = Some(Arc::clone(&self.material));
+                let (u, v) = utils::get_sphere_uv(&rec.normal);
+                rec.u = u;
+                rec.v = v;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        Some(self.bounding_box)
+    }
+}
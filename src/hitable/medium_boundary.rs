@@ -0,0 +1,80 @@
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use medium::medium::Medium;
+use medium::medium_stack::MediumEntry;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::sync::Arc;
+
+/// Wraps a closed Hitable, filling the space it encloses with a participating medium.
+///
+/// Rays which pass through the boundary's surface (e.g. by being refracted by a glass
+/// boundary) travel through the medium until they next hit a surface. The surface normals
+/// of the boundary must face outwards, as they decide whether a ray enters or leaves it.
+///
+/// Boundaries may be nested or overlap, in which case their priorities decide which medium
+/// fills the space they share. For example, a liquid whose boundary slightly overlaps the
+/// inner wall of its (higher priority) glass meets the glass without any gap of air, as the
+/// part of the liquid's surface within the glass is ignored.
+#[derive(Clone)]
+pub struct MediumBoundary {
+    pub boundary: Arc<Hitable>,
+    pub medium: Arc<Medium>,
+    /// Whether the boundary's surface is rendered with its material, or is invisible
+    /// (letting rays pass straight through it)
+    pub visible: bool,
+    /// The priority of the medium where it overlaps other media (0 by default)
+    pub priority: i32,
+}
+
+impl MediumBoundary {
+    /// Constructs a new medium boundary whose surface is rendered with its own material
+    /// (e.g. a glass containing a liquid)
+    pub fn new(boundary: Arc<Hitable>, medium: Arc<Medium>) -> Self {
+        MediumBoundary {
+            boundary,
+            medium,
+            visible: true,
+            priority: 0,
+        }
+    }
+
+    /// Constructs a new medium boundary whose surface is invisible (e.g. the edge of a
+    /// cloud of gas)
+    pub fn new_invisible(boundary: Arc<Hitable>, medium: Arc<Medium>) -> Self {
+        MediumBoundary {
+            boundary,
+            medium,
+            visible: false,
+            priority: 0,
+        }
+    }
+}
+
+impl Hitable for MediumBoundary {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        if self.boundary.hit(ray, t_min, t_max, rec, sampler) {
+            if !self.visible {
+                rec.material = None;
+            }
+            rec.medium = Some(MediumEntry {
+                medium: Arc::clone(&self.medium),
+                priority: self.priority,
+            });
+            return true;
+        }
+        false
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        self.boundary.bounding_box(start_time, end_time)
+    }
+}
@@ -1,12 +1,23 @@
+pub mod animated_transform;
 pub mod bvh_node;
+pub mod csg;
+pub mod curves;
+pub mod deforming_mesh;
 pub mod flip_normals;
 pub mod hit_record;
 pub mod hitable;
 pub mod hitable_list;
+pub mod implicit_surface;
+pub mod medium_boundary;
 pub mod moving_sphere;
+pub mod object_tag;
+pub mod point_cloud;
 pub mod polygon;
+pub mod quadrics;
 pub mod rectangles;
 pub mod sphere;
-pub mod utils;
+pub mod subsurface;
+pub mod torus;
 pub mod transformations;
+pub mod utils;
 pub mod volumes;
@@ -5,6 +5,7 @@ use hitable::hitable::Hitable;
 use hitable::utils as hitable_utils;
 use material::material::Material;
 use ray::Ray;
+use sampler::sampler::Sampler;
 use std::sync::Arc;
 use vec3::{dot, Vec3};
 
@@ -30,7 +31,14 @@ impl MovingSphere {
 }
 
 impl Hitable for MovingSphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
         let oc = ray.origin - self.get_center(ray.time);
         let a = dot(&ray.direction, &ray.direction);
         let b = dot(&oc, &ray.direction);
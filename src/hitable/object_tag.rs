@@ -0,0 +1,52 @@
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::sync::Arc;
+
+/// Wrapper struct that wraps a Hitable and stamps its ID onto every
+/// HitRecord it produces, for use in the object ID render pass.
+#[derive(Clone)]
+pub struct ObjectTag {
+    hitable: Arc<Hitable>,
+    id: usize,
+}
+
+impl ObjectTag {
+    /// Arguments:
+    /// - `hitable`: The Hitable to be tagged
+    /// - `id`: The ID of the object; 0 is reserved for untagged objects
+    pub fn new(hitable: Arc<Hitable>, id: usize) -> Self {
+        ObjectTag { hitable, id }
+    }
+
+    /// Tags every Hitable in the given list with sequential IDs, starting from 1
+    pub fn tag_all(list: Vec<Arc<Hitable>>) -> Vec<Arc<Hitable>> {
+        list.into_iter()
+            .enumerate()
+            .map(|(idx, hitable)| Arc::new(ObjectTag::new(hitable, idx + 1)) as Arc<Hitable>)
+            .collect()
+    }
+}
+
+impl Hitable for ObjectTag {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        if self.hitable.hit(ray, t_min, t_max, rec, sampler) {
+            rec.object_id = self.id;
+            return true;
+        }
+        false
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        self.hitable.bounding_box(start_time, end_time)
+    }
+}
@@ -0,0 +1,266 @@
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use hitable::utils;
+use material::material::Material;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::MAX as FLOAT_MAX;
+use std::f64::MIN as FLOAT_MIN;
+use std::sync::Arc;
+use vec3::{dot, Vec3};
+
+/// The maximum number of points stored in a leaf of a point cloud's BVH
+const MAX_POINTS_PER_LEAF: usize = 4;
+
+/// A node of a point cloud's BVH, which is stored as a flat array in depth-first order
+#[derive(Copy, Clone, Debug)]
+struct PointBvhNode {
+    /// The minimum and maximum corners of the node's bounding box
+    bounds: [Vec3; 2],
+    /// For a leaf, the index of its first point; otherwise, the index of its second child
+    /// (its first child immediately follows it)
+    offset: u32,
+    /// The number of points in a leaf (zero for interior nodes)
+    count: u8,
+    /// The axis along which an interior node's children were split
+    axis: u8,
+}
+
+impl PointBvhNode {
+    /// Determines whether a ray passes through the node's bounding box within [t_min, t_max]
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        let mut near = t_min;
+        let mut far = t_max;
+        for axis in 0..3 {
+            let sign = ray.sign[axis] as usize;
+            let t_0 = (self.bounds[sign][axis] - ray.origin[axis]) * ray.invert_direction[axis];
+            let t_1 = (self.bounds[1 - sign][axis] - ray.origin[axis]) * ray.invert_direction[axis];
+            near = near.max(t_0);
+            far = far.min(t_1);
+            if near > far {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// A large collection of spheres (e.g. particles or scanned points) sharing a material,
+/// each with its own radius and optional color.
+///
+/// Points are stored in flat, single precision arrays rather than as individual
+/// hitables, and are traversed by a dedicated BVH, so that clouds of millions of points
+/// stay compact. The color of the point that was hit is available to textures as the
+/// hit's color attribute (see `VertexColorTexture`).
+#[derive(Clone)]
+pub struct PointCloud {
+    positions: Vec<[f32; 3]>,
+    radii: Vec<f32>,
+    colors: Option<Vec<[f32; 3]>>,
+    nodes: Vec<PointBvhNode>,
+    pub material: Arc<Material>,
+}
+
+impl PointCloud {
+    /// Constructs a new point cloud, building its BVH
+    /// #### Arguments:
+    /// - `positions`: The center of each point
+    /// - `radii`: The radius of each point
+    /// - `colors`: The color of each point, if the points are colored
+    /// - `material`: The material of the points
+    pub fn new(
+        positions: &[Vec3],
+        radii: &[f64],
+        colors: Option<&[Vec3]>,
+        material: Arc<Material>,
+    ) -> Self {
+        assert_eq!(positions.len(), radii.len(), "Every point needs a radius");
+        let mut order: Vec<usize> = (0..positions.len()).collect();
+        let mut nodes = vec![];
+        if !positions.is_empty() {
+            build_bvh(positions, radii, &mut order, 0, &mut nodes);
+        }
+        // Store the points in the order of the BVH's leaves
+        let to_f32 = |v: &Vec3| [v.x() as f32, v.y() as f32, v.z() as f32];
+        PointCloud {
+            positions: order.iter().map(|&i| to_f32(&positions[i])).collect(),
+            radii: order.iter().map(|&i| radii[i] as f32).collect(),
+            colors: colors.map(|colors| order.iter().map(|&i| to_f32(&colors[i])).collect()),
+            nodes,
+            material,
+        }
+    }
+
+    /// The number of points in the cloud
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Whether the cloud has no points
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    fn position(&self, index: usize) -> Vec3 {
+        let p = self.positions[index];
+        Vec3::new(f64::from(p[0]), f64::from(p[1]), f64::from(p[2]))
+    }
+
+    /// Intersects a ray with a single point
+    /// #### Returns:
+    /// - The distance along the ray of the nearest intersection within [t_min, t_max], if any
+    fn hit_point(&self, index: usize, ray: &Ray, t_min: f64, t_max: f64) -> Option<f64> {
+        let radius = f64::from(self.radii[index]);
+        let oc = ray.origin - self.position(index);
+        let a = dot(&ray.direction, &ray.direction);
+        let b = dot(&oc, &ray.direction);
+        let c = dot(&oc, &oc) - radius * radius;
+        let discriminant = b * b - a * c;
+        if discriminant <= 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        [(-b - root) / a, (-b + root) / a]
+            .iter()
+            .cloned()
+            .find(|&t| t > t_min && t < t_max)
+    }
+}
+
+/// Recursively builds the BVH over the points whose indices are in `order`, splitting
+/// them at their median along the axis in which their centers are most spread out
+/// #### Returns:
+/// - The index of the node that was built
+fn build_bvh(
+    positions: &[Vec3],
+    radii: &[f64],
+    order: &mut [usize],
+    first: usize,
+    nodes: &mut Vec<PointBvhNode>,
+) -> usize {
+    let mut bounds = [
+        Vec3::new(FLOAT_MAX, FLOAT_MAX, FLOAT_MAX),
+        Vec3::new(FLOAT_MIN, FLOAT_MIN, FLOAT_MIN),
+    ];
+    let mut center_bounds = bounds;
+    for &i in order.iter() {
+        for axis in 0..3 {
+            let center = positions[i][axis];
+            bounds[0][axis] = bounds[0][axis].min(center - radii[i]);
+            bounds[1][axis] = bounds[1][axis].max(center + radii[i]);
+            center_bounds[0][axis] = center_bounds[0][axis].min(center);
+            center_bounds[1][axis] = center_bounds[1][axis].max(center);
+        }
+    }
+    // Pad the bounds to cover the rounding of the points to single precision
+    let padding = 1e-6 * (bounds[1] - bounds[0]).length() + 1e-6;
+    let bounds = [bounds[0] - padding, bounds[1] + padding];
+
+    let index = nodes.len();
+    if order.len() <= MAX_POINTS_PER_LEAF {
+        nodes.push(PointBvhNode {
+            bounds,
+            offset: first as u32,
+            count: order.len() as u8,
+            axis: 0,
+        });
+        return index;
+    }
+
+    let extent = center_bounds[1] - center_bounds[0];
+    let axis = if extent.x() > extent.y() && extent.x() > extent.z() {
+        0
+    } else if extent.y() > extent.z() {
+        1
+    } else {
+        2
+    };
+    let middle = order.len() / 2;
+    order.select_nth_unstable_by(middle, |&a, &b| {
+        positions[a][axis].partial_cmp(&positions[b][axis]).unwrap()
+    });
+    nodes.push(PointBvhNode {
+        bounds,
+        offset: 0,
+        count: 0,
+        axis: axis as u8,
+    });
+    let (left, right) = order.split_at_mut(middle);
+    build_bvh(positions, radii, left, first, nodes);
+    let second = build_bvh(positions, radii, right, first + middle, nodes);
+    nodes[index].offset = second as u32;
+    index
+}
+
+impl Hitable for PointCloud {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        let mut closest: Option<(usize, f64)> = None;
+        let mut closest_t = t_max;
+        // The BVH is balanced, so its depth (and the size of the stack) is logarithmic
+        let mut stack = [0usize; 64];
+        let mut stack_size = 1;
+        while stack_size > 0 {
+            stack_size -= 1;
+            let index = stack[stack_size];
+            let node = &self.nodes[index];
+            if !node.hit(ray, t_min, closest_t) {
+                continue;
+            }
+            if node.count > 0 {
+                let first = node.offset as usize;
+                for point in first..first + node.count as usize {
+                    if let Some(t) = self.hit_point(point, ray, t_min, closest_t) {
+                        closest_t = t;
+                        closest = Some((point, t));
+                    }
+                }
+                continue;
+            }
+            // Visit the child nearer the ray's origin first
+            let (near, far) = if ray.sign[node.axis as usize] {
+                (node.offset as usize, index + 1)
+            } else {
+                (index + 1, node.offset as usize)
+            };
+            stack[stack_size] = far;
+            stack[stack_size + 1] = near;
+            stack_size += 2;
+        }
+
+        match closest {
+            Some((point, t)) => {
+                let center = self.position(point);
+                rec.t = t;
+                rec.hit_point = ray.point_at_param(t);
+                rec.normal = (rec.hit_point - center) / f64::from(self.radii[point]);
+                rec.material = Some(Arc::clone(&self.material));
+                let (u, v) = utils::get_sphere_uv(&rec.normal);
+                rec.u = u;
+                rec.v = v;
+                rec.color = self.colors.as_ref().map(|colors| {
+                    let c = colors[point];
+                    Vec3::new(f64::from(c[0]), f64::from(c[1]), f64::from(c[2]))
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        self.nodes
+            .first()
+            .map(|root| AxisAlignedBoundingBox::new(root.bounds[0], root.bounds[1]))
+    }
+}
@@ -5,6 +5,7 @@ use hitable::hitable::Hitable;
 use hitable::hitable_list::HitableList;
 use material::material::Material;
 use ray::Ray;
+use sampler::sampler::Sampler;
 use std::f64::MAX as FLOAT_MAX;
 use std::f64::MIN as FLOAT_MIN;
 use std::sync::Arc;
@@ -17,6 +18,7 @@ pub struct Polygon {
     pub normal: Vec3,
     pub vertex_normals: Option<Vec<Vec3>>,
     pub texture_coords: Option<Vec<(f64, f64)>>,
+    pub vertex_colors: Option<Vec<Vec3>>,
     bounding_box: Option<AxisAlignedBoundingBox>,
     material: Arc<Material>,
 }
@@ -29,6 +31,7 @@ impl Polygon {
             normal: Vec3::new(0.0, 0.0, 0.0),
             vertex_normals: None,
             texture_coords: None,
+            vertex_colors: None,
             bounding_box: None,
             material,
         };
@@ -91,6 +94,21 @@ impl Polygon {
         }
     }
 
+    /// Interpolates a *triangle's* vertex colors at a given `hit_point`
+    fn interpolate_color(&self, hit_point: Vec3) -> Option<Vec3> {
+        self.vertex_colors.as_ref().map(|colors| {
+            let a = self.vertices[0];
+            let b = self.vertices[1];
+            let c = self.vertices[2];
+            let area_abc = cross(&(b - a), &(c - a)).length();
+            let area_pbc = cross(&(b - hit_point), &(c - hit_point)).length();
+            let area_pca = cross(&(c - hit_point), &(a - hit_point)).length();
+            let u = area_pbc / area_abc;
+            let v = area_pca / area_abc;
+            u * colors[0] + v * colors[1] + (1.0 - u - v) * colors[2]
+        })
+    }
+
     /// Uses the even/odd test to determine if the given point lies within the polygon
     fn is_point_in_poly(&self, point: (f64, f64), poly: Vec<(f64, f64)>) -> bool {
         let len = poly.len();
@@ -114,7 +132,14 @@ impl Polygon {
 }
 
 impl Hitable for Polygon {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
         let point_on_plane;
         match self.vertices.get(0) {
             Some(point) => {
@@ -168,6 +193,7 @@ impl Hitable for Polygon {
         let (u, v) = self.interpolate_uv(hit_point);
         rec.u = u;
         rec.v = v;
+        rec.color = self.interpolate_color(hit_point);
         rec.material = Some(Arc::clone(&self.material));
         true
     }
@@ -204,8 +230,15 @@ impl PolygonMesh {
 }
 
 impl Hitable for PolygonMesh {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
-        self.polygons.hit(ray, t_min, t_max, rec)
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        self.polygons.hit(ray, t_min, t_max, rec, sampler)
     }
 
     fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
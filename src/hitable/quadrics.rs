@@ -0,0 +1,695 @@
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use hitable::flip_normals::FlipNormals;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use hitable::hitable_list::HitableList;
+use material::material::Material;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::consts::PI;
+use std::sync::Arc;
+use vec3::{unit_vector, Vec3};
+
+// All of the quadrics below are symmetric about a vertical axis (parallel to Y) passing
+// through their `center`, and are swept about that axis from an angle of 0 (the +X direction)
+// up to `phi_max` degrees (towards +Z). A `phi_max` of 360 gives the complete surface,
+// while a `phi_max` of 0 (or less) gives an empty surface which is never hit.
+// Other orientations can be achieved by wrapping them in a transform.
+
+/// Calculates the angle (in radians, between 0 and 2π) of a point about the Y axis
+pub fn azimuth(x: f64, z: f64) -> f64 {
+    let phi = z.atan2(x);
+    if phi < 0.0 {
+        phi + 2.0 * PI
+    } else {
+        phi
+    }
+}
+
+/// Finds the nearest root of `a*t^2 + b*t + c = 0` between `t_min` and `t_max`
+/// which is accepted by `accept` (used to clip the surface to its extent and sweep)
+fn nearest_root<F: Fn(f64) -> bool>(
+    a: f64,
+    b: f64,
+    c: f64,
+    t_min: f64,
+    t_max: f64,
+    accept: F,
+) -> Option<f64> {
+    let roots = if a.abs() < 1e-12 {
+        if b == 0.0 {
+            return None;
+        }
+        [-c / b, -c / b]
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let (t_0, t_1) = (
+            (-b - discriminant.sqrt()) / (2.0 * a),
+            (-b + discriminant.sqrt()) / (2.0 * a),
+        );
+        [t_0.min(t_1), t_0.max(t_1)]
+    };
+    roots
+        .iter()
+        .cloned()
+        .find(|&t| t > t_min && t < t_max && accept(t))
+}
+
+/// Fills in the hit record of a quadric intersection
+fn set_hit_record(
+    rec: &mut HitRecord,
+    ray: &Ray,
+    t: f64,
+    normal: Vec3,
+    u: f64,
+    v: f64,
+    material: &Arc<Material>,
+) {
+    rec.t = t;
+    rec.hit_point = ray.point_at_param(t);
+    rec.normal = unit_vector(normal);
+    rec.material = Some(Arc::clone(material));
+    rec.u = u;
+    rec.v = v;
+}
+
+/// Represents a disk (or an annulus, if `inner_radius` is greater than zero)
+/// lying in the X-Z plane, facing +Y
+#[derive(Clone)]
+pub struct Disk {
+    pub center: Vec3,
+    pub radius: f64,
+    pub inner_radius: f64,
+    /// The angle (in degrees) through which the disk is swept
+    pub phi_max: f64,
+    pub material: Arc<Material>,
+}
+
+impl Disk {
+    pub fn new(
+        center: Vec3,
+        radius: f64,
+        inner_radius: f64,
+        phi_max: f64,
+        material: Arc<Material>,
+    ) -> Self {
+        Disk {
+            center,
+            radius,
+            inner_radius,
+            phi_max,
+            material,
+        }
+    }
+}
+
+impl Hitable for Disk {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        if self.phi_max <= 0.0 || ray.direction.y() == 0.0 {
+            return false;
+        }
+        let t = (self.center.y() - ray.origin.y()) / ray.direction.y();
+        if t < t_min || t > t_max {
+            return false;
+        }
+        let local = ray.point_at_param(t) - self.center;
+        let distance = (local.x() * local.x() + local.z() * local.z()).sqrt();
+        if distance > self.radius || distance < self.inner_radius {
+            return false;
+        }
+        let phi = azimuth(local.x(), local.z());
+        if phi > self.phi_max.to_radians() {
+            return false;
+        }
+        set_hit_record(
+            rec,
+            ray,
+            t,
+            Vec3::new(0.0, 1.0, 0.0),
+            phi / self.phi_max.to_radians(),
+            (self.radius - distance) / (self.radius - self.inner_radius),
+            &self.material,
+        );
+        true
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        Some(AxisAlignedBoundingBox::new(
+            self.center - Vec3::new(self.radius, 0.0001, self.radius),
+            self.center + Vec3::new(self.radius, 0.0001, self.radius),
+        ))
+    }
+}
+
+/// Represents a cylinder whose base is centered on `center`, extending `height` units along +Y
+#[derive(Clone)]
+pub struct Cylinder {
+    pub center: Vec3,
+    pub radius: f64,
+    pub height: f64,
+    /// The angle (in degrees) through which the cylinder is swept
+    pub phi_max: f64,
+    pub material: Arc<Material>,
+    /// The disks closing the ends of the cylinder (if it is capped)
+    pub caps: Option<HitableList>,
+}
+
+impl Cylinder {
+    /// Constructs a new cylinder
+    /// #### Arguments:
+    /// - `center`: The center of the cylinder's base
+    /// - `radius`: The radius of the cylinder
+    /// - `height`: The height of the cylinder
+    /// - `phi_max`: The angle (in degrees) through which the cylinder is swept
+    /// - `capped`: Whether the ends of the cylinder are closed by disks (swept by the same angle)
+    /// - `material`: The material of the cylinder
+    pub fn new(
+        center: Vec3,
+        radius: f64,
+        height: f64,
+        phi_max: f64,
+        capped: bool,
+        material: Arc<Material>,
+    ) -> Self {
+        let caps = if capped {
+            let bottom = Disk::new(center, radius, 0.0, phi_max, Arc::clone(&material));
+            let top = Disk::new(
+                center + Vec3::new(0.0, height, 0.0),
+                radius,
+                0.0,
+                phi_max,
+                Arc::clone(&material),
+            );
+            Some(HitableList {
+                list: vec![Arc::new(FlipNormals::new(Arc::new(bottom))), Arc::new(top)],
+            })
+        } else {
+            None
+        };
+        Cylinder {
+            center,
+            radius,
+            height,
+            phi_max,
+            material,
+            caps,
+        }
+    }
+}
+
+impl Hitable for Cylinder {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        if self.phi_max <= 0.0 {
+            return false;
+        }
+        let (o, d) = (ray.origin - self.center, ray.direction);
+        let phi_max = self.phi_max.to_radians();
+        let a = d.x() * d.x() + d.z() * d.z();
+        let b = 2.0 * (o.x() * d.x() + o.z() * d.z());
+        let c = o.x() * o.x() + o.z() * o.z() - self.radius * self.radius;
+        let root = nearest_root(a, b, c, t_min, t_max, |t| {
+            let p = o + t * d;
+            p.y() >= 0.0 && p.y() <= self.height && azimuth(p.x(), p.z()) <= phi_max
+        });
+        let mut closest = t_max;
+        if let Some(t) = root {
+            let p = o + t * d;
+            set_hit_record(
+                rec,
+                ray,
+                t,
+                Vec3::new(p.x(), 0.0, p.z()),
+                azimuth(p.x(), p.z()) / phi_max,
+                p.y() / self.height,
+                &self.material,
+            );
+            closest = t;
+        }
+        let hit_cap = match self.caps {
+            Some(ref caps) => caps.hit(ray, t_min, closest, rec, sampler),
+            None => false,
+        };
+        root.is_some() || hit_cap
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        Some(AxisAlignedBoundingBox::new(
+            self.center - Vec3::new(self.radius, 0.0, self.radius),
+            self.center + Vec3::new(self.radius, self.height, self.radius),
+        ))
+    }
+}
+
+/// Represents a cone whose base is centered on `center`, with its apex `height` units above it
+#[derive(Clone)]
+pub struct Cone {
+    pub center: Vec3,
+    /// The radius of the cone's base
+    pub radius: f64,
+    pub height: f64,
+    /// The angle (in degrees) through which the cone is swept
+    pub phi_max: f64,
+    pub material: Arc<Material>,
+}
+
+impl Cone {
+    pub fn new(
+        center: Vec3,
+        radius: f64,
+        height: f64,
+        phi_max: f64,
+        material: Arc<Material>,
+    ) -> Self {
+        Cone {
+            center,
+            radius,
+            height,
+            phi_max,
+            material,
+        }
+    }
+}
+
+impl Hitable for Cone {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        if self.phi_max <= 0.0 {
+            return false;
+        }
+        // x^2 + z^2 = k * (height - y)^2
+        let (o, d) = (ray.origin - self.center, ray.direction);
+        let phi_max = self.phi_max.to_radians();
+        let k = (self.radius / self.height).powi(2);
+        let above = self.height - o.y();
+        let a = d.x() * d.x() + d.z() * d.z() - k * d.y() * d.y();
+        let b = 2.0 * (o.x() * d.x() + o.z() * d.z() + k * above * d.y());
+        let c = o.x() * o.x() + o.z() * o.z() - k * above * above;
+        let root = nearest_root(a, b, c, t_min, t_max, |t| {
+            let p = o + t * d;
+            p.y() >= 0.0 && p.y() <= self.height && azimuth(p.x(), p.z()) <= phi_max
+        });
+        match root {
+            Some(t) => {
+                let p = o + t * d;
+                set_hit_record(
+                    rec,
+                    ray,
+                    t,
+                    Vec3::new(p.x(), k * (self.height - p.y()), p.z()),
+                    azimuth(p.x(), p.z()) / phi_max,
+                    p.y() / self.height,
+                    &self.material,
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        Some(AxisAlignedBoundingBox::new(
+            self.center - Vec3::new(self.radius, 0.0, self.radius),
+            self.center + Vec3::new(self.radius, self.height, self.radius),
+        ))
+    }
+}
+
+/// Represents a paraboloid whose vertex lies on `center`, opening towards +Y
+/// until it reaches `radius` at `height` units above its vertex
+#[derive(Clone)]
+pub struct Paraboloid {
+    pub center: Vec3,
+    pub radius: f64,
+    pub height: f64,
+    /// The angle (in degrees) through which the paraboloid is swept
+    pub phi_max: f64,
+    pub material: Arc<Material>,
+}
+
+impl Paraboloid {
+    pub fn new(
+        center: Vec3,
+        radius: f64,
+        height: f64,
+        phi_max: f64,
+        material: Arc<Material>,
+    ) -> Self {
+        Paraboloid {
+            center,
+            radius,
+            height,
+            phi_max,
+            material,
+        }
+    }
+}
+
+impl Hitable for Paraboloid {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        if self.phi_max <= 0.0 {
+            return false;
+        }
+        // y = k * (x^2 + z^2)
+        let (o, d) = (ray.origin - self.center, ray.direction);
+        let phi_max = self.phi_max.to_radians();
+        let k = self.height / (self.radius * self.radius);
+        let a = k * (d.x() * d.x() + d.z() * d.z());
+        let b = 2.0 * k * (o.x() * d.x() + o.z() * d.z()) - d.y();
+        let c = k * (o.x() * o.x() + o.z() * o.z()) - o.y();
+        let root = nearest_root(a, b, c, t_min, t_max, |t| {
+            let p = o + t * d;
+            p.y() <= self.height && azimuth(p.x(), p.z()) <= phi_max
+        });
+        match root {
+            Some(t) => {
+                let p = o + t * d;
+                set_hit_record(
+                    rec,
+                    ray,
+                    t,
+                    Vec3::new(2.0 * k * p.x(), -1.0, 2.0 * k * p.z()),
+                    azimuth(p.x(), p.z()) / phi_max,
+                    p.y() / self.height,
+                    &self.material,
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        Some(AxisAlignedBoundingBox::new(
+            self.center - Vec3::new(self.radius, 0.0, self.radius),
+            self.center + Vec3::new(self.radius, self.height, self.radius),
+        ))
+    }
+}
+
+/// Represents a hyperboloid of one sheet, centered on `center`, whose radius is `radius`
+/// at its waist and `end_radius` at its ends, `height / 2` units above and below the waist.
+///
+/// An `end_radius` smaller than `radius` produces a section of an ellipsoid instead.
+#[derive(Clone)]
+pub struct Hyperboloid {
+    pub center: Vec3,
+    pub radius: f64,
+    pub end_radius: f64,
+    pub height: f64,
+    /// The angle (in degrees) through which the hyperboloid is swept
+    pub phi_max: f64,
+    pub material: Arc<Material>,
+}
+
+impl Hyperboloid {
+    pub fn new(
+        center: Vec3,
+        radius: f64,
+        end_radius: f64,
+        height: f64,
+        phi_max: f64,
+        material: Arc<Material>,
+    ) -> Self {
+        Hyperboloid {
+            center,
+            radius,
+            end_radius,
+            height,
+            phi_max,
+            material,
+        }
+    }
+}
+
+impl Hitable for Hyperboloid {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        if self.phi_max <= 0.0 {
+            return false;
+        }
+        // x^2 + z^2 = radius^2 + k * y^2
+        let (o, d) = (ray.origin - self.center, ray.direction);
+        let phi_max = self.phi_max.to_radians();
+        let half_height = 0.5 * self.height;
+        let k = (self.end_radius * self.end_radius - self.radius * self.radius)
+            / (half_height * half_height);
+        let a = d.x() * d.x() + d.z() * d.z() - k * d.y() * d.y();
+        let b = 2.0 * (o.x() * d.x() + o.z() * d.z() - k * o.y() * d.y());
+        let c = o.x() * o.x() + o.z() * o.z() - k * o.y() * o.y() - self.radius * self.radius;
+        let root = nearest_root(a, b, c, t_min, t_max, |t| {
+            let p = o + t * d;
+            p.y().abs() <= half_height && azimuth(p.x(), p.z()) <= phi_max
+        });
+        match root {
+            Some(t) => {
+                let p = o + t * d;
+                set_hit_record(
+                    rec,
+                    ray,
+                    t,
+                    Vec3::new(p.x(), -k * p.y(), p.z()),
+                    azimuth(p.x(), p.z()) / phi_max,
+                    (p.y() + half_height) / self.height,
+                    &self.material,
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        let radius = self.radius.max(self.end_radius);
+        let half_height = 0.5 * self.height;
+        Some(AxisAlignedBoundingBox::new(
+            self.center - Vec3::new(radius, half_height, radius),
+            self.center + Vec3::new(radius, half_height, radius),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use material::materials::Lambertian;
+    use sampler::samplers::IndependentSampler;
+    use texture::textures::ConstantTexture;
+
+    fn material() -> Arc<Material> {
+        Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture::new(Vec3::new(0.5, 0.5, 0.5))),
+        })
+    }
+
+    fn hit(hitable: &Hitable, origin: Vec3, direction: Vec3) -> Option<HitRecord> {
+        let mut sampler = IndependentSampler::new(1, 0);
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(origin, direction, 0.0);
+        if hitable.hit(&ray, 0.001, std::f64::MAX, &mut rec, &mut sampler) {
+            Some(rec)
+        } else {
+            None
+        }
+    }
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn disk_hits_between_its_radii() {
+        let disk = Disk::new(Vec3::new(0.0, 0.0, 0.0), 2.0, 0.5, 360.0, material());
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        let rec = hit(&disk, Vec3::new(1.0, 5.0, 0.0), down).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-9);
+        assert_close(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!((rec.v - 1.0 / 1.5).abs() < 1e-9);
+        assert!(hit(&disk, Vec3::new(0.25, 5.0, 0.0), down).is_none());
+        assert!(hit(&disk, Vec3::new(3.0, 5.0, 0.0), down).is_none());
+        assert!(hit(&disk, Vec3::new(1.0, 5.0, 0.0), Vec3::new(1.0, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn disk_is_clipped_to_its_sweep() {
+        let disk = Disk::new(Vec3::new(0.0, 0.0, 0.0), 2.0, 0.0, 90.0, material());
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        let rec = hit(&disk, Vec3::new(0.7, 5.0, 0.7), down).unwrap();
+        assert!((rec.u - 0.5).abs() < 1e-9);
+        assert!(hit(&disk, Vec3::new(-1.0, 5.0, 0.0), down).is_none());
+    }
+
+    #[test]
+    fn cylinder_hits_within_its_height() {
+        let cylinder = Cylinder::new(Vec3::new(0.0, 0.0, 0.0), 1.0, 2.0, 360.0, false, material());
+        let right = Vec3::new(1.0, 0.0, 0.0);
+        let rec = hit(&cylinder, Vec3::new(-5.0, 1.0, 0.0), right).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert_close(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!((rec.v - 0.5).abs() < 1e-9);
+        assert!(hit(&cylinder, Vec3::new(-5.0, 3.0, 0.0), right).is_none());
+        // An uncapped cylinder is open at its ends
+        assert!(hit(
+            &cylinder,
+            Vec3::new(0.0, 5.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0)
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn cylinder_hit_from_inside_has_outward_normal() {
+        let cylinder = Cylinder::new(Vec3::new(0.0, 0.0, 0.0), 1.0, 2.0, 360.0, false, material());
+        let rec = hit(
+            &cylinder,
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+        )
+        .unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-9);
+        assert_close(rec.normal, Vec3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn capped_cylinder_hits_its_caps() {
+        let cylinder = Cylinder::new(Vec3::new(0.0, 0.0, 0.0), 1.0, 2.0, 360.0, true, material());
+        let rec = hit(
+            &cylinder,
+            Vec3::new(0.0, 5.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+        )
+        .unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert_close(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        let rec = hit(
+            &cylinder,
+            Vec3::new(0.0, -5.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        )
+        .unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-9);
+        assert_close(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn cylinder_skips_hits_outside_its_sweep() {
+        let cylinder = Cylinder::new(Vec3::new(0.0, 0.0, 0.0), 1.0, 2.0, 180.0, false, material());
+        // The near side (z = -1) lies outside of the sweep, so the far side is hit
+        let rec = hit(
+            &cylinder,
+            Vec3::new(0.0, 1.0, -5.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        )
+        .unwrap();
+        assert!((rec.t - 6.0).abs() < 1e-9);
+        assert_close(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert!((rec.u - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cone_hits_its_slanted_side() {
+        let cone = Cone::new(Vec3::new(0.0, 0.0, 0.0), 1.0, 2.0, 360.0, material());
+        let right = Vec3::new(1.0, 0.0, 0.0);
+        let rec = hit(&cone, Vec3::new(-5.0, 1.0, 0.0), right).unwrap();
+        assert!((rec.t - 4.5).abs() < 1e-9);
+        assert_close(rec.normal, unit_vector(Vec3::new(-2.0, 1.0, 0.0)));
+        // The mirrored cone above the apex is not part of the surface
+        assert!(hit(&cone, Vec3::new(-5.0, 3.0, 0.0), right).is_none());
+        assert!(hit(&cone, Vec3::new(-5.0, -1.0, 0.0), right).is_none());
+    }
+
+    #[test]
+    fn paraboloid_hits_its_bowl() {
+        let paraboloid = Paraboloid::new(Vec3::new(0.0, 0.0, 0.0), 1.0, 1.0, 360.0, material());
+        let rec = hit(
+            &paraboloid,
+            Vec3::new(0.0, 5.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+        )
+        .unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-9);
+        assert_close(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+        let right = Vec3::new(1.0, 0.0, 0.0);
+        let rec = hit(&paraboloid, Vec3::new(-5.0, 0.25, 0.0), right).unwrap();
+        assert!((rec.t - 4.5).abs() < 1e-9);
+        assert_close(rec.normal, unit_vector(Vec3::new(-1.0, -1.0, 0.0)));
+        assert!(hit(&paraboloid, Vec3::new(-5.0, 2.0, 0.0), right).is_none());
+        assert!(hit(&paraboloid, Vec3::new(-5.0, -0.5, 0.0), right).is_none());
+    }
+
+    #[test]
+    fn hyperboloid_hits_its_waist_and_sides() {
+        let hyperboloid =
+            Hyperboloid::new(Vec3::new(0.0, 0.0, 0.0), 1.0, 2.0, 2.0, 360.0, material());
+        let right = Vec3::new(1.0, 0.0, 0.0);
+        let rec = hit(&hyperboloid, Vec3::new(-5.0, 0.0, 0.0), right).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert_close(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!((rec.v - 0.5).abs() < 1e-9);
+        // x^2 + z^2 = 1 + 3y^2
+        let rec = hit(&hyperboloid, Vec3::new(-5.0, 0.5, 0.0), right).unwrap();
+        assert!((rec.t - (5.0 - 1.75f64.sqrt())).abs() < 1e-9);
+        assert_close(
+            rec.normal,
+            unit_vector(Vec3::new(-1.75f64.sqrt(), -1.5, 0.0)),
+        );
+        assert!(hit(&hyperboloid, Vec3::new(-5.0, 1.5, 0.0), right).is_none());
+        assert!(hit(
+            &hyperboloid,
+            Vec3::new(0.0, 5.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0)
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn quadrics_swept_through_no_angle_are_never_hit() {
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        let left = Vec3::new(-1.0, 0.0, 0.0);
+        let disk = Disk::new(origin, 2.0, 0.0, 0.0, material());
+        assert!(hit(&disk, Vec3::new(1.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0)).is_none());
+        let cylinder = Cylinder::new(origin, 1.0, 2.0, 0.0, true, material());
+        assert!(hit(&cylinder, Vec3::new(5.0, 1.0, 0.0), left).is_none());
+        let cone = Cone::new(origin, 1.0, 2.0, 0.0, material());
+        assert!(hit(&cone, Vec3::new(5.0, 1.0, 0.0), left).is_none());
+        let paraboloid = Paraboloid::new(origin, 1.0, 1.0, 0.0, material());
+        assert!(hit(&paraboloid, Vec3::new(5.0, 0.25, 0.0), left).is_none());
+        let hyperboloid = Hyperboloid::new(origin, 1.0, 2.0, 2.0, 0.0, material());
+        assert!(hit(&hyperboloid, Vec3::new(5.0, 0.0, 0.0), left).is_none());
+    }
+}
@@ -5,8 +5,9 @@ use hitable::hitable::Hitable;
 use hitable::hitable_list::HitableList;
 use material::material::Material;
 use ray::Ray;
+use sampler::sampler::Sampler;
 use std::sync::Arc;
-use vec3::Vec3;
+use vec3::{cross, dot, unit_vector, Vec3};
 
 /// Represents a rectangle aligned along the X-Y axis
 #[derive(Clone)]
@@ -20,7 +21,14 @@ pub struct XYRect {
 }
 
 impl Hitable for XYRect {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
         let t = (self.k - ray.origin.z()) / ray.direction.z();
         if t < t_min || t > t_max {
             return false;
@@ -63,7 +71,14 @@ pub struct XZRect {
 }
 
 impl Hitable for XZRect {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
         let t = (self.k - ray.origin.y()) / ray.direction.y();
         if t < t_min || t > t_max {
             return false;
@@ -106,7 +121,14 @@ pub struct YZRect {
 }
 
 impl Hitable for YZRect {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
         let t = (self.k - ray.origin.x()) / ray.direction.x();
         if t < t_min || t > t_max {
             return false;
@@ -137,6 +159,85 @@ impl Hitable for YZRect {
     }
 }
 
+/// Represents a parallelogram with an arbitrary orientation, spanned by the
+/// edges `u` and `v` leaving its `corner`. It faces in the direction of `u` × `v`.
+#[derive(Clone)]
+pub struct Quad {
+    pub material: Arc<Material>,
+    pub corner: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+}
+
+impl Quad {
+    pub fn new(corner: Vec3, u: Vec3, v: Vec3, material: Arc<Material>) -> Self {
+        Quad {
+            material,
+            corner,
+            u,
+            v,
+        }
+    }
+}
+
+impl Hitable for Quad {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        let normal = cross(&self.u, &self.v);
+        let denominator = dot(&normal, &ray.direction);
+        if denominator.abs() < 1e-12 {
+            return false;
+        }
+        let t = dot(&normal, &(self.corner - ray.origin)) / denominator;
+        if t < t_min || t > t_max {
+            return false;
+        }
+        // Find the coordinates of the hit point along each edge
+        let hit_point = ray.point_at_param(t);
+        let p = hit_point - self.corner;
+        let w = normal / dot(&normal, &normal);
+        let (u, v) = (dot(&w, &cross(&p, &self.v)), dot(&w, &cross(&self.u, &p)));
+        if u < 0.0 || u > 1.0 || v < 0.0 || v > 1.0 {
+            return false;
+        }
+        rec.t = t;
+        rec.hit_point = hit_point;
+        rec.normal = unit_vector(normal);
+        rec.material = Some(Arc::clone(&self.material));
+        rec.u = u;
+        rec.v = v;
+        true
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        let corners = [
+            self.corner,
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ];
+        let padding = Vec3::new(0.0001, 0.0001, 0.0001);
+        let mut min_bound = corners[0];
+        let mut max_bound = corners[0];
+        for corner in corners.iter() {
+            for axis in 0..3 {
+                min_bound[axis] = min_bound[axis].min(corner[axis]);
+                max_bound[axis] = max_bound[axis].max(corner[axis]);
+            }
+        }
+        Some(AxisAlignedBoundingBox::new(
+            min_bound - padding,
+            max_bound + padding,
+        ))
+    }
+}
+
 /// Represents a block (i.e. a six-sided cuboid)
 #[derive(Clone)]
 pub struct AxisAlignedBlock {
@@ -212,8 +313,15 @@ impl AxisAlignedBlock {
 }
 
 impl Hitable for AxisAlignedBlock {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
-        self.sides.hit(ray, t_min, t_max, rec)
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        self.sides.hit(ray, t_min, t_max, rec, sampler)
     }
 
     fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
@@ -4,6 +4,7 @@ use hitable::hitable::Hitable;
 use hitable::utils;
 use material::material::Material;
 use ray::Ray;
+use sampler::sampler::Sampler;
 use std::sync::Arc;
 use vec3::{dot, Vec3};
 
@@ -16,7 +17,14 @@ pub struct Sphere {
 }
 
 impl Hitable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
         let oc = ray.origin - self.center;
         let a = dot(&ray.direction, &ray.direction);
         let b = dot(&oc, &ray.direction);
@@ -0,0 +1,62 @@
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use material::material::Material;
+use material::materials::SubsurfaceScattering;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::sync::Arc;
+use texture::texture::Texture;
+
+/// Wraps a closed Hitable (e.g. a sphere, block or mesh), rendering it with a translucent
+/// material through which light scatters beneath the surface (replacing its own material)
+#[derive(Clone)]
+pub struct Subsurface {
+    pub object: Arc<Hitable>,
+    material: Arc<Material>,
+}
+
+impl Subsurface {
+    /// Constructs a new translucent object
+    /// #### Arguments:
+    /// - `object`: The closed object, whose surface normals must face outwards
+    /// - `albedo`: The color of the surface
+    /// - `mean_free_path`: The distance light of each channel travels between scattering
+    ///   events, which sets how far light spreads beneath the surface
+    /// - `refractive_index`: The refractive index of the surface
+    pub fn new(
+        object: Arc<Hitable>,
+        albedo: Arc<Texture>,
+        mean_free_path: Arc<Texture>,
+        refractive_index: f64,
+    ) -> Self {
+        let material = Arc::new(SubsurfaceScattering::new(
+            Arc::clone(&object),
+            albedo,
+            mean_free_path,
+            refractive_index,
+        ));
+        Subsurface { object, material }
+    }
+}
+
+impl Hitable for Subsurface {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        if self.object.hit(ray, t_min, t_max, rec, sampler) {
+            rec.material = Some(Arc::clone(&self.material));
+            return true;
+        }
+        false
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        self.object.bounding_box(start_time, end_time)
+    }
+}
@@ -0,0 +1,231 @@
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use hitable::quadrics::azimuth;
+use hitable::utils::solve_polynomial;
+use material::material::Material;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::consts::PI;
+use std::sync::Arc;
+use vec3::{dot, unit_vector, Vec3};
+
+/// Represents a torus centered on `center`, lying in the X-Z plane
+#[derive(Clone)]
+pub struct Torus {
+    pub center: Vec3,
+    /// The distance from the center of the torus to the center of its tube
+    pub major_radius: f64,
+    /// The radius of the tube
+    pub minor_radius: f64,
+    /// The angle (in degrees) through which the torus is swept about the Y axis
+    /// (a torus swept through no angle is never hit)
+    pub phi_max: f64,
+    pub material: Arc<Material>,
+}
+
+impl Torus {
+    pub fn new(
+        center: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        phi_max: f64,
+        material: Arc<Material>,
+    ) -> Self {
+        Torus {
+            center,
+            major_radius,
+            minor_radius,
+            phi_max,
+            material,
+        }
+    }
+}
+
+impl Hitable for Torus {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        if self.phi_max <= 0.0 {
+            return false;
+        }
+        // The quartic is solved along a normalized ray, starting from the point closest to the
+        // torus' center, which keeps its coefficients small for distant rays
+        let length = ray.direction.length();
+        let d = ray.direction / length;
+        let shift = -dot(&(ray.origin - self.center), &d);
+        let o = ray.origin - self.center + shift * d;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 * R^2 * (p.x^2 + p.z^2), where p = o + s * d
+        let r_2 = self.major_radius * self.major_radius;
+        let n = dot(&o, &d);
+        let k = dot(&o, &o) + r_2 - self.minor_radius * self.minor_radius;
+        let a = d.x() * d.x() + d.z() * d.z();
+        let b = o.x() * d.x() + o.z() * d.z();
+        let c = o.x() * o.x() + o.z() * o.z();
+        let roots = solve_polynomial(&[
+            1.0,
+            4.0 * n,
+            4.0 * n * n + 2.0 * k - 4.0 * r_2 * a,
+            4.0 * n * k - 8.0 * r_2 * b,
+            k * k - 4.0 * r_2 * c,
+        ]);
+
+        let phi_max = self.phi_max.to_radians();
+        for s in roots {
+            let t = (s + shift) / length;
+            if t <= t_min || t >= t_max {
+                continue;
+            }
+            let p = o + s * d;
+            let phi = azimuth(p.x(), p.z());
+            if phi > phi_max {
+                continue;
+            }
+            // The normal points away from the nearest point on the center of the tube
+            let distance = (p.x() * p.x() + p.z() * p.z()).sqrt();
+            let tube_center = Vec3::new(p.x(), 0.0, p.z()) * (self.major_radius / distance);
+            let mut theta = p.y().atan2(distance - self.major_radius);
+            if theta < 0.0 {
+                theta += 2.0 * PI;
+            }
+            rec.t = t;
+            rec.hit_point = ray.point_at_param(t);
+            rec.normal = unit_vector(p - tube_center);
+            rec.material = Some(Arc::clone(&self.material));
+            rec.u = phi / phi_max;
+            rec.v = theta / (2.0 * PI);
+            return true;
+        }
+        false
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        let radius = self.major_radius + self.minor_radius;
+        Some(AxisAlignedBoundingBox::new(
+            self.center - Vec3::new(radius, self.minor_radius, radius),
+            self.center + Vec3::new(radius, self.minor_radius, radius),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use material::materials::Lambertian;
+    use sampler::samplers::IndependentSampler;
+    use texture::textures::ConstantTexture;
+
+    fn torus(phi_max: f64) -> Torus {
+        let material = Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture::new(Vec3::new(0.5, 0.5, 0.5))),
+        });
+        Torus::new(Vec3::new(0.0, 0.0, 0.0), 2.0, 0.5, phi_max, material)
+    }
+
+    fn hit(torus: &Torus, origin: Vec3, direction: Vec3) -> Option<HitRecord> {
+        let mut sampler = IndependentSampler::new(1, 0);
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(origin, direction, 0.0);
+        if torus.hit(&ray, 0.001, std::f64::MAX, &mut rec, &mut sampler) {
+            Some(rec)
+        } else {
+            None
+        }
+    }
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-6, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn hits_outside_of_tube() {
+        let rec = hit(
+            &torus(360.0),
+            Vec3::new(-5.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+        )
+        .unwrap();
+        assert!((rec.t - 2.5).abs() < 1e-6);
+        assert_close(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!((rec.u - 0.5).abs() < 1e-6);
+        // A longer direction scales the ray's parameter
+        let rec = hit(
+            &torus(360.0),
+            Vec3::new(-5.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+        )
+        .unwrap();
+        assert!((rec.t - 1.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rays_through_hole_miss() {
+        let torus = torus(360.0);
+        assert!(hit(&torus, Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0)).is_none());
+        assert!(hit(&torus, Vec3::new(1.0, 5.0, 0.5), Vec3::new(0.0, -1.0, 0.0)).is_none());
+        assert!(hit(&torus, Vec3::new(3.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn tangent_rays_graze_top_of_tube() {
+        let torus = torus(360.0);
+        let rec = hit(&torus, Vec3::new(-5.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-4);
+        assert_close(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!(hit(
+            &torus,
+            Vec3::new(-5.0, 0.501, 0.0),
+            Vec3::new(1.0, 0.0, 0.0)
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn rays_starting_inside_tube_hit_its_far_wall() {
+        let rec = hit(
+            &torus(360.0),
+            Vec3::new(-2.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+        )
+        .unwrap();
+        assert!((rec.t - 0.5).abs() < 1e-6);
+        assert_close(rec.normal, Vec3::new(1.0, 0.0, 0.0));
+        let rec = hit(
+            &torus(360.0),
+            Vec3::new(-2.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        )
+        .unwrap();
+        assert!((rec.t - 0.5).abs() < 1e-6);
+        assert_close(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!((rec.v - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn skips_hits_outside_of_sweep() {
+        let torus = torus(90.0);
+        // The tube around x = -2 lies outside of the sweep
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        assert!(hit(&torus, Vec3::new(-2.0, 5.0, 0.0), down).is_none());
+        // as does the near side of the tube (z = -2), so the far side is hit
+        let rec = hit(&torus, Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0)).unwrap();
+        assert!((rec.t - 6.5).abs() < 1e-6);
+        assert!((rec.u - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn torus_swept_through_no_angle_is_never_hit() {
+        let rec = hit(
+            &torus(0.0),
+            Vec3::new(5.0, 0.0, 0.0),
+            Vec3::new(-1.0, 0.0, 0.0),
+        );
+        assert!(rec.is_none());
+    }
+}
@@ -2,6 +2,7 @@ use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
 use hitable::hit_record::HitRecord;
 use hitable::hitable::Hitable;
 use ray::Ray;
+use sampler::sampler::Sampler;
 use std::f64::consts::PI;
 use std::f64::MAX as FLOAT_MAX;
 use std::f64::MIN as FLOAT_MIN;
@@ -25,9 +26,19 @@ impl Translate {
 }
 
 impl Hitable for Translate {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
         let translated_ray = Ray::new(ray.origin - self.offset, ray.direction, ray.time);
-        if self.hitable.hit(&translated_ray, t_min, t_max, rec) {
+        if self
+            .hitable
+            .hit(&translated_ray, t_min, t_max, rec, sampler)
+        {
             rec.hit_point += self.offset;
             return true;
         }
@@ -112,7 +123,14 @@ impl RotateY {
 }
 
 impl Hitable for RotateY {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
         let mut origin = ray.origin;
         let mut direction = ray.direction;
         // Rotated counter-clockwise about the Y axis
@@ -121,15 +139,19 @@ impl Hitable for RotateY {
         direction[0] = self.cos_theta * ray.direction.x() - self.sin_theta * ray.direction.z();
         direction[2] = self.sin_theta * ray.direction.x() + self.cos_theta * ray.direction.z();
         let rotated_ray = Ray::new(origin, direction, ray.time);
-        if self.hitable.hit(&rotated_ray, t_min, t_max, rec) {
+        if self.hitable.hit(&rotated_ray, t_min, t_max, rec, sampler) {
             let mut hit_point = rec.hit_point;
             let mut normal = rec.normal;
+            let mut tangent = rec.tangent;
             hit_point[0] = self.cos_theta * rec.hit_point.x() + self.sin_theta * rec.hit_point.z();
             hit_point[2] = -self.sin_theta * rec.hit_point.x() + self.cos_theta * rec.hit_point.z();
             normal[0] = self.cos_theta * rec.normal.x() + self.sin_theta * rec.normal.z();
             normal[2] = -self.sin_theta * rec.normal.x() + self.cos_theta * rec.normal.z();
+            tangent[0] = self.cos_theta * rec.tangent.x() + self.sin_theta * rec.tangent.z();
+            tangent[2] = -self.sin_theta * rec.tangent.x() + self.cos_theta * rec.tangent.z();
             rec.hit_point = hit_point;
             rec.normal = normal;
+            rec.tangent = tangent;
             return true;
         } else {
             return false;
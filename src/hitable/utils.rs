@@ -1,41 +1,56 @@
-use rand::distributions::{Distribution, Uniform};
+use sampler::sampler::Sampler;
 use std::f64::consts::PI;
-use vec3::{dot, unit_vector, Vec3};
+use vec3::{cross, dot, unit_vector, Vec3};
 
 /// Generates a random point in a unit-radius sphere
-pub fn random_point_in_unit_sphere() -> Vec3 {
-    let range = Uniform::new_inclusive(0.0, 1.0);
-    let mut rng = rand::thread_rng();
-    let mut point;
-    loop {
-        point =
-            2.0 * Vec3::new(
-                range.sample(&mut rng),
-                range.sample(&mut rng),
-                range.sample(&mut rng),
-            ) - Vec3::new(1.0, 1.0, 1.0);
-        if dot(&point, &point) < 1.0 {
-            break;
-        }
-    }
+///
+/// The point is constructed from a uniformly distributed direction and a radius
+/// (rather than by rejection sampling), so that well-stratified sample values
+/// result in well-stratified points.
+pub fn random_point_in_unit_sphere(sampler: &mut Sampler) -> Vec3 {
+    let direction = random_unit_vector(sampler);
+    let radius = sampler.get_1d().cbrt();
+
+    radius * direction
+}
+
+/// Generates a random, uniformly distributed unit-length direction
+pub fn random_unit_vector(sampler: &mut Sampler) -> Vec3 {
+    let (u, v) = sampler.get_2d();
+    let z = 1.0 - 2.0 * u;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
 
-    point
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Builds two unit vectors which are perpendicular to `z` and to each other
+pub fn orthonormal_basis(z: &Vec3) -> (Vec3, Vec3) {
+    let x = if z.x().abs() > z.y().abs() {
+        Vec3::new(-z.z(), 0.0, z.x()) / (z.x() * z.x() + z.z() * z.z()).sqrt()
+    } else {
+        Vec3::new(0.0, z.z(), -z.y()) / (z.y() * z.y() + z.z() * z.z()).sqrt()
+    };
+    (x, cross(z, &x))
 }
 
 /// Generates a random point in a unit-radius disk
-pub fn random_point_in_unit_disk() -> Vec3 {
-    let range = Uniform::new_inclusive(0.0, 1.0);
-    let mut rng = rand::thread_rng();
-    let mut point;
-    loop {
-        point = 2.0 * Vec3::new(range.sample(&mut rng), range.sample(&mut rng), 0.0)
-            - Vec3::new(1.0, 1.0, 0.0);
-        if dot(&point, &point) < 1.0 {
-            break;
-        }
+///
+/// Uses Shirley and Chiu's concentric mapping from the unit square to the disk,
+/// which preserves the stratification of the sample values.
+pub fn random_point_in_unit_disk(sampler: &mut Sampler) -> Vec3 {
+    let (u, v) = sampler.get_2d();
+    let (x, y) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if x == 0.0 && y == 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
     }
+    let (radius, theta) = if x.abs() > y.abs() {
+        (x, (PI / 4.0) * (y / x))
+    } else {
+        (y, (PI / 2.0) - (PI / 4.0) * (x / y))
+    };
 
-    point
+    Vec3::new(radius * theta.cos(), radius * theta.sin(), 0.0)
 }
 
 /// Calculates a fractional position on a sphere for a given Ray `hit_point`
@@ -81,3 +96,165 @@ pub fn schlick_approx(cosine: f64, refractive_index: f64) -> f64 {
     r0 = r0 * r0;
     r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
+
+/// Finds the real roots of a polynomial, in increasing order
+///
+/// The roots of the polynomial's derivative (found recursively) split the real line into
+/// intervals on which the polynomial is monotonic, so each interval holds at most one root,
+/// which is then found by bisection. Repeated roots are only reported once.
+/// #### Arguments:
+/// - `coefficients`: The coefficients of the polynomial, from the highest degree term to the constant term
+pub fn solve_polynomial(coefficients: &[f64]) -> Vec<f64> {
+    let coefficients = match coefficients.iter().position(|&c| c != 0.0) {
+        Some(first) => &coefficients[first..],
+        None => return vec![],
+    };
+    let degree = coefficients.len() - 1;
+    match degree {
+        0 => vec![],
+        1 => vec![-coefficients[1] / coefficients[0]],
+        _ => {
+            let derivative: Vec<f64> = coefficients[..degree]
+                .iter()
+                .enumerate()
+                .map(|(i, c)| c * (degree - i) as f64)
+                .collect();
+            // Cauchy's bound on the magnitude of the roots
+            let bound = 1.0
+                + coefficients[1..]
+                    .iter()
+                    .map(|c| (c / coefficients[0]).abs())
+                    .fold(0.0, f64::max);
+            let mut ends = vec![-bound];
+            ends.extend(
+                solve_polynomial(&derivative)
+                    .into_iter()
+                    .filter(|x| x.abs() < bound),
+            );
+            ends.push(bound);
+            // An extremum which touches zero is a repeated root, across which the polynomial
+            // doesn't change sign, so values within rounding error of zero are treated as zero
+            let ends: Vec<(f64, f64)> = ends
+                .into_iter()
+                .map(|x| {
+                    let value = evaluate_polynomial(coefficients, x);
+                    let magnitude = coefficients
+                        .iter()
+                        .fold(0.0, |sum, c| sum * x.abs() + c.abs());
+                    if value.abs() <= 1e-9 * magnitude {
+                        (x, 0.0)
+                    } else {
+                        (x, value)
+                    }
+                })
+                .collect();
+            ends.windows(2)
+                .filter_map(|interval| bisect_polynomial(coefficients, interval[0], interval[1]))
+                .collect()
+        }
+    }
+}
+
+/// Evaluates a polynomial (given from its highest degree coefficient) using Horner's method
+fn evaluate_polynomial(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().fold(0.0, |sum, c| sum * x + c)
+}
+
+/// Finds the root of a polynomial which is monotonic between `low` and `high`, if there is one.
+/// A root lying exactly on `low` is ignored, as it belongs to the preceding interval.
+/// #### Arguments:
+/// - `coefficients`: The coefficients of the polynomial, from the highest degree term to the constant term
+/// - `low`, `high`: The ends of the interval, paired with the polynomial's value at each
+fn bisect_polynomial(
+    coefficients: &[f64],
+    (mut low, low_value): (f64, f64),
+    (mut high, high_value): (f64, f64),
+) -> Option<f64> {
+    if high_value == 0.0 {
+        return Some(high);
+    }
+    if low_value == 0.0 || (low_value > 0.0) == (high_value > 0.0) {
+        return None;
+    }
+    for _ in 0..100 {
+        let mid = 0.5 * (low + high);
+        if mid <= low || mid >= high {
+            break;
+        }
+        if (evaluate_polynomial(coefficients, mid) > 0.0) == (low_value > 0.0) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Some(0.5 * (low + high))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expands `(x - r_0)(x - r_1)...` into its coefficients, from the highest degree term
+    fn from_roots(roots: &[f64]) -> Vec<f64> {
+        roots.iter().fold(vec![1.0], |coefficients, root| {
+            let mut product = coefficients.clone();
+            product.push(0.0);
+            for (i, c) in coefficients.iter().enumerate() {
+                product[i + 1] -= root * c;
+            }
+            product
+        })
+    }
+
+    fn assert_roots(coefficients: &[f64], expected: &[f64], tolerance: f64) {
+        let roots = solve_polynomial(coefficients);
+        assert_eq!(
+            roots.len(),
+            expected.len(),
+            "found {:?}, expected {:?}",
+            roots,
+            expected
+        );
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!(
+                (root - expected).abs() < tolerance,
+                "found {:?}, expected {:?}",
+                roots,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn solves_low_degree_polynomials() {
+        assert_roots(&[2.0, -3.0], &[1.5], 1e-12);
+        assert_roots(&[1.0, 0.0, -4.0], &[-2.0, 2.0], 1e-9);
+        assert_roots(&[1.0, 0.0, 4.0], &[], 0.0);
+        assert_roots(&[5.0], &[], 0.0);
+        assert_roots(&[0.0, 0.0], &[], 0.0);
+    }
+
+    #[test]
+    fn ignores_leading_zero_coefficients() {
+        assert_roots(&[0.0, 0.0, 1.0, -1.0], &[1.0], 1e-12);
+    }
+
+    #[test]
+    fn solves_quartics_with_distinct_roots() {
+        let roots = [-3.0, -0.5, 1.25, 4.0];
+        assert_roots(&from_roots(&roots), &roots, 1e-9);
+        // (x^2 + 1)(x^2 - 9) has only two real roots
+        assert_roots(&[1.0, 0.0, -8.0, 0.0, -9.0], &[-3.0, 3.0], 1e-9);
+        // x^4 + 1 has none
+        assert_roots(&[1.0, 0.0, 0.0, 0.0, 1.0], &[], 0.0);
+    }
+
+    #[test]
+    fn solves_quartics_with_repeated_roots() {
+        assert_roots(&from_roots(&[-2.0, 1.0, 1.0, 3.0]), &[-2.0, 1.0, 3.0], 1e-6);
+        assert_roots(&from_roots(&[-1.0, -1.0, 2.0, 2.0]), &[-1.0, 2.0], 1e-6);
+        assert_roots(&from_roots(&[0.5, 0.5, 0.5, 0.5]), &[0.5], 1e-3);
+        assert_roots(&from_roots(&[-1.7, 0.3, 0.3, 2.1]), &[-1.7, 0.3, 2.1], 1e-6);
+        assert_roots(&from_roots(&[0.1, 0.1, 0.7, 0.7]), &[0.1, 0.7], 1e-6);
+    }
+}
@@ -2,15 +2,54 @@ use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
 use hitable::hit_record::HitRecord;
 use hitable::hitable::Hitable;
 use material::material::Material;
-use material::materials::Isotropic;
-use rand::Rng;
+use material::materials::{DiffuseLight, MediumScattering};
+use medium::blackbody::blackbody_color;
+use medium::phase_function::PhaseFunction;
+use medium::phase_functions::IsotropicPhase;
+use medium::voxel_grid::{MajorantGrid, VoxelGrid};
 use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::INFINITY;
 use std::f64::MAX as FLOAT_MAX;
-use std::f64::MIN as FLOAT_MIN;
 use std::sync::Arc;
 use texture::perlin::Perlin;
 use texture::texture::Texture;
-use vec3::Vec3;
+use texture::textures::{ConstantTexture, VertexColorTexture};
+use vec3::{dot, Vec3};
+
+/// The distance past a boundary's surface from which the next intersection is searched for
+const BOUNDARY_EPSILON: f64 = 0.00001;
+/// The number of voxels (along each axis) covered by each cell of a grid medium's majorants
+const MAJORANT_CELL_SIZE: usize = 8;
+/// The number of temperatures at which blackbody colors are tabulated
+const BLACKBODY_TABLE_SIZE: usize = 256;
+
+/// Finds the first interval of a ray (between `t_start` and `t_max`) which lies inside a
+/// closed boundary, whose surface normals face outwards.
+///
+/// Each intersection is classified as an entry or an exit by its normal, so the ray may
+/// start inside the boundary, and the boundary need not be convex.
+fn next_interval_inside(
+    boundary: &Hitable,
+    ray: &Ray,
+    t_start: f64,
+    t_max: f64,
+    sampler: &mut Sampler,
+) -> Option<(f64, f64)> {
+    let mut t = t_start;
+    while t < t_max {
+        let ref mut rec = HitRecord::new();
+        if !boundary.hit(ray, t, FLOAT_MAX, rec, sampler) {
+            return None;
+        }
+        if dot(&ray.direction, &rec.normal) > 0.0 {
+            // The ray leaves the boundary, so it has been inside since `t`
+            return Some((t, rec.t.min(t_max)));
+        }
+        t = rec.t + BOUNDARY_EPSILON;
+    }
+    None
+}
 
 /// Represents a participating medium with a constant density
 #[derive(Clone)]
@@ -26,8 +65,27 @@ pub struct ConstantMedium {
 }
 
 impl ConstantMedium {
+    /// Constructs a new constant medium which scatters light isotropically
     pub fn new(boundary: Arc<Hitable>, density: f64, texture: Arc<Texture>) -> Self {
-        let phase_func = Arc::new(Isotropic { albedo: texture });
+        ConstantMedium::with_phase_function(boundary, density, texture, Arc::new(IsotropicPhase))
+    }
+
+    /// Constructs a new constant medium
+    /// #### Arguments:
+    /// - `boundary`: The boundary within which the medium is contained
+    /// - `density`: The density of the medium
+    /// - `texture`: The albedo of the medium
+    /// - `phase_function`: The distribution of the directions in which light is scattered
+    pub fn with_phase_function(
+        boundary: Arc<Hitable>,
+        density: f64,
+        texture: Arc<Texture>,
+        phase_function: Arc<PhaseFunction>,
+    ) -> Self {
+        let phase_func = Arc::new(MediumScattering {
+            albedo: texture,
+            phase_function,
+        });
         ConstantMedium {
             boundary,
             density,
@@ -37,40 +95,33 @@ impl ConstantMedium {
 }
 
 impl Hitable for ConstantMedium {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
-        let ref mut rec_1 = HitRecord::new();
-        let ref mut rec_2 = HitRecord::new();
-
-        if self.boundary.hit(ray, FLOAT_MIN, FLOAT_MAX, rec_1) {
-            if self.boundary.hit(ray, rec_1.t + 0.00001, FLOAT_MAX, rec_2) {
-                if rec_1.t < t_min {
-                    rec_1.t = t_min;
-                }
-                if rec_2.t > t_max {
-                    rec_2.t = t_max;
-                }
-                if rec_1.t >= rec_2.t {
-                    return false;
-                }
-                if rec_1.t < 0f64 {
-                    rec_1.t = 0f64;
-                }
-
-                // The probabilistic distance that a Ray would need to travel through the
-                // medium before it would be reflected.
-                let hit_distance = -(1.0 - rand::thread_rng().gen::<f64>()).ln() / self.density;
-                let ray_length = ray.direction.length();
-                // The actual distance that the ray travels through the medium.
-                let dist_inside_boundary = (rec_2.t - rec_1.t) * ray_length;
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let ray_length = ray.direction.length();
+        let mut t = t_min;
+        while let Some((start, end)) = next_interval_inside(&*self.boundary, ray, t, t_max, sampler)
+        {
+            // The probabilistic distance that a Ray would need to travel through the
+            // medium before it would be reflected.
+            let hit_distance = -(1.0 - sampler.get_1d()).ln() / self.density;
+            // The actual distance that the ray travels through the medium.
+            let dist_inside_boundary = (end - start) * ray_length;
 
-                if hit_distance < dist_inside_boundary {
-                    rec.t = rec_1.t + (hit_distance / ray_length);
-                    rec.hit_point = ray.point_at_param(rec.t);
-                    rec.normal = Vec3::new(1.0, 0.0, 0.0); // An arbitrary direction
-                    rec.material = Some(Arc::clone(&self.phase_func));
-                    return true;
-                }
+            if hit_distance < dist_inside_boundary {
+                rec.t = start + (hit_distance / ray_length);
+                rec.hit_point = ray.point_at_param(rec.t);
+                rec.normal = Vec3::new(1.0, 0.0, 0.0); // An arbitrary direction
+                rec.material = Some(Arc::clone(&self.phase_func));
+                return true;
             }
+            // Since free paths are memoryless, the next interval is sampled afresh
+            t = end + BOUNDARY_EPSILON;
         }
 
         false
@@ -98,8 +149,32 @@ pub struct VariableMedium {
 }
 
 impl VariableMedium {
+    /// Constructs a new variable medium which scatters light isotropically
     pub fn new(boundary: Arc<Hitable>, max_density: f64, texture: Arc<Texture>) -> Self {
-        let phase_func = Arc::new(Isotropic { albedo: texture });
+        VariableMedium::with_phase_function(
+            boundary,
+            max_density,
+            texture,
+            Arc::new(IsotropicPhase),
+        )
+    }
+
+    /// Constructs a new variable medium
+    /// #### Arguments:
+    /// - `boundary`: The boundary within which the medium is contained
+    /// - `max_density`: The maximum density of the medium
+    /// - `texture`: The albedo of the medium
+    /// - `phase_function`: The distribution of the directions in which light is scattered
+    pub fn with_phase_function(
+        boundary: Arc<Hitable>,
+        max_density: f64,
+        texture: Arc<Texture>,
+        phase_function: Arc<PhaseFunction>,
+    ) -> Self {
+        let phase_func = Arc::new(MediumScattering {
+            albedo: texture,
+            phase_function,
+        });
         VariableMedium {
             boundary,
             max_density,
@@ -110,50 +185,38 @@ impl VariableMedium {
 }
 
 impl Hitable for VariableMedium {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
-        let ref mut rec_1 = HitRecord::new();
-        let ref mut rec_2 = HitRecord::new();
-
-        if self.boundary.hit(ray, FLOAT_MIN, FLOAT_MAX, rec_1) {
-            if self.boundary.hit(ray, rec_1.t + 0.00001, FLOAT_MAX, rec_2) {
-                if rec_1.t < t_min {
-                    rec_1.t = t_min;
-                }
-                if rec_2.t > t_max {
-                    rec_2.t = t_max;
-                }
-                if rec_1.t >= rec_2.t {
-                    return false;
-                }
-                if rec_1.t < 0f64 {
-                    rec_1.t = 0f64;
-                }
-
-                let ray_length = ray.direction.length();
-                let unit_length_direction = ray.direction / ray_length;
-                // The actual distance that the ray travels through the medium.
-                let dist_inside_boundary = (rec_2.t - rec_1.t) * ray_length;
-                let mut hit_distance = 0.0;
-                let mut rng = rand::thread_rng();
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let ray_length = ray.direction.length();
+        let mut t = t_min;
+        while let Some((start, end)) = next_interval_inside(&*self.boundary, ray, t, t_max, sampler)
+        {
+            // The actual distance that the ray travels through the medium.
+            let dist_inside_boundary = (end - start) * ray_length;
+            let mut hit_distance = 0.0;
 
-                loop {
-                    let x = rng.gen::<f64>();
-                    let y = rng.gen::<f64>();
-                    hit_distance += -(1.0 - x).ln() / self.max_density;
-                    let temp_hit_point = ray.origin + hit_distance * unit_length_direction;
-                    if y < self.noise_func.turbulance(temp_hit_point, 8, 256.0) / self.max_density {
-                        break;
-                    }
+            loop {
+                let (x, y) = sampler.get_2d();
+                hit_distance += -(1.0 - x).ln() / self.max_density;
+                if hit_distance >= dist_inside_boundary {
+                    break;
                 }
-
-                if hit_distance < dist_inside_boundary {
-                    rec.t = rec_1.t + (hit_distance / ray_length);
-                    rec.hit_point = ray.point_at_param(rec.t);
+                let temp_hit_point = ray.point_at_param(start + hit_distance / ray_length);
+                if y < self.noise_func.turbulance(temp_hit_point, 8, 256.0) / self.max_density {
+                    rec.t = start + (hit_distance / ray_length);
+                    rec.hit_point = temp_hit_point;
                     rec.normal = Vec3::new(1.0, 0.0, 0.0); // An arbitrary direction
                     rec.material = Some(Arc::clone(&self.phase_func));
                     return true;
                 }
             }
+            t = end + BOUNDARY_EPSILON;
         }
 
         false
@@ -163,3 +226,295 @@ impl Hitable for VariableMedium {
         self.boundary.bounding_box(start_time, end_time)
     }
 }
+
+/// Describes how a medium emits light as a blackbody (e.g. the flames of a fire), with its
+/// temperature given by a voxel grid
+#[derive(Clone)]
+pub struct BlackbodyEmission {
+    temperature: VoxelGrid,
+    kelvin_range: (f64, f64),
+    intensity: f64,
+    /// The colors of evenly spaced temperatures across the range
+    colors: Vec<Vec3>,
+}
+
+impl BlackbodyEmission {
+    /// Constructs a new description of blackbody emission
+    /// #### Arguments:
+    /// - `temperature`: The grid of temperatures, whose values in [0, 1] span `kelvin_range`
+    ///   (voxels with a value of 0 or less emit no light)
+    /// - `kelvin_range`: The temperatures (in Kelvin) of the grid's values of 0 and 1
+    /// - `intensity`: The radiance emitted at the hottest temperature of the range. Cooler
+    ///   regions emit less light, in proportion to the fourth power of their temperature
+    pub fn new(temperature: VoxelGrid, kelvin_range: (f64, f64), intensity: f64) -> Self {
+        let (min, max) = kelvin_range;
+        let colors = (0..BLACKBODY_TABLE_SIZE)
+            .map(|i| {
+                let t = i as f64 / (BLACKBODY_TABLE_SIZE - 1) as f64;
+                blackbody_color(min + t * (max - min))
+            })
+            .collect();
+        BlackbodyEmission {
+            temperature,
+            kelvin_range,
+            intensity,
+            colors,
+        }
+    }
+
+    /// Calculates the radiance emitted at a point in the unit cube spanned by the grid
+    pub fn emission(&self, point: &Vec3) -> Vec3 {
+        let value = self.temperature.lookup(point, 0);
+        if value <= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let value = value.min(1.0);
+        let (min, max) = self.kelvin_range;
+        let kelvin = min + value * (max - min);
+        let position = value * (BLACKBODY_TABLE_SIZE - 1) as f64;
+        let index = (position as usize).min(BLACKBODY_TABLE_SIZE - 2);
+        let t = position - index as f64;
+        let color = (1.0 - t) * self.colors[index] + t * self.colors[index + 1];
+        self.intensity * (kelvin / max).powi(4) * color
+    }
+}
+
+/// Represents a participating medium whose density varies according to a voxel grid (e.g.
+/// simulated smoke, clouds or fire), with the grid spanning a box.
+///
+/// Rays are traced through the medium by delta tracking, stepping through a coarse grid of
+/// majorants so that sparse regions of the medium are crossed in a few large steps.
+#[derive(Clone)]
+pub struct GridMedium {
+    /// The box spanned by the medium's voxel grids
+    pub bounding_box: AxisAlignedBoundingBox,
+    /// The density of the medium, relative to `density_scale`
+    density: VoxelGrid,
+    /// The density of the medium where the density grid has a value of 1
+    density_scale: f64,
+    /// The largest densities of the grid within each of a set of regions
+    majorants: MajorantGrid,
+    /// The fraction of the light scattered (rather than absorbed) at any given point
+    /// within the medium
+    pub albedo: Arc<Texture>,
+    /// If set, a grid of colors by which the albedo is multiplied
+    pub albedo_grid: Option<VoxelGrid>,
+    /// If set, describes how the medium emits light
+    pub emission: Option<BlackbodyEmission>,
+    /// Scatters light in the collision's albedo (which is given as the hit's color)
+    phase_func: Arc<Material>,
+    /// Emits the collision's light (which is given as the hit's color)
+    emitter: Arc<Material>,
+}
+
+impl GridMedium {
+    /// Constructs a new grid medium which scatters light isotropically
+    pub fn new(
+        bounding_box: AxisAlignedBoundingBox,
+        density: VoxelGrid,
+        density_scale: f64,
+        albedo: Arc<Texture>,
+    ) -> Self {
+        GridMedium::with_phase_function(
+            bounding_box,
+            density,
+            density_scale,
+            albedo,
+            Arc::new(IsotropicPhase),
+        )
+    }
+
+    /// Constructs a new grid medium
+    /// #### Arguments:
+    /// - `bounding_box`: The box spanned by the density grid
+    /// - `density`: The grid of the medium's density, relative to `density_scale`
+    /// - `density_scale`: The density of the medium where the grid has a value of 1
+    /// - `albedo`: The fraction of the light scattered at any given point within the medium
+    /// - `phase_function`: The distribution of the directions in which light is scattered
+    pub fn with_phase_function(
+        bounding_box: AxisAlignedBoundingBox,
+        density: VoxelGrid,
+        density_scale: f64,
+        albedo: Arc<Texture>,
+        phase_function: Arc<PhaseFunction>,
+    ) -> Self {
+        let mut majorant_resolution = [0; 3];
+        for axis in 0..3 {
+            majorant_resolution[axis] =
+                (density.resolution[axis] + MAJORANT_CELL_SIZE - 1) / MAJORANT_CELL_SIZE;
+        }
+        let majorants = MajorantGrid::new(&density, majorant_resolution);
+        let phase_func = Arc::new(MediumScattering {
+            albedo: Arc::new(VertexColorTexture::new(Arc::new(ConstantTexture::new(
+                Vec3::new(1.0, 1.0, 1.0),
+            )))),
+            phase_function,
+        });
+        let emitter = Arc::new(DiffuseLight::new(Arc::new(VertexColorTexture::new(
+            Arc::new(ConstantTexture::new(Vec3::new(0.0, 0.0, 0.0))),
+        ))));
+        GridMedium {
+            bounding_box,
+            density,
+            density_scale,
+            majorants,
+            albedo,
+            albedo_grid: None,
+            emission: None,
+            phase_func,
+            emitter,
+        }
+    }
+
+    /// Clips the interval `t_min..t_max` of a ray to the bounding box
+    fn clip_to_bounds(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let (mut near, mut far) = (t_min, t_max);
+        for axis in 0..3 {
+            let t_0 =
+                (self.bounding_box.min_bound[axis] - ray.origin[axis]) * ray.invert_direction[axis];
+            let t_1 =
+                (self.bounding_box.max_bound[axis] - ray.origin[axis]) * ray.invert_direction[axis];
+            near = near.max(t_0.min(t_1));
+            far = far.min(t_0.max(t_1));
+        }
+        if near < far {
+            Some((near, far))
+        } else {
+            None
+        }
+    }
+
+    /// Records a collision of a ray with a particle of the medium, which either scatters
+    /// the ray or (if the medium is emissive) absorbs it, emitting the medium's light
+    fn record_collision(
+        &self,
+        ray: &Ray,
+        t: f64,
+        point: &Vec3,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) {
+        rec.t = t;
+        rec.hit_point = ray.point_at_param(t);
+        rec.normal = Vec3::new(1.0, 0.0, 0.0); // An arbitrary direction
+        rec.u = 0.0;
+        rec.v = 0.0;
+        let mut albedo = self.albedo.value(0.0, 0.0, &rec.hit_point);
+        if let Some(ref albedo_grid) = self.albedo_grid {
+            albedo *= albedo_grid.lookup_color(point);
+        }
+        if let Some(ref emission) = self.emission {
+            let radiance = emission.emission(point);
+            let absorbed = Vec3::new(
+                (1.0 - albedo.x()).max(0.0),
+                (1.0 - albedo.y()).max(0.0),
+                (1.0 - albedo.z()).max(0.0),
+            );
+            // Absorption is chosen in proportion to the light absorbed, and both outcomes
+            // are reweighted by the chance of choosing them
+            let absorb_probability = ((absorbed.x() + absorbed.y() + absorbed.z()) / 3.0).min(1.0);
+            if radiance.squared_length() > 0.0 && absorb_probability > 0.0 {
+                if sampler.get_1d() < absorb_probability {
+                    rec.color = Some(radiance * absorbed / absorb_probability);
+                    rec.material = Some(Arc::clone(&self.emitter));
+                    return;
+                }
+                albedo /= 1.0 - absorb_probability;
+            }
+        }
+        rec.color = Some(albedo);
+        rec.material = Some(Arc::clone(&self.phase_func));
+    }
+}
+
+impl Hitable for GridMedium {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let (start, end) = match self.clip_to_bounds(ray, t_min, t_max) {
+            Some(interval) => interval,
+            None => return false,
+        };
+        // The ray is traced through the unit cube spanned by the grids
+        let extent = self.bounding_box.max_bound - self.bounding_box.min_bound;
+        let grid_origin = (ray.origin - self.bounding_box.min_bound) / extent;
+        let grid_direction = ray.direction / extent;
+        // Densities are given per unit of distance, so they are scaled by the ray's speed
+        let speed = ray.direction.length();
+
+        // Set up the traversal of the majorant grid's cells along the ray
+        let resolution = self.majorants.resolution;
+        let mut cell = [0; 3];
+        let mut step = [0isize; 3];
+        let mut next_crossing = [INFINITY; 3];
+        let mut crossing_spacing = [INFINITY; 3];
+        for axis in 0..3 {
+            let position =
+                (grid_origin[axis] + start * grid_direction[axis]) * resolution[axis] as f64;
+            cell[axis] = position.floor().max(0.0).min(resolution[axis] as f64 - 1.0) as usize;
+            let cells_per_t = grid_direction[axis] * resolution[axis] as f64;
+            if cells_per_t > 0.0 {
+                step[axis] = 1;
+                next_crossing[axis] = start + ((cell[axis] + 1) as f64 - position) / cells_per_t;
+                crossing_spacing[axis] = 1.0 / cells_per_t;
+            } else if cells_per_t < 0.0 {
+                step[axis] = -1;
+                next_crossing[axis] = start + (cell[axis] as f64 - position) / cells_per_t;
+                crossing_spacing[axis] = -1.0 / cells_per_t;
+            }
+        }
+
+        let mut t = start;
+        loop {
+            let axis = if next_crossing[0] < next_crossing[1] {
+                if next_crossing[0] < next_crossing[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if next_crossing[1] < next_crossing[2] {
+                1
+            } else {
+                2
+            };
+            let cell_end = next_crossing[axis].min(end);
+            let majorant = self.density_scale * self.majorants.majorant(cell);
+            if majorant > 0.0 {
+                loop {
+                    let (x, y) = sampler.get_2d();
+                    // Since free paths are memoryless, a path leaving the cell is restarted
+                    // from the cell's boundary with the next cell's majorant
+                    t += -(1.0 - x).ln() / (majorant * speed);
+                    if t >= cell_end {
+                        break;
+                    }
+                    let point = grid_origin + t * grid_direction;
+                    let density = self.density_scale * self.density.lookup(&point, 0);
+                    if y * majorant < density {
+                        self.record_collision(ray, t, &point, rec, sampler);
+                        return true;
+                    }
+                }
+            }
+            t = cell_end;
+            if t >= end {
+                return false;
+            }
+            let next_cell = cell[axis] as isize + step[axis];
+            if next_cell < 0 || next_cell >= resolution[axis] as isize {
+                return false;
+            }
+            cell[axis] = next_cell as usize;
+            next_crossing[axis] += crossing_spacing[axis];
+        }
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<AxisAlignedBoundingBox> {
+        Some(self.bounding_box)
+    }
+}
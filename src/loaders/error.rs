@@ -0,0 +1,47 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use tobj;
+
+/// An error encountered while loading geometry (e.g. a mesh or a point cloud) from a file
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read
+    Io(io::Error),
+    /// The file's contents are malformed, or use a feature which is not supported
+    Format(String),
+    /// The MTL parser rejected a material library of an OBJ file
+    Obj(tobj::LoadError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Io(ref error) => write!(f, "I/O error: {}", error),
+            LoadError::Format(ref message) => write!(f, "Invalid file: {}", message),
+            LoadError::Obj(ref error) => write!(f, "Invalid MTL file: {}", error),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match *self {
+            LoadError::Io(ref error) => Some(error),
+            LoadError::Format(_) => None,
+            LoadError::Obj(ref error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+impl From<tobj::LoadError> for LoadError {
+    fn from(error: tobj::LoadError) -> Self {
+        LoadError::Obj(error)
+    }
+}
@@ -0,0 +1,659 @@
+use hitable::bvh_node::BvhNode;
+use hitable::deforming_mesh::{DeformingMesh, MeshKeyframes};
+use hitable::hitable::Hitable;
+use hitable::hitable_list::HitableList;
+use hitable::polygon::PolygonMesh;
+use loaders::error::LoadError;
+use loaders::obj::read_obj;
+use loaders::ply::PlyFile;
+use loaders::stl::read_stl;
+use material::material::Material;
+use material::materials::{Lambertian, Principled};
+use mesh::displacement::Displacement;
+use mesh::mesh_data::MeshData;
+use mesh::subdivision::Subdivision;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use texture::texture::Texture;
+use texture::textures::{ConstantTexture, ImageTexture};
+use tobj;
+use vec3::Vec3;
+
+/// The parameters of the PBR extension of the MTL format, any of which make a material
+/// physically based
+const MTL_PBR_PARAMETERS: [&str; 6] = ["Pr", "Pm", "Ps", "Pc", "Pcr", "aniso"];
+
+/// Loads all the meshes defined in an OBJ, PLY or STL file (chosen by the file's
+/// extension), and returns them in a constructed BVH.
+/// #### Arguments:
+/// - `file_path`: The path of the mesh file
+/// - `material`: The material of meshes without a material of their own
+/// - `subdivision`: If set, how each mesh is smoothed by subdivision
+/// - `displacement`: If set, how each mesh is displaced (after being subdivided)
+pub fn load_mesh_file(
+    file_path: &Path,
+    material: Arc<Material>,
+    subdivision: Option<Subdivision>,
+    displacement: Option<Displacement>,
+) -> Result<BvhNode, LoadError> {
+    let extension = file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+    match extension.as_ref().map(|e| e.as_str()) {
+        Some("obj") => load_obj_file(file_path, material, subdivision, displacement),
+        Some("ply") => load_ply_file(file_path, material, subdivision, displacement),
+        Some("stl") => load_stl_file(file_path, material, subdivision, displacement),
+        _ => Err(LoadError::Format(format!(
+            "{:?} is not an OBJ, PLY or STL file",
+            file_path
+        ))),
+    }
+}
+
+/// Loads all the meshes defined in an OBJ file, and returns them in a
+/// constructed BVH.
+/// #### Arguments:
+/// - `file_path`: The path of the OBJ file
+/// - `material`: The material of meshes without a material of their own
+/// - `subdivision`: If set, how each mesh is smoothed by subdivision
+/// - `displacement`: If set, how each mesh is displaced (after being subdivided)
+pub fn load_obj_file(
+    file_path: &Path,
+    material: Arc<Material>,
+    subdivision: Option<Subdivision>,
+    displacement: Option<Displacement>,
+) -> Result<BvhNode, LoadError> {
+    let obj = read_obj(file_path)?;
+    let mut mtl_materials = HashMap::new();
+    for library in obj.material_libraries.iter() {
+        let (materials, names) = tobj::load_mtl(library)?;
+        for (name, index) in names {
+            mtl_materials.insert(name, materials[index].clone());
+        }
+    }
+    let mut meshes: Vec<Arc<Hitable>> = vec![];
+    let mut img_textures = HashMap::new();
+    for obj_mesh in obj.meshes {
+        let mesh_material = match obj_mesh
+            .material
+            .as_ref()
+            .and_then(|name| mtl_materials.get(name))
+        {
+            Some(mtl) => mtl_material(mtl, &mut img_textures),
+            None => Arc::clone(&material),
+        };
+        meshes.push(Arc::new(build_mesh(
+            file_path,
+            obj_mesh.mesh,
+            mesh_material,
+            &subdivision,
+            &displacement,
+        )?));
+    }
+    build_bvh(file_path, meshes)
+}
+
+/// Constructs the material described by an MTL material.
+///
+/// Materials using the PBR extension of the MTL format (roughness `Pr`, metallic `Pm`,
+/// sheen `Ps`, clearcoat `Pc` and its roughness `Pcr`, and anisotropy `aniso`), or which
+/// are transparent (with a dissolve `d` below 1), are mapped onto a `Principled` material,
+/// and all others onto a `Lambertian` one.
+/// #### Arguments:
+/// - `mtl`: The MTL material
+/// - `img_textures`: The image textures already loaded, by their file names
+fn mtl_material(
+    mtl: &tobj::Material,
+    img_textures: &mut HashMap<String, Arc<Texture>>,
+) -> Arc<Material> {
+    // FIXME: This is a hack to prevent trying to map to a transparent image
+    let base_color = if !mtl.diffuse_texture.is_empty() && mtl.dissolve_texture.is_empty() {
+        if !img_textures.contains_key(&mtl.diffuse_texture) {
+            img_textures.insert(
+                mtl.diffuse_texture.to_string(),
+                Arc::new(ImageTexture::new(mtl.diffuse_texture.as_str())) as Arc<Texture>,
+            );
+        }
+        Arc::clone(img_textures.get(&mtl.diffuse_texture).unwrap())
+    } else {
+        Arc::new(ConstantTexture::new(Vec3::new(
+            mtl.diffuse[0] as f64,
+            mtl.diffuse[1] as f64,
+            mtl.diffuse[2] as f64,
+        ))) as Arc<Texture>
+    };
+
+    let is_physically_based = MTL_PBR_PARAMETERS
+        .iter()
+        .any(|key| mtl.unknown_param.contains_key(*key));
+    if !is_physically_based && mtl.dissolve >= 1.0 {
+        return Arc::new(Lambertian { albedo: base_color });
+    }
+    let mut principled = Principled::new(base_color);
+    principled.roughness = match mtl_parameter(mtl, "Pr") {
+        Some(roughness) => roughness,
+        // Otherwise the roughness is estimated from the Phong exponent (whose equivalent
+        // GGX alpha is sqrt(2 / (Ns + 2)), and the roughness is the square root of alpha)
+        None => (2.0 / (mtl.shininess.max(0.0) as f64 + 2.0)).powf(0.25),
+    };
+    principled.metallic = mtl_parameter(mtl, "Pm").unwrap_or(0.0);
+    principled.sheen = mtl_parameter(mtl, "Ps").unwrap_or(0.0);
+    principled.clearcoat = mtl_parameter(mtl, "Pc").unwrap_or(0.0);
+    principled.clearcoat_gloss = 1.0 - mtl_parameter(mtl, "Pcr").unwrap_or(0.0);
+    principled.anisotropic = mtl_parameter(mtl, "aniso").unwrap_or(0.0);
+    principled.transmission = (1.0 - mtl.dissolve as f64).max(0.0).min(1.0);
+    if mtl.optical_density >= 1.0 {
+        principled.refractive_index = mtl.optical_density as f64;
+    }
+    Arc::new(principled)
+}
+
+/// Reads a scalar parameter of an MTL material which `tobj` doesn't recognize (taking
+/// the first component of parameters given as colors)
+fn mtl_parameter(mtl: &tobj::Material, key: &str) -> Option<f64> {
+    mtl.unknown_param
+        .get(key)
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|value| value.parse::<f64>().ok())
+        .map(|value| value.max(0.0).min(1.0))
+}
+
+/// Loads the mesh defined in a PLY file (in any of its encodings), and returns it in a
+/// constructed BVH.
+///
+/// The mesh's vertex normals, texture coordinates and colors are used if the file
+/// provides them. Vertex colors are given to textures as the hit's color attribute,
+/// so they are only visible through a material using a `VertexColorTexture`.
+/// #### Arguments:
+/// - `file_path`: The path of the PLY file
+/// - `material`: The material of the mesh
+/// - `subdivision`: If set, how the mesh is smoothed by subdivision
+/// - `displacement`: If set, how the mesh is displaced (after being subdivided)
+pub fn load_ply_file(
+    file_path: &Path,
+    material: Arc<Material>,
+    subdivision: Option<Subdivision>,
+    displacement: Option<Displacement>,
+) -> Result<BvhNode, LoadError> {
+    let mesh = read_ply_mesh(&PlyFile::open(file_path)?)?;
+    let meshes: Vec<Arc<Hitable>> = vec![Arc::new(build_mesh(
+        file_path,
+        mesh,
+        material,
+        &subdivision,
+        &displacement,
+    )?)];
+    build_bvh(file_path, meshes)
+}
+
+/// Loads the mesh defined in an STL file (in either of its encodings), and returns it in
+/// a constructed BVH.
+/// #### Arguments:
+/// - `file_path`: The path of the STL file
+/// - `material`: The material of the mesh
+/// - `subdivision`: If set, how the mesh is smoothed by subdivision
+/// - `displacement`: If set, how the mesh is displaced (after being subdivided)
+pub fn load_stl_file(
+    file_path: &Path,
+    material: Arc<Material>,
+    subdivision: Option<Subdivision>,
+    displacement: Option<Displacement>,
+) -> Result<BvhNode, LoadError> {
+    let meshes: Vec<Arc<Hitable>> = vec![Arc::new(build_mesh(
+        file_path,
+        read_stl(file_path)?,
+        material,
+        &subdivision,
+        &displacement,
+    )?)];
+    build_bvh(file_path, meshes)
+}
+
+/// Loads a sequence of OBJ files (one per frame) as a single deforming mesh.
+///
+/// Every file must contain the same mesh topology (i.e. the same faces), with only the
+/// positions (and normals) of the vertices differing between them. The texture
+/// coordinates are taken from the first file.
+/// #### Arguments:
+/// - `file_paths`: The paths of the files, in frame order
+/// - `frames_per_second`: The frame rate of the sequence (the first file is shown at time 0)
+/// - `material`: The material of the mesh
+/// - `start_time`: The time at which the camera shutter opens
+/// - `end_time`: The time at which the camera shutter closes
+pub fn load_obj_sequence(
+    file_paths: &[PathBuf],
+    frames_per_second: f64,
+    material: Arc<Material>,
+    start_time: f64,
+    end_time: f64,
+) -> Result<DeformingMesh, LoadError> {
+    let mut keyframes = MeshKeyframes {
+        times: vec![],
+        positions: vec![],
+        normals: Some(vec![]),
+        texture_coords: None,
+    };
+    let mut faces: Vec<Vec<usize>> = vec![];
+    let mut indices: Vec<[usize; 3]> = vec![];
+    for (frame, file_path) in file_paths.iter().enumerate() {
+        // Every mesh in the file is merged into a single list of vertices
+        let mesh = read_obj(file_path)?.merged();
+        if frame == 0 {
+            faces = mesh.faces.clone();
+            indices = mesh.triangles();
+            keyframes.texture_coords = mesh.texture_coords;
+        } else if mesh.positions.len() != keyframes.positions[0].len() || mesh.faces != faces {
+            return Err(LoadError::Format(format!(
+                "The topology of {:?} differs from the first frame of the sequence",
+                file_path
+            )));
+        }
+        // Vertex normals are only used if every frame provides them
+        match mesh.normals {
+            Some(normals) => {
+                if let Some(ref mut keyframe_normals) = keyframes.normals {
+                    keyframe_normals.push(normals);
+                }
+            }
+            None => keyframes.normals = None,
+        }
+        keyframes.times.push(frame as f64 / frames_per_second);
+        keyframes.positions.push(mesh.positions);
+    }
+    DeformingMesh::new(keyframes, &indices, material, start_time, end_time)
+        .map_err(LoadError::Format)
+}
+
+/// Refines a loaded mesh (if requested) and builds it into a PolygonMesh, which must
+/// have at least one triangle (as a BVH can't be built over an empty mesh)
+fn build_mesh(
+    file_path: &Path,
+    mut mesh: MeshData,
+    material: Arc<Material>,
+    subdivision: &Option<Subdivision>,
+    displacement: &Option<Displacement>,
+) -> Result<PolygonMesh, LoadError> {
+    let no_triangles = || LoadError::Format(format!("A mesh of {:?} has no triangles", file_path));
+    if mesh.triangles().is_empty() {
+        return Err(no_triangles());
+    }
+    if let Some(ref subdivision) = *subdivision {
+        mesh = subdivision.apply(&mesh);
+    }
+    if let Some(ref displacement) = *displacement {
+        mesh = displacement.apply(&mesh);
+    }
+    if mesh.triangles().is_empty() {
+        return Err(no_triangles());
+    }
+    Ok(mesh.to_polygon_mesh(material))
+}
+
+/// Builds the BVH of the meshes loaded from a file, which must not be empty
+fn build_bvh(file_path: &Path, meshes: Vec<Arc<Hitable>>) -> Result<BvhNode, LoadError> {
+    if meshes.is_empty() {
+        return Err(LoadError::Format(format!(
+            "{:?} contains no meshes",
+            file_path
+        )));
+    }
+    Ok(BvhNode::new(&mut HitableList { list: meshes }, 0.0, 0.0))
+}
+
+/// Reads the vertices and faces of a PLY file into a mesh
+fn read_ply_mesh(ply: &PlyFile) -> Result<MeshData, LoadError> {
+    let vertices = ply
+        .element("vertex")
+        .ok_or_else(|| LoadError::Format("The PLY file has no vertices".to_string()))?;
+    let vec3s = |names: [&[&str]; 3]| match (
+        vertices.scalars(names[0]),
+        vertices.scalars(names[1]),
+        vertices.scalars(names[2]),
+    ) {
+        (Some((x, scalar)), Some((y, _)), Some((z, _))) => Some((
+            (0..vertices.count)
+                .map(|i| Vec3::new(x[i], y[i], z[i]))
+                .collect::<Vec<Vec3>>(),
+            scalar,
+        )),
+        _ => None,
+    };
+    let positions = match vec3s([&["x"], &["y"], &["z"]]) {
+        Some((positions, _)) => positions,
+        None => {
+            return Err(LoadError::Format(
+                "The vertices of the PLY file have no positions".to_string(),
+            ))
+        }
+    };
+    let faces = match ply
+        .element("face")
+        .and_then(|faces| faces.lists(&["vertex_indices", "vertex_index"]))
+    {
+        Some(faces) if !faces.is_empty() => faces,
+        _ => {
+            return Err(LoadError::Format(
+                "The PLY file has no faces (load it as a point cloud instead)".to_string(),
+            ))
+        }
+    };
+    // Indices are read as floating point values, so they are checked to be valid before
+    // being cast (which would turn negative indices into 0)
+    let is_valid_index =
+        |index: f64| index >= 0.0 && index.fract() == 0.0 && index < positions.len() as f64;
+    for (index, face) in faces.iter().enumerate() {
+        if face.len() < 3 {
+            return Err(LoadError::Format(format!(
+                "Face {} of the PLY file has {} vertices, but a face needs at least three",
+                index,
+                face.len()
+            )));
+        }
+        if face.iter().any(|&vertex| !is_valid_index(vertex)) {
+            return Err(LoadError::Format(format!(
+                "Face {} of the PLY file refers to a missing vertex",
+                index
+            )));
+        }
+    }
+    let faces = faces
+        .iter()
+        .map(|face| face.iter().map(|&index| index as usize).collect())
+        .collect();
+
+    let mut mesh = MeshData::new(positions, faces);
+    mesh.normals = vec3s([&["nx"], &["ny"], &["nz"]]).map(|(normals, _)| normals);
+    mesh.colors = vec3s([
+        &["red", "diffuse_red"],
+        &["green", "diffuse_green"],
+        &["blue", "diffuse_blue"],
+    ])
+    .map(|(colors, scalar)| {
+        let scale = scalar.color_scale();
+        colors.into_iter().map(|color| scale * color).collect()
+    });
+    let u = vertices.scalars(&["u", "s", "texture_u", "texture_s"]);
+    let v = vertices.scalars(&["v", "t", "texture_v", "texture_t"]);
+    if let (Some((u, _)), Some((v, _))) = (u, v) {
+        mesh.texture_coords = Some(u.iter().cloned().zip(v.iter().cloned()).collect());
+    }
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Writes the contents of a test file into the temporary directory
+    fn write_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = env::temp_dir().join(format!("mesh_loader_test_{}", name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn load(name: &str, contents: &[u8]) -> Result<BvhNode, LoadError> {
+        let path = write_file(name, contents);
+        let material = Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture::new(Vec3::new(0.5, 0.5, 0.5))),
+        });
+        let result = load_mesh_file(&path, material, None, None);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    fn ply(faces: &str) -> Vec<u8> {
+        format!(
+            "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\n\
+             property float z\n{}end_header\n0 0 0\n1 0 0\n0 1 0\n",
+            faces
+        )
+        .into_bytes()
+    }
+
+    /// A binary STL file whose header claims `triangles` triangles, holding the same
+    /// triangle as many times as fits in `length` bytes
+    fn binary_stl(triangles: u32, length: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; length];
+        bytes[80..84].copy_from_slice(&[triangles as u8, (triangles >> 8) as u8, 0, 0]);
+        for triangle in bytes[84..].chunks_mut(50) {
+            // Vertices (1, 0, 0) and (0, 1, 0), after the normal and the vertex at the origin
+            triangle[24..28].copy_from_slice(&1.0f32.to_bits().to_le_bytes());
+            triangle[40..44].copy_from_slice(&1.0f32.to_bits().to_le_bytes());
+        }
+        bytes
+    }
+
+    const ASCII_STL: &str = "solid test\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\n\
+                             vertex 1 0 0\nvertex 0 1 0\nendloop\nendfacet\nendsolid test\n";
+
+    #[test]
+    fn loads_valid_ply() {
+        let mut contents = ply("element face 1\nproperty list uchar int vertex_indices\n");
+        contents.extend_from_slice(b"3 0 1 2\n");
+        assert!(load("valid.ply", &contents).is_ok());
+    }
+
+    #[test]
+    fn rejects_ply_without_faces() {
+        assert!(load("no_faces.ply", &ply("")).is_err());
+        let contents = ply("element face 0\nproperty list uchar int vertex_indices\n");
+        assert!(load("empty_faces.ply", &contents).is_err());
+    }
+
+    #[test]
+    fn rejects_ply_with_too_few_face_vertices() {
+        let mut contents = ply("element face 2\nproperty list uchar int vertex_indices\n");
+        contents.extend_from_slice(b"3 0 1 2\n2 0 1\n");
+        match load("degenerate_faces.ply", &contents) {
+            Err(LoadError::Format(message)) => {
+                assert!(message.starts_with("Face 1 "), "{}", message)
+            }
+            _ => panic!("A face with two vertices was accepted"),
+        }
+    }
+
+    #[test]
+    fn reads_ply_vertex_attributes() {
+        let header = |color_type: &str, color_names: [&str; 3]| {
+            format!(
+                "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\n\
+                 property float z\nproperty float nx\nproperty float ny\nproperty float nz\n\
+                 property float u\nproperty float v\nproperty {0} {1}\nproperty {0} {2}\n\
+                 property {0} {3}\nelement face 1\nproperty list uchar int vertex_indices\n\
+                 end_header\n",
+                color_type, color_names[0], color_names[1], color_names[2]
+            )
+        };
+        let contents = header("uchar", ["red", "green", "blue"])
+            + "0 0 0 0 0 1 0 0 255 0 0\n1 0 0 0 1 0 1 0 0 255 0\n\
+               0 1 0 1 0 0 0 1 0 0 51\n3 0 1 2\n";
+        let mesh = read_ply_mesh(&PlyFile::read(contents.as_bytes()).unwrap()).unwrap();
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2]]);
+        assert_eq!(mesh.positions[1].e, [1.0, 0.0, 0.0]);
+        assert_eq!(mesh.normals.as_ref().unwrap()[1].e, [0.0, 1.0, 0.0]);
+        assert_eq!(mesh.texture_coords.as_ref().unwrap()[2], (0.0, 1.0));
+        // Integer colors are scaled by the range of their type
+        let colors = mesh.colors.unwrap();
+        assert_eq!(colors[0].e, [1.0, 0.0, 0.0]);
+        assert!((colors[2] - Vec3::new(0.0, 0.0, 0.2)).length() < 1e-12);
+
+        let contents = header("ushort", ["red", "green", "blue"])
+            + "0 0 0 0 0 1 0 0 65535 0 0\n1 0 0 0 1 0 1 0 0 13107 0\n\
+               0 1 0 1 0 0 0 1 0 0 0\n3 0 1 2\n";
+        let mesh = read_ply_mesh(&PlyFile::read(contents.as_bytes()).unwrap()).unwrap();
+        assert!((mesh.colors.unwrap()[1] - Vec3::new(0.0, 0.2, 0.0)).length() < 1e-12);
+
+        // while floating-point colors are used as they are
+        let contents = header("float", ["diffuse_red", "diffuse_green", "diffuse_blue"])
+            + "0 0 0 0 0 1 0 0 0.5 0.25 1\n1 0 0 0 1 0 1 0 0 0 0\n\
+               0 1 0 1 0 0 0 1 0 0 0\n3 0 1 2\n";
+        let mesh = read_ply_mesh(&PlyFile::read(contents.as_bytes()).unwrap()).unwrap();
+        assert_eq!(mesh.colors.unwrap()[0].e, [0.5, 0.25, 1.0]);
+    }
+
+    #[test]
+    fn rejects_ply_with_invalid_indices() {
+        for (name, face) in &[
+            ("negative_index.ply", "3 -1 1 2\n"),
+            ("missing_index.ply", "3 0 1 3\n"),
+            ("fractional_index.ply", "3 0 1 1.5\n"),
+        ] {
+            let mut contents = ply("element face 1\nproperty list uchar int vertex_indices\n");
+            contents.extend_from_slice(face.as_bytes());
+            assert!(load(name, &contents).is_err(), "{} was loaded", name);
+        }
+    }
+
+    #[test]
+    fn rejects_ply_with_invalid_list_lengths() {
+        for (name, face) in &[
+            ("negative_length.ply", "-3 0 1 2\n"),
+            ("fractional_length.ply", "2.5 0 1 2\n"),
+        ] {
+            let mut contents = ply("element face 1\nproperty list float int vertex_indices\n");
+            contents.extend_from_slice(face.as_bytes());
+            assert!(load(name, &contents).is_err(), "{} was loaded", name);
+        }
+    }
+
+    #[test]
+    fn rejects_ply_with_oversized_counts() {
+        let contents = b"ply\nformat binary_little_endian 1.0\nelement vertex 4000000000\n\
+                         property float x\nproperty float y\nproperty float z\nend_header\n";
+        assert!(load("oversized_element.ply", contents).is_err());
+
+        let mut contents = ply("element face 1\nproperty list uint int vertex_indices\n");
+        contents.extend_from_slice(b"4000000000 0 1 2\n");
+        assert!(load("oversized_list.ply", &contents).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_ply() {
+        let mut contents = ply("element face 1\nproperty list uchar int vertex_indices\n");
+        contents.extend_from_slice(b"3 0 1\n");
+        assert!(load("truncated.ply", &contents).is_err());
+
+        let mut contents = b"ply\nformat binary_little_endian 1.0\nelement vertex 3\n\
+                             property float x\nproperty float y\nproperty float z\nend_header\n"
+            .to_vec();
+        contents.extend_from_slice(&[0u8; 20]);
+        assert!(load("truncated_binary.ply", &contents).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_ply() {
+        assert!(load("empty.ply", b"").is_err());
+        assert!(load("no_format.ply", b"ply\nelement vertex 0\nend_header\n").is_err());
+        let mut contents = ply("element face 1\nproperty list uchar int vertex_indices\n");
+        contents.extend_from_slice(b"3 0 one 2\n");
+        assert!(load("invalid_value.ply", &contents).is_err());
+    }
+
+    #[test]
+    fn loads_valid_stl() {
+        assert!(load("valid_ascii.stl", ASCII_STL.as_bytes()).is_ok());
+        assert!(load("valid_binary.stl", &binary_stl(1, 84 + 50)).is_ok());
+    }
+
+    #[test]
+    fn reads_every_binary_stl_triangle() {
+        // A stack of triangles, each one unit above the last
+        let mut bytes = vec![0u8; 84];
+        bytes[80..84].copy_from_slice(&4u32.to_le_bytes());
+        for z in 0..4 {
+            let mut triangle = [0u8; 50];
+            for (vertex, position) in [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]].iter().enumerate() {
+                let coordinates = [position[0], position[1], z as f32];
+                for (axis, coordinate) in coordinates.iter().enumerate() {
+                    let offset = 12 + 12 * vertex + 4 * axis;
+                    triangle[offset..offset + 4]
+                        .copy_from_slice(&coordinate.to_bits().to_le_bytes());
+                }
+            }
+            bytes.extend_from_slice(&triangle);
+        }
+        let path = write_file("stack.stl", &bytes);
+        let mesh = read_stl(&path);
+        fs::remove_file(&path).unwrap();
+        let mesh = mesh.unwrap();
+        assert_eq!(mesh.faces.len(), 4);
+        assert_eq!(mesh.positions.len(), 12);
+        assert_eq!(mesh.positions[mesh.faces[3][1]].e, [1.0, 0.0, 3.0]);
+    }
+
+    #[test]
+    fn rejects_empty_stl() {
+        assert!(load("empty.stl", b"").is_err());
+        assert!(load("empty_ascii.stl", b"solid test\nendsolid test\n").is_err());
+        assert!(load("empty_binary.stl", &binary_stl(0, 84)).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_stl() {
+        let truncated = &ASCII_STL[..ASCII_STL.find("vertex 0 1 0").unwrap()];
+        assert!(load("truncated_ascii.stl", truncated.as_bytes()).is_err());
+        assert!(load("truncated_binary.stl", &binary_stl(2, 84 + 50)).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_stl() {
+        let malformed = ASCII_STL.replace("vertex 1 0 0", "vertex 1 zero 0");
+        assert!(load("malformed.stl", malformed.as_bytes()).is_err());
+    }
+
+    fn load_sequence(frames: &[&str]) -> Result<DeformingMesh, LoadError> {
+        let paths: Vec<PathBuf> = frames
+            .iter()
+            .enumerate()
+            .map(|(frame, contents)| {
+                write_file(&format!("sequence_{}.obj", frame), contents.as_bytes())
+            })
+            .collect();
+        let material = Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture::new(Vec3::new(0.5, 0.5, 0.5))),
+        });
+        let result = load_obj_sequence(&paths, 24.0, material, 0.0, 1.0);
+        for path in paths.iter() {
+            fs::remove_file(path).unwrap();
+        }
+        result
+    }
+
+    #[test]
+    fn loads_obj_sequence() {
+        let frame_0 = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf 1 2 3\nf 2 4 3\n";
+        let frame_1 = "v 0 0 1\nv 1 0 1\nv 0 1 1\nv 1 1 1\nf 1 2 3\nf 2 4 3\n";
+        assert!(load_sequence(&[frame_0, frame_1]).is_ok());
+    }
+
+    #[test]
+    fn rejects_obj_sequence_with_changing_topology() {
+        let frame_0 = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf 1 2 3\nf 2 4 3\n";
+        // The same vertices, connected differently
+        let frame_1 = "v 0 0 1\nv 1 0 1\nv 0 1 1\nv 1 1 1\nf 1 2 4\nf 1 4 3\n";
+        assert!(load_sequence(&[frame_0, frame_1]).is_err());
+        // A different number of vertices
+        let frame_1 = "v 0 0 1\nv 1 0 1\nv 0 1 1\nf 1 2 3\n";
+        assert!(load_sequence(&[frame_0, frame_1]).is_err());
+    }
+
+    #[test]
+    fn rejects_obj_sequence_with_missing_frames() {
+        assert!(load_sequence(&[]).is_err());
+        let material = Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture::new(Vec3::new(0.5, 0.5, 0.5))),
+        });
+        let missing = [env::temp_dir().join("mesh_loader_test_missing_frame.obj")];
+        assert!(load_obj_sequence(&missing, 24.0, material, 0.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn rejects_obj_without_triangles() {
+        assert!(load("no_faces.obj", b"o empty\nv 0 0 0\nv 1 0 0\nv 0 1 0\n").is_err());
+    }
+}
@@ -0,0 +1,7 @@
+pub mod error;
+pub mod meshes;
+pub mod obj;
+pub mod ply;
+pub mod points;
+pub mod stl;
+pub mod voxels;
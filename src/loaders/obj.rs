@@ -0,0 +1,332 @@
+use loaders::error::LoadError;
+use mesh::mesh_data::MeshData;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::SplitWhitespace;
+use vec3::Vec3;
+
+/// The indices of the position, texture coordinates and normal of a face's vertex
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+/// A group of faces of an OBJ file which share a material
+pub struct ObjMesh {
+    pub mesh: MeshData,
+    /// The name of the material used by the faces (set by `usemtl`), if any
+    pub material: Option<String>,
+}
+
+/// The geometry of an OBJ file
+pub struct ObjFile {
+    /// The file's meshes, which are split wherever a new object, group or material begins
+    pub meshes: Vec<ObjMesh>,
+    /// The paths of the MTL files referenced by the file (relative to the file's directory)
+    pub material_libraries: Vec<PathBuf>,
+}
+
+impl ObjFile {
+    /// Merges all of the file's meshes into one (discarding their materials). Normals and
+    /// texture coordinates are only kept if every mesh has them.
+    pub fn merged(self) -> MeshData {
+        let mut merged = MeshData {
+            normals: Some(vec![]),
+            texture_coords: Some(vec![]),
+            ..MeshData::default()
+        };
+        for obj_mesh in self.meshes {
+            let mesh = obj_mesh.mesh;
+            let offset = merged.positions.len();
+            merged.faces.extend(
+                mesh.faces
+                    .into_iter()
+                    .map(|face| face.into_iter().map(|vertex| offset + vertex).collect()),
+            );
+            merged.positions.extend(mesh.positions);
+            merged.normals = match (merged.normals, mesh.normals) {
+                (Some(mut normals), Some(mesh_normals)) => {
+                    normals.extend(mesh_normals);
+                    Some(normals)
+                }
+                _ => None,
+            };
+            merged.texture_coords = match (merged.texture_coords, mesh.texture_coords) {
+                (Some(mut texcoords), Some(mesh_texcoords)) => {
+                    texcoords.extend(mesh_texcoords);
+                    Some(texcoords)
+                }
+                _ => None,
+            };
+        }
+        merged
+    }
+}
+
+/// Reads the meshes of an OBJ file, keeping each face as the polygon the file defines
+/// (rather than triangulating it), so that quads are subdivided as quads.
+///
+/// A vertex is created for each distinct combination of position, texture coordinates and
+/// normal used by the faces of a mesh. Normals and texture coordinates are only kept if
+/// every vertex of the mesh has them.
+pub fn read_obj(file_path: &Path) -> Result<ObjFile, LoadError> {
+    let reader = BufReader::new(File::open(file_path)?);
+    let directory = file_path.parent().unwrap_or_else(|| Path::new(""));
+    let mut attributes = ObjAttributes::default();
+    let mut faces: Vec<Vec<VertexKey>> = vec![];
+    let mut material: Option<String> = None;
+    let mut obj = ObjFile {
+        meshes: vec![],
+        material_libraries: vec![],
+    };
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let invalid = || {
+            LoadError::Format(format!(
+                "Line {} of {:?} is invalid",
+                line_number + 1,
+                file_path
+            ))
+        };
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => attributes
+                .positions
+                .push(parse_vec3(tokens).ok_or_else(invalid)?),
+            Some("vn") => attributes
+                .normals
+                .push(parse_vec3(tokens).ok_or_else(invalid)?),
+            Some("vt") => {
+                let u = tokens
+                    .next()
+                    .and_then(|u| u.parse().ok())
+                    .ok_or_else(invalid)?;
+                let v = match tokens.next() {
+                    Some(v) => v.parse().map_err(|_| invalid())?,
+                    None => 0.0,
+                };
+                attributes.texcoords.push((u, v));
+            }
+            Some("f") => {
+                let face = tokens
+                    .map(|token| attributes.parse_vertex(token))
+                    .collect::<Option<Vec<VertexKey>>>()
+                    .ok_or_else(invalid)?;
+                if face.len() < 3 {
+                    return Err(LoadError::Format(format!(
+                        "The face on line {} of {:?} has fewer than three vertices",
+                        line_number + 1,
+                        file_path
+                    )));
+                }
+                faces.push(face);
+            }
+            Some(keyword) if keyword == "o" || keyword == "g" || keyword == "usemtl" => {
+                // A new object, group or material ends the current mesh
+                if !faces.is_empty() {
+                    obj.meshes.push(ObjMesh {
+                        mesh: attributes.build_mesh(&faces),
+                        material: material.clone(),
+                    });
+                    faces.clear();
+                }
+                if keyword == "usemtl" {
+                    material = tokens.next().map(|name| name.to_string());
+                }
+            }
+            Some("mtllib") => obj
+                .material_libraries
+                .extend(tokens.map(|name| directory.join(name))),
+            _ => {}
+        }
+    }
+    if !faces.is_empty() {
+        obj.meshes.push(ObjMesh {
+            mesh: attributes.build_mesh(&faces),
+            material,
+        });
+    }
+    Ok(obj)
+}
+
+/// Parses the three coordinates of a position or normal (ignoring any values following them)
+fn parse_vec3(mut tokens: SplitWhitespace) -> Option<Vec3> {
+    let mut coordinates = [0.0; 3];
+    for coordinate in coordinates.iter_mut() {
+        *coordinate = tokens.next()?.parse().ok()?;
+    }
+    Some(Vec3::new(coordinates[0], coordinates[1], coordinates[2]))
+}
+
+/// Parses an index of a face's vertex, which counts from 1 (or, if negative, backwards
+/// from the last of the `count` values read so far)
+fn parse_index(token: &str, count: usize) -> Option<usize> {
+    let index: isize = token.parse().ok()?;
+    let index = if index < 0 {
+        count as isize + index
+    } else {
+        index - 1
+    };
+    if index >= 0 && (index as usize) < count {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+/// The vertex attributes of an OBJ file, which are shared by all of its meshes
+#[derive(Default)]
+struct ObjAttributes {
+    positions: Vec<Vec3>,
+    texcoords: Vec<(f64, f64)>,
+    normals: Vec<Vec3>,
+}
+
+impl ObjAttributes {
+    /// Parses a vertex of a face (given as `v`, `v/vt`, `v//vn` or `v/vt/vn`), whose
+    /// indices must refer to attributes which have already been read
+    fn parse_vertex(&self, token: &str) -> Option<VertexKey> {
+        let mut indices = token.split('/');
+        let position = parse_index(indices.next()?, self.positions.len())?;
+        let mut optional_index = |count| match indices.next() {
+            None | Some("") => Some(None),
+            Some(index) => parse_index(index, count).map(Some),
+        };
+        let texcoord = optional_index(self.texcoords.len())?;
+        let normal = optional_index(self.normals.len())?;
+        Some((position, texcoord, normal))
+    }
+
+    /// Builds a mesh from faces, creating a vertex for each distinct combination of
+    /// attributes they use
+    fn build_mesh(&self, faces: &[Vec<VertexKey>]) -> MeshData {
+        let mut vertices: HashMap<VertexKey, usize> = HashMap::new();
+        let mut keys: Vec<VertexKey> = vec![];
+        let faces = faces
+            .iter()
+            .map(|face| {
+                face.iter()
+                    .map(|&key| {
+                        *vertices.entry(key).or_insert_with(|| {
+                            keys.push(key);
+                            keys.len() - 1
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut mesh = MeshData::new(
+            keys.iter().map(|key| self.positions[key.0]).collect(),
+            faces,
+        );
+        mesh.texture_coords = keys
+            .iter()
+            .map(|key| key.1.map(|index| self.texcoords[index]))
+            .collect();
+        mesh.normals = keys
+            .iter()
+            .map(|key| key.2.map(|index| self.normals[index]))
+            .collect();
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn read(name: &str, contents: &str) -> Result<ObjFile, LoadError> {
+        let path = env::temp_dir().join(format!("obj_loader_test_{}.obj", name));
+        fs::write(&path, contents).unwrap();
+        let result = read_obj(&path);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn keeps_polygon_faces() {
+        let obj = read(
+            "polygons",
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\nv 3 0.5 0\n\
+             f 1 2 3 4\nf 2 5 7 6 3\n",
+        )
+        .unwrap();
+        assert_eq!(obj.meshes.len(), 1);
+        let mesh = &obj.meshes[0].mesh;
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2, 3], vec![1, 4, 5, 6, 2]]);
+        assert_eq!(mesh.positions[5].e, [3.0, 0.5, 0.0]);
+        assert!(mesh.normals.is_none());
+        assert!(mesh.texture_coords.is_none());
+    }
+
+    #[test]
+    fn creates_vertices_for_each_attribute_combination() {
+        let obj = read(
+            "attributes",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\nvn 0 0 -1\n\
+             f 1/1/1 2/2/1 3/3/1\nf 3/3/2 2/2/2 -3/1/-1\n",
+        )
+        .unwrap();
+        let mesh = &obj.meshes[0].mesh;
+        // The faces share positions and texture coordinates, but not normals
+        assert_eq!(mesh.positions.len(), 6);
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2], vec![3, 4, 5]]);
+        assert_eq!(mesh.texture_coords.as_ref().unwrap()[4], (1.0, 0.0));
+        assert_eq!(mesh.normals.as_ref().unwrap()[3].e, [0.0, 0.0, -1.0]);
+    }
+
+    #[test]
+    fn drops_attributes_missing_from_any_vertex() {
+        let obj = read(
+            "partial_attributes",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3\n",
+        )
+        .unwrap();
+        assert!(obj.meshes[0].mesh.normals.is_none());
+    }
+
+    #[test]
+    fn splits_meshes_by_object_and_material() {
+        let obj = read(
+            "groups",
+            "mtllib scene.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\n\
+             o first\nf 1 2 3\nusemtl red\nf 2 4 3\no second\nf 1 2 4\n",
+        )
+        .unwrap();
+        let materials: Vec<Option<String>> = obj
+            .meshes
+            .iter()
+            .map(|mesh| mesh.material.clone())
+            .collect();
+        assert_eq!(
+            materials,
+            vec![None, Some("red".to_string()), Some("red".to_string())]
+        );
+        // Each mesh only has the vertices its faces use
+        assert_eq!(obj.meshes[1].mesh.positions.len(), 3);
+        assert_eq!(
+            obj.material_libraries,
+            vec![env::temp_dir().join("scene.mtl")]
+        );
+        let merged = obj.merged();
+        assert_eq!(merged.positions.len(), 9);
+        assert_eq!(merged.faces[2], vec![6, 7, 8]);
+    }
+
+    #[test]
+    fn rejects_invalid_faces() {
+        let vertices = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+        let line = |face: &str| read("invalid", &format!("{}{}\n", vertices, face));
+        match line("f 1 2") {
+            Err(LoadError::Format(message)) => assert!(message.contains("line 4")),
+            _ => panic!("A face with two vertices was accepted"),
+        }
+        assert!(line("f 1 2 4").is_err());
+        assert!(line("f 0 1 2").is_err());
+        assert!(line("f 1 2 -4").is_err());
+        assert!(line("f 1/1 2/2 3/3").is_err());
+        assert!(line("f 1 2 x").is_err());
+        assert!(read("invalid", "v 0 0\n").is_err());
+    }
+}
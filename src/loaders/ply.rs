@@ -0,0 +1,316 @@
+use loaders::error::LoadError;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// The most values preallocated for an element or a list, as their counts are read from
+/// the file and can't be trusted (larger ones grow as their values are actually read)
+const MAX_PREALLOCATED_VALUES: usize = 1 << 16;
+
+/// The encoding of the data in a PLY file
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// The type of a value stored in a PLY file
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PlyScalar {
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+impl PlyScalar {
+    /// Parses the name of a type (in either its old or its sized form, e.g. `uchar` or `uint8`)
+    fn parse(name: &str) -> Result<Self, LoadError> {
+        match name {
+            "char" | "int8" => Ok(PlyScalar::Char),
+            "uchar" | "uint8" => Ok(PlyScalar::UChar),
+            "short" | "int16" => Ok(PlyScalar::Short),
+            "ushort" | "uint16" => Ok(PlyScalar::UShort),
+            "int" | "int32" => Ok(PlyScalar::Int),
+            "uint" | "uint32" => Ok(PlyScalar::UInt),
+            "float" | "float32" => Ok(PlyScalar::Float),
+            "double" | "float64" => Ok(PlyScalar::Double),
+            _ => Err(LoadError::Format(format!("Unknown PLY type `{}`", name))),
+        }
+    }
+
+    /// The number of bytes taken by a value of this type in a binary PLY file
+    fn size(self) -> usize {
+        match self {
+            PlyScalar::Char | PlyScalar::UChar => 1,
+            PlyScalar::Short | PlyScalar::UShort => 2,
+            PlyScalar::Int | PlyScalar::UInt | PlyScalar::Float => 4,
+            PlyScalar::Double => 8,
+        }
+    }
+
+    /// The factor which maps colors stored as this type into [0, 1]
+    /// (integer colors span the type's range, while floating-point colors are already in [0, 1])
+    pub fn color_scale(self) -> f64 {
+        match self {
+            PlyScalar::Char => 1.0 / 127.0,
+            PlyScalar::UChar => 1.0 / 255.0,
+            PlyScalar::Short => 1.0 / 32767.0,
+            PlyScalar::UShort => 1.0 / 65535.0,
+            PlyScalar::Int => 1.0 / 2_147_483_647.0,
+            PlyScalar::UInt => 1.0 / 4_294_967_295.0,
+            PlyScalar::Float | PlyScalar::Double => 1.0,
+        }
+    }
+
+    /// Decodes a binary value of this type
+    fn decode(self, bytes: &[u8], format: PlyFormat) -> f64 {
+        let mut buffer = [0u8; 8];
+        let size = self.size();
+        buffer[..size].copy_from_slice(&bytes[..size]);
+        // Reorder big endian values so that they can be decoded as little endian ones
+        if format == PlyFormat::BinaryBigEndian {
+            buffer[..size].reverse();
+        }
+        let le_u16 = || u16::from(buffer[0]) | u16::from(buffer[1]) << 8;
+        let le_u32 = || {
+            u32::from(buffer[0])
+                | u32::from(buffer[1]) << 8
+                | u32::from(buffer[2]) << 16
+                | u32::from(buffer[3]) << 24
+        };
+        match self {
+            PlyScalar::Char => f64::from(buffer[0] as i8),
+            PlyScalar::UChar => f64::from(buffer[0]),
+            PlyScalar::Short => f64::from(le_u16() as i16),
+            PlyScalar::UShort => f64::from(le_u16()),
+            PlyScalar::Int => f64::from(le_u32() as i32),
+            PlyScalar::UInt => f64::from(le_u32()),
+            PlyScalar::Float => f64::from(f32::from_bits(le_u32())),
+            PlyScalar::Double => {
+                let mut bits = 0u64;
+                for (i, byte) in buffer.iter().enumerate() {
+                    bits |= u64::from(*byte) << (8 * i);
+                }
+                f64::from_bits(bits)
+            }
+        }
+    }
+}
+
+/// The values of a property of every instance of an element
+#[derive(Clone, Debug)]
+pub enum PlyValues {
+    Scalars(Vec<f64>),
+    Lists(Vec<Vec<f64>>),
+}
+
+/// A property of an element of a PLY file (e.g. the `x` coordinate of each vertex)
+#[derive(Clone, Debug)]
+pub struct PlyProperty {
+    pub name: String,
+    /// The type of the property's values
+    pub scalar: PlyScalar,
+    /// The type of the length of each list, if the property is a list
+    pub list_length: Option<PlyScalar>,
+    pub values: PlyValues,
+}
+
+/// A kind of element of a PLY file (e.g. vertices or faces), along with its data
+#[derive(Clone, Debug)]
+pub struct PlyElement {
+    pub name: String,
+    /// The number of instances of the element
+    pub count: usize,
+    pub properties: Vec<PlyProperty>,
+}
+
+impl PlyElement {
+    /// Finds a property of the element by name
+    pub fn property(&self, name: &str) -> Option<&PlyProperty> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+
+    /// Finds the first of several alternative names for a scalar property,
+    /// returning its values along with its type
+    pub fn scalars(&self, names: &[&str]) -> Option<(&[f64], PlyScalar)> {
+        names
+            .iter()
+            .filter_map(|name| self.property(name))
+            .find_map(|property| match property.values {
+                PlyValues::Scalars(ref values) => Some((&values[..], property.scalar)),
+                PlyValues::Lists(_) => None,
+            })
+    }
+
+    /// Finds the first of several alternative names for a list property
+    pub fn lists(&self, names: &[&str]) -> Option<&[Vec<f64>]> {
+        names
+            .iter()
+            .filter_map(|name| self.property(name))
+            .find_map(|property| match property.values {
+                PlyValues::Lists(ref values) => Some(&values[..]),
+                PlyValues::Scalars(_) => None,
+            })
+    }
+}
+
+/// The contents of a PLY (Polygon File Format) file, in any of its encodings
+#[derive(Clone, Debug)]
+pub struct PlyFile {
+    pub format: PlyFormat,
+    pub elements: Vec<PlyElement>,
+}
+
+impl PlyFile {
+    /// Reads a PLY file from disk
+    pub fn open(file_path: &Path) -> Result<Self, LoadError> {
+        PlyFile::read(BufReader::new(File::open(file_path)?))
+    }
+
+    /// Reads a PLY file from a stream
+    pub fn read<R: BufRead>(mut reader: R) -> Result<Self, LoadError> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        if line.trim() != "ply" {
+            return Err(LoadError::Format("Not a PLY file".to_string()));
+        }
+
+        // Parse the header, which describes the elements (and their properties) in the file
+        let mut format = None;
+        let mut elements: Vec<PlyElement> = vec![];
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(LoadError::Format(
+                    "The PLY header is incomplete".to_string(),
+                ));
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["end_header"] => break,
+                ["format", encoding, _] => {
+                    format = Some(match *encoding {
+                        "ascii" => PlyFormat::Ascii,
+                        "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                        "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                        _ => {
+                            return Err(LoadError::Format(format!(
+                                "Unknown PLY format `{}`",
+                                encoding
+                            )))
+                        }
+                    })
+                }
+                ["element", name, count] => elements.push(PlyElement {
+                    name: name.to_string(),
+                    count: count.parse().map_err(|_| {
+                        LoadError::Format(format!("Invalid element count `{}`", count))
+                    })?,
+                    properties: vec![],
+                }),
+                ["property", "list", length, scalar, name] => {
+                    let property = PlyProperty {
+                        name: name.to_string(),
+                        scalar: PlyScalar::parse(scalar)?,
+                        list_length: Some(PlyScalar::parse(length)?),
+                        values: PlyValues::Lists(vec![]),
+                    };
+                    elements
+                        .last_mut()
+                        .ok_or_else(|| {
+                            LoadError::Format("Property without an element".to_string())
+                        })?
+                        .properties
+                        .push(property);
+                }
+                ["property", scalar, name] => {
+                    let property = PlyProperty {
+                        name: name.to_string(),
+                        scalar: PlyScalar::parse(scalar)?,
+                        list_length: None,
+                        values: PlyValues::Scalars(vec![]),
+                    };
+                    elements
+                        .last_mut()
+                        .ok_or_else(|| {
+                            LoadError::Format("Property without an element".to_string())
+                        })?
+                        .properties
+                        .push(property);
+                }
+                // Comments, object info and blank lines carry no data
+                _ => {}
+            }
+        }
+        let format =
+            format.ok_or_else(|| LoadError::Format("The PLY format is missing".to_string()))?;
+
+        // Read the data of each element in turn
+        let mut ascii_tokens = String::new();
+        if format == PlyFormat::Ascii {
+            reader.read_to_string(&mut ascii_tokens)?;
+        }
+        let mut tokens = ascii_tokens.split_whitespace();
+        let mut bytes = [0u8; 8];
+        let mut read_value = |scalar: PlyScalar| -> Result<f64, LoadError> {
+            if format == PlyFormat::Ascii {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| LoadError::Format("The PLY data ends early".to_string()))?;
+                token
+                    .parse()
+                    .map_err(|_| LoadError::Format(format!("Invalid PLY value `{}`", token)))
+            } else {
+                reader.read_exact(&mut bytes[..scalar.size()])?;
+                Ok(scalar.decode(&bytes, format))
+            }
+        };
+        for element in elements.iter_mut() {
+            for property in element.properties.iter_mut() {
+                let capacity = element.count.min(MAX_PREALLOCATED_VALUES);
+                property.values = match property.list_length {
+                    Some(_) => PlyValues::Lists(Vec::with_capacity(capacity)),
+                    None => PlyValues::Scalars(Vec::with_capacity(capacity)),
+                };
+            }
+            for _ in 0..element.count {
+                for property in element.properties.iter_mut() {
+                    match property.values {
+                        PlyValues::Scalars(ref mut values) => {
+                            values.push(read_value(property.scalar)?)
+                        }
+                        PlyValues::Lists(ref mut values) => {
+                            let length = read_value(property.list_length.unwrap())?;
+                            if !length.is_finite() || length < 0.0 || length.fract() != 0.0 {
+                                return Err(LoadError::Format(format!(
+                                    "Invalid PLY list length `{}`",
+                                    length
+                                )));
+                            }
+                            let length = length as usize;
+                            let mut list = Vec::with_capacity(length.min(MAX_PREALLOCATED_VALUES));
+                            for _ in 0..length {
+                                list.push(read_value(property.scalar)?);
+                            }
+                            values.push(list);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(PlyFile { format, elements })
+    }
+
+    /// Finds an element of the file by name
+    pub fn element(&self, name: &str) -> Option<&PlyElement> {
+        self.elements.iter().find(|element| element.name == name)
+    }
+}
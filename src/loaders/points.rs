@@ -0,0 +1,239 @@
+use hitable::point_cloud::PointCloud;
+use loaders::error::LoadError;
+use loaders::ply::PlyFile;
+use material::material::Material;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+use vec3::Vec3;
+
+/// The columns of a text point file which hold each attribute of a point
+struct Columns {
+    position: [usize; 3],
+    radius: Option<usize>,
+    color: Option<[usize; 3]>,
+}
+
+impl Columns {
+    /// Infers the columns of a file without a header from how many there are:
+    /// `x y z`, `x y z radius`, `x y z r g b` or `x y z radius r g b`
+    fn from_count(count: usize) -> Result<Self, LoadError> {
+        match count {
+            3 | 4 | 6 | 7 => Ok(Columns {
+                position: [0, 1, 2],
+                radius: if count % 3 == 1 { Some(3) } else { None },
+                color: if count >= 6 {
+                    Some([count - 3, count - 2, count - 1])
+                } else {
+                    None
+                },
+            }),
+            _ => Err(LoadError::Format(format!(
+                "Points with {} columns have an unknown layout",
+                count
+            ))),
+        }
+    }
+
+    /// Finds the columns of a file from the names in its header
+    fn from_header(names: &[&str]) -> Result<Self, LoadError> {
+        let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+        let find = |candidates: &[&str]| {
+            names
+                .iter()
+                .position(|name| candidates.contains(&name.as_str()))
+        };
+        let position = match (find(&["x"]), find(&["y"]), find(&["z"])) {
+            (Some(x), Some(y), Some(z)) => [x, y, z],
+            _ => {
+                return Err(LoadError::Format(
+                    "The header does not name the x, y and z columns".to_string(),
+                ))
+            }
+        };
+        let color = match (
+            find(&["red", "r"]),
+            find(&["green", "g"]),
+            find(&["blue", "b"]),
+        ) {
+            (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+            _ => None,
+        };
+        Ok(Columns {
+            position,
+            radius: find(&["radius", "pscale"]),
+            color,
+        })
+    }
+}
+
+/// Loads a point cloud from a PLY, XYZ or CSV file (chosen by the file's extension).
+///
+/// Each point may have its own radius (a `radius` or `pscale` property or column) and color
+/// (`red`, `green` and `blue`, which are scaled into [0, 1] if stored as integers).
+/// XYZ files hold whitespace-separated columns in the order `x y z [radius] [r g b]`, while
+/// CSV files are comma-separated, and may name their columns in a header.
+/// #### Arguments:
+/// - `file_path`: The path of the point file
+/// - `radius`: The radius of points which do not have one of their own
+/// - `material`: The material of the points
+pub fn load_point_cloud(
+    file_path: &Path,
+    radius: f64,
+    material: Arc<Material>,
+) -> Result<PointCloud, LoadError> {
+    let extension = file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+    let (positions, radii, colors) = match extension.as_ref().map(|e| e.as_str()) {
+        Some("ply") => read_ply_points(file_path, radius)?,
+        Some("csv") => read_text_points(file_path, Some(','), radius)?,
+        _ => read_text_points(file_path, None, radius)?,
+    };
+    if positions.is_empty() {
+        return Err(LoadError::Format(format!(
+            "{:?} contains no points",
+            file_path
+        )));
+    }
+    Ok(PointCloud::new(
+        &positions,
+        &radii,
+        colors.as_ref().map(|colors| &colors[..]),
+        material,
+    ))
+}
+
+/// The positions, radii and (optional) colors of a set of points
+type Points = (Vec<Vec3>, Vec<f64>, Option<Vec<Vec3>>);
+
+/// Reads the vertices of a PLY file as points
+fn read_ply_points(file_path: &Path, radius: f64) -> Result<Points, LoadError> {
+    let ply = PlyFile::open(file_path)?;
+    let vertices = ply
+        .element("vertex")
+        .ok_or_else(|| LoadError::Format(format!("{:?} has no vertices", file_path)))?;
+    let (x, y, z) = match (
+        vertices.scalars(&["x"]),
+        vertices.scalars(&["y"]),
+        vertices.scalars(&["z"]),
+    ) {
+        (Some((x, _)), Some((y, _)), Some((z, _))) => (x, y, z),
+        _ => {
+            return Err(LoadError::Format(format!(
+                "The vertices of {:?} have no positions",
+                file_path
+            )))
+        }
+    };
+    let positions = (0..vertices.count)
+        .map(|i| Vec3::new(x[i], y[i], z[i]))
+        .collect();
+    let radii = match vertices.scalars(&["radius", "pscale"]) {
+        Some((radii, _)) => radii.to_vec(),
+        None => vec![radius; vertices.count],
+    };
+    let colors = match (
+        vertices.scalars(&["red", "diffuse_red"]),
+        vertices.scalars(&["green", "diffuse_green"]),
+        vertices.scalars(&["blue", "diffuse_blue"]),
+    ) {
+        (Some((r, scalar)), Some((g, _)), Some((b, _))) => {
+            let scale = scalar.color_scale();
+            Some(
+                (0..vertices.count)
+                    .map(|i| scale * Vec3::new(r[i], g[i], b[i]))
+                    .collect(),
+            )
+        }
+        _ => None,
+    };
+    Ok((positions, radii, colors))
+}
+
+/// Reads the points of a text file, whose columns are split by `separator`
+/// (or by whitespace if there is no separator)
+fn read_text_points(
+    file_path: &Path,
+    separator: Option<char>,
+    radius: f64,
+) -> Result<Points, LoadError> {
+    let reader = BufReader::new(File::open(file_path)?);
+    let mut columns: Option<Columns> = None;
+    let mut positions = vec![];
+    let mut radii = vec![];
+    let mut colors = vec![];
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+        let fields: Vec<&str> = match separator {
+            Some(separator) => line.split(separator).map(|field| field.trim()).collect(),
+            None => line.split_whitespace().collect(),
+        };
+        let values: Vec<f64> = match fields.iter().map(|field| field.parse()).collect() {
+            Ok(values) => values,
+            // A line of names before any points is a header
+            Err(_) if columns.is_none() => {
+                columns = Some(Columns::from_header(&fields)?);
+                continue;
+            }
+            Err(_) => {
+                return Err(LoadError::Format(format!(
+                    "Line {} of {:?} is not a point",
+                    line_number + 1,
+                    file_path
+                )))
+            }
+        };
+        if columns.is_none() {
+            columns = Some(Columns::from_count(values.len())?);
+        }
+        let layout = columns.as_ref().unwrap();
+        let value = |column: usize| {
+            values.get(column).cloned().ok_or_else(|| {
+                LoadError::Format(format!(
+                    "Line {} of {:?} has too few columns",
+                    line_number + 1,
+                    file_path
+                ))
+            })
+        };
+        positions.push(Vec3::new(
+            value(layout.position[0])?,
+            value(layout.position[1])?,
+            value(layout.position[2])?,
+        ));
+        radii.push(match layout.radius {
+            Some(column) => value(column)?,
+            None => radius,
+        });
+        if let Some(color) = layout.color {
+            colors.push(Vec3::new(
+                value(color[0])?,
+                value(color[1])?,
+                value(color[2])?,
+            ));
+        }
+    }
+
+    let colors = if colors.is_empty() {
+        None
+    } else {
+        // Colors written as integers span [0, 255]
+        if colors
+            .iter()
+            .any(|color| color.x() > 1.0 || color.y() > 1.0 || color.z() > 1.0)
+        {
+            for color in colors.iter_mut() {
+                *color /= 255.0;
+            }
+        }
+        Some(colors)
+    };
+    Ok((positions, radii, colors))
+}
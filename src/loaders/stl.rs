@@ -0,0 +1,103 @@
+use loaders::error::LoadError;
+use mesh::mesh_data::MeshData;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use vec3::Vec3;
+
+/// The size of the header of a binary STL file, followed by its number of triangles
+const BINARY_HEADER_SIZE: usize = 84;
+/// The size of each triangle of a binary STL file (a normal, three vertices and a
+/// 16-bit attribute)
+const BINARY_TRIANGLE_SIZE: usize = 50;
+
+/// Reads a mesh from an STL file, in either its ASCII or binary encoding.
+///
+/// STL files store every triangle separately, so their vertices are welded to reconnect
+/// the mesh (facet normals are ignored, as they are implied by each triangle's winding).
+pub fn read_stl(file_path: &Path) -> Result<MeshData, LoadError> {
+    let mut bytes = vec![];
+    File::open(file_path)?.read_to_end(&mut bytes)?;
+    let positions = if is_binary(&bytes) {
+        read_binary(&bytes)
+    } else {
+        read_ascii(&String::from_utf8_lossy(&bytes))?
+    };
+    if positions.is_empty() {
+        return Err(LoadError::Format(format!(
+            "{:?} contains no triangles",
+            file_path
+        )));
+    }
+    let faces = (0..positions.len() / 3)
+        .map(|i| vec![3 * i, 3 * i + 1, 3 * i + 2])
+        .collect();
+    let mut mesh = MeshData::new(positions, faces);
+    mesh.weld_vertices();
+    Ok(mesh)
+}
+
+/// Whether the contents of an STL file are binary, which is decided by whether its size
+/// matches the number of triangles in its header (as binary files may also begin with
+/// the word `solid`)
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < BINARY_HEADER_SIZE {
+        return false;
+    }
+    let count = read_u32(&bytes[80..84]) as usize;
+    bytes.len() == BINARY_HEADER_SIZE + count * BINARY_TRIANGLE_SIZE
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from(bytes[0])
+        | u32::from(bytes[1]) << 8
+        | u32::from(bytes[2]) << 16
+        | u32::from(bytes[3]) << 24
+}
+
+/// Reads the vertices of each triangle of a binary STL file
+fn read_binary(bytes: &[u8]) -> Vec<Vec3> {
+    let read_vec3 = |bytes: &[u8]| {
+        Vec3::new(
+            f64::from(f32::from_bits(read_u32(&bytes[0..4]))),
+            f64::from(f32::from_bits(read_u32(&bytes[4..8]))),
+            f64::from(f32::from_bits(read_u32(&bytes[8..12]))),
+        )
+    };
+    bytes[BINARY_HEADER_SIZE..]
+        .chunks(BINARY_TRIANGLE_SIZE)
+        .flat_map(|triangle| {
+            // Skip the facet normal, and the attribute following the vertices
+            vec![
+                read_vec3(&triangle[12..24]),
+                read_vec3(&triangle[24..36]),
+                read_vec3(&triangle[36..48]),
+            ]
+        })
+        .collect()
+}
+
+/// Reads the vertices of each triangle of an ASCII STL file
+fn read_ascii(text: &str) -> Result<Vec<Vec3>, LoadError> {
+    let mut positions = vec![];
+    let mut tokens = text.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token != "vertex" {
+            continue;
+        }
+        let mut coordinates = [0.0; 3];
+        for coordinate in coordinates.iter_mut() {
+            let value = tokens.next().unwrap_or("");
+            *coordinate = value
+                .parse()
+                .map_err(|_| LoadError::Format(format!("Invalid STL coordinate `{}`", value)))?;
+        }
+        positions.push(Vec3::new(coordinates[0], coordinates[1], coordinates[2]));
+    }
+    if positions.len() % 3 != 0 {
+        return Err(LoadError::Format(
+            "An STL facet does not have three vertices".to_string(),
+        ));
+    }
+    Ok(positions)
+}
@@ -0,0 +1,99 @@
+use loaders::error::LoadError;
+use medium::voxel_grid::VoxelGrid;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// The size of the header of a Mitsuba volume file
+const VOL_HEADER_SIZE: usize = 48;
+
+/// Loads a voxel grid from a file of raw 32-bit (little-endian) floats, ordered by x, then y,
+/// then z, with the channels of each voxel adjacent
+/// #### Arguments:
+/// - `file_path`: The path of the raw grid file
+/// - `resolution`: The number of voxels along the x, y and z axes
+/// - `channels`: The number of values stored in each voxel
+pub fn read_raw_grid(
+    file_path: &Path,
+    resolution: [usize; 3],
+    channels: usize,
+) -> Result<VoxelGrid, LoadError> {
+    let bytes = read_bytes(file_path)?;
+    let count = resolution[0] * resolution[1] * resolution[2] * channels;
+    if count == 0 || bytes.len() != 4 * count {
+        return Err(LoadError::Format(format!(
+            "{:?} holds {} bytes, but a {}x{}x{} grid of {} channel(s) needs {}",
+            file_path,
+            bytes.len(),
+            resolution[0],
+            resolution[1],
+            resolution[2],
+            channels,
+            4 * count
+        )));
+    }
+    Ok(VoxelGrid::new(resolution, channels, read_floats(&bytes)))
+}
+
+/// Loads a voxel grid from a Mitsuba volume (`.vol`) file, which stores a grid's resolution
+/// and number of channels in its header.
+///
+/// The bounding box stored in the header is ignored, as the grid is placed by the medium
+/// using it.
+pub fn read_vol_grid(file_path: &Path) -> Result<VoxelGrid, LoadError> {
+    let bytes = read_bytes(file_path)?;
+    if bytes.len() < VOL_HEADER_SIZE || &bytes[0..3] != b"VOL" {
+        return Err(LoadError::Format(format!(
+            "{:?} is not a volume file",
+            file_path
+        )));
+    }
+    if bytes[3] != 3 {
+        return Err(LoadError::Format(format!(
+            "Version {} volume files are not supported",
+            bytes[3]
+        )));
+    }
+    let header: Vec<usize> = bytes[4..24]
+        .chunks(4)
+        .map(|chunk| read_u32(chunk) as usize)
+        .collect();
+    // Encoding 1 is dense 32-bit floats (other encodings store 16-bit floats or bytes)
+    if header[0] != 1 {
+        return Err(LoadError::Format(format!(
+            "Volume files with encoding {} are not supported",
+            header[0]
+        )));
+    }
+    let resolution = [header[1], header[2], header[3]];
+    let channels = header[4];
+    let count = resolution[0] * resolution[1] * resolution[2] * channels;
+    if count == 0 || bytes.len() < VOL_HEADER_SIZE + 4 * count {
+        return Err(LoadError::Format(format!(
+            "{:?} is missing some of its voxels",
+            file_path
+        )));
+    }
+    let values = read_floats(&bytes[VOL_HEADER_SIZE..VOL_HEADER_SIZE + 4 * count]);
+    Ok(VoxelGrid::new(resolution, channels, values))
+}
+
+fn read_bytes(file_path: &Path) -> Result<Vec<u8>, LoadError> {
+    let mut bytes = vec![];
+    File::open(file_path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from(bytes[0])
+        | u32::from(bytes[1]) << 8
+        | u32::from(bytes[2]) << 16
+        | u32::from(bytes[3]) << 24
+}
+
+fn read_floats(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks(4)
+        .map(|chunk| f32::from_bits(read_u32(chunk)))
+        .collect()
+}
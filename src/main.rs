@@ -1,57 +1,160 @@
 extern crate image;
 extern crate indicatif;
 extern crate num_cpus;
-extern crate rand;
 extern crate tobj;
 
+pub mod animation;
 pub mod bounding_boxes;
 pub mod camera;
+pub mod film;
 pub mod hitable;
+pub mod loaders;
 pub mod material;
+pub mod medium;
+pub mod mesh;
 pub mod ray;
+pub mod sampler;
+pub mod sdf;
 pub mod texture;
 pub mod vec3;
 
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::distributions::{Distribution, Uniform};
-use rand::Rng;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::f64::MAX as FLOAT_MAX;
 use std::fs::File;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
 use std::sync::Arc;
 use std::thread;
 
-use camera::Camera;
+use animation::frames::FrameRange;
+use animation::track::{Interpolation, Keyframe, Track};
+use bounding_boxes::axis_aligned::AxisAlignedBoundingBox;
+use camera::camera::Camera;
+use camera::cameras::PerspectiveCamera;
+use film::adaptive::{self, AdaptiveSampling, PixelVariance};
+use film::aov::{AovBuffer, AovPixel, AovSample};
+use film::film::{Film, FilmSample};
+use film::filter::Filter;
+use film::filters::MitchellFilter;
+use hitable::animated_transform::AnimatedTransform;
 use hitable::bvh_node::BvhNode;
+use hitable::csg::Csg;
+use hitable::curves::{CurveBasis, CurveType, Curves};
+use hitable::deforming_mesh::{DeformingMesh, MeshKeyframes};
 use hitable::flip_normals::FlipNormals;
 use hitable::hit_record::HitRecord;
 use hitable::hitable::Hitable;
 use hitable::hitable_list::HitableList;
+use hitable::implicit_surface::ImplicitSurface;
+use hitable::medium_boundary::MediumBoundary;
 use hitable::moving_sphere::MovingSphere;
-use hitable::polygon::{Polygon, PolygonMesh};
-use hitable::rectangles::{AxisAlignedBlock, XYRect, XZRect, YZRect};
+use hitable::object_tag::ObjectTag;
+use hitable::point_cloud::PointCloud;
+use hitable::quadrics::{Cone, Cylinder, Disk, Hyperboloid, Paraboloid};
+use hitable::rectangles::{AxisAlignedBlock, Quad, XYRect, XZRect, YZRect};
 use hitable::sphere::Sphere;
+use hitable::subsurface::Subsurface;
+use hitable::torus::Torus;
 use hitable::transformations::{RotateY, Translate};
-use hitable::volumes::{ConstantMedium, VariableMedium};
+use hitable::volumes::{BlackbodyEmission, ConstantMedium, GridMedium, VariableMedium};
+use loaders::meshes::load_obj_file;
 use material::material::Material;
-use material::materials::{Dielectric, DiffuseLight, Lambertian, Metal};
+use material::materials::{
+    Dielectric, DiffuseLight, Hair, Lambertian, LayeredMaterial, Metal, MixMaterial, Principled,
+};
+use medium::media::HomogeneousMedium;
+use medium::medium_stack::MediumStack;
+use medium::phase_function::PhaseFunction;
+use medium::phase_functions::{
+    DoubleHenyeyGreenstein, HenyeyGreenstein, IsotropicPhase, Mie, Rayleigh,
+};
+use medium::voxel_grid::VoxelGrid;
+use mesh::subdivision::{Subdivision, SubdivisionScheme};
 use ray::Ray;
-use texture::texture::Texture;
-use texture::textures::{CheckerTexture, ConstantTexture, ImageTexture, NoiseTexture};
-use vec3::Vec3;
+use sampler::rng;
+use sampler::sampler::Sampler;
+use sampler::samplers::CorrelatedMultiJitteredSampler;
+use sdf::distance_functions::{
+    Offset, Repeat, Scale, SdfMandelbulb, SdfRoundedBox, SdfSphere, SdfTorus, SmoothUnion, Twist,
+};
+use texture::perlin::Perlin;
+use texture::textures::{
+    AnimatedTexture, CheckerTexture, ConstantTexture, NoiseTexture, VertexColorTexture,
+};
+use vec3::{unit_vector, Vec3};
 
 static MAX_DEPTH: i32 = 5;
+/// The most invisible medium boundaries a path may pass through (which aren't bounces)
+static MAX_BOUNDARY_CROSSINGS: i32 = 64;
 
-/// Calculates a final color value for a given Ray
-fn get_color(ray: &Ray, world: &BvhNode, depth: i32) -> Vec3 {
-    let ref mut rec = HitRecord::new();
-    if world.hit(ray, 0.00001, FLOAT_MAX, rec) {
+/// Calculates a final color value for a given Ray, along with the auxiliary
+/// output variables gathered along its path
+/// #### Arguments:
+/// - `ray`: The ray leaving the camera
+/// - `world`: The scene
+/// - `media`: The media enclosing the camera
+/// - `sampler`: Provides the sample values of the path
+fn get_color(
+    ray: &Ray,
+    world: &BvhNode,
+    media: &MediumStack,
+    sampler: &mut Sampler,
+) -> (Vec3, AovSample) {
+    let mut aov = AovSample::new();
+    let mut color = Vec3::new(0.0, 0.0, 0.0);
+    // The accumulated attenuation of every scattering event along the path so far
+    let mut throughput = Vec3::new(1.0, 1.0, 1.0);
+    let mut ray = *ray;
+    // The media through which the ray is currently travelling
+    let mut media = media.clone();
+    let mut depth = 0;
+    let mut boundary_crossings = 0;
+    loop {
+        let ref mut rec = HitRecord::new();
+        let did_hit = world.hit(&ray, 0.00001, FLOAT_MAX, rec, sampler);
+        rec.time = ray.time;
+        if let Some(current_medium) = media.current().cloned() {
+            let t_max = if did_hit { rec.t } else { FLOAT_MAX };
+            let interaction = current_medium.sample_interaction(&ray, t_max, sampler);
+            let contribution = throughput * interaction.emission;
+            color += contribution;
+            aov.record_light(depth, contribution);
+            throughput *= interaction.weight;
+            if let Some(t) = interaction.t {
+                // The ray scatters within the medium, before reaching the next surface
+                if depth == MAX_DEPTH {
+                    break;
+                }
+                let (direction, _pdf) = current_medium
+                    .phase_function()
+                    .sample(&unit_vector(ray.direction), sampler);
+                ray = Ray::new(ray.point_at_param(t), direction, ray.time);
+                depth += 1;
+                continue;
+            }
+        }
+        if !did_hit {
+            break;
+        }
+        let is_crossed = match rec.medium {
+            // Invisible boundaries, and boundaries hidden within a medium of a higher
+            // priority, don't scatter the ray, so crossing them isn't counted as a bounce
+            Some(ref entry) => rec.material.is_none() || media.is_false_boundary(entry),
+            None => false,
+        };
+        if is_crossed {
+            boundary_crossings += 1;
+            if boundary_crossings > MAX_BOUNDARY_CROSSINGS {
+                break;
+            }
+            media.cross(rec, &ray.direction, &ray.direction);
+            ray = Ray::new(rec.hit_point, ray.direction, ray.time);
+            continue;
+        }
         let ((scattered_ray, attenuation, did_scatter), emitted_light) = match rec.material {
-            Some(ref mat) => (
-                mat.scatter(ray, rec),
-                mat.emit(rec.u, rec.v, &rec.hit_point),
-            ),
+            Some(ref mat) => (mat.scatter(&ray, rec, sampler), mat.emit_at_hit(rec)),
             None => (
                 (
                     Ray::new(ray.origin, ray.direction, 0.0),
@@ -61,20 +164,26 @@ fn get_color(ray: &Ray, world: &BvhNode, depth: i32) -> Vec3 {
                 Vec3::new(0.0, 0.0, 0.0),
             ),
         };
+        let contribution = throughput * emitted_light;
+        color += contribution;
+        if depth == 0 {
+            aov.record_hit(rec);
+        }
+        aov.record_light(depth, contribution);
         if depth < MAX_DEPTH && did_scatter {
-            return emitted_light + attenuation * get_color(&scattered_ray, world, depth + 1);
+            throughput *= attenuation;
+            media.cross(rec, &ray.direction, &scattered_ray.direction);
+            ray = scattered_ray;
+            depth += 1;
         } else {
-            return emitted_light;
+            break;
         }
-    } else {
-        return Vec3::new(0.0, 0.0, 0.0);
     }
+
+    (color, aov)
 }
 
-fn create_rand_scene(
-    mut rng: rand::prelude::ThreadRng,
-    range: &rand::distributions::Uniform<f64>,
-) -> BvhNode {
+fn create_rand_scene() -> BvhNode {
     #![allow(dead_code)]
     let mut sphere_list = vec![Arc::new(Sphere {
         center: Vec3::new(0.0, -1000.0, 0.0),
@@ -89,11 +198,11 @@ fn create_rand_scene(
 
     for a in -11..11 {
         for b in -11..11 {
-            let material_choice = range.sample(&mut rng);
+            let material_choice = rng::scene_random();
             let center = Vec3::new(
-                a as f64 + 0.9 * range.sample(&mut rng),
+                a as f64 + 0.9 * rng::scene_random(),
                 0.2,
-                b as f64 + 0.9 * range.sample(&mut rng),
+                b as f64 + 0.9 * rng::scene_random(),
             );
             if ((center - Vec3::new(4.0, 0.2, 0.0)).length() <= 0.9)
                 || ((center - Vec3::new(0.0, 0.2, 1.0)).length() <= 0.9)
@@ -106,15 +215,15 @@ fn create_rand_scene(
                     // Matte
                     Arc::new(MovingSphere {
                         start_center: center,
-                        end_center: center + Vec3::new(0.0, 0.5 * range.sample(&mut rng), 0.0),
+                        end_center: center + Vec3::new(0.0, 0.5 * rng::scene_random(), 0.0),
                         start_time: 0.0,
                         end_time: 1.0,
                         radius: 0.2,
                         material: Arc::new(Lambertian {
                             albedo: Arc::new(ConstantTexture::new(Vec3::new(
-                                range.sample(&mut rng) * range.sample(&mut rng),
-                                range.sample(&mut rng) * range.sample(&mut rng),
-                                range.sample(&mut rng) * range.sample(&mut rng),
+                                rng::scene_random() * rng::scene_random(),
+                                rng::scene_random() * rng::scene_random(),
+                                rng::scene_random() * rng::scene_random(),
                             ))),
                         }),
                     })
@@ -125,11 +234,11 @@ fn create_rand_scene(
                         radius: 0.2,
                         material: Arc::new(Metal::new(
                             Arc::new(ConstantTexture::new(Vec3::new(
-                                0.5 * (1.0 + range.sample(&mut rng)),
-                                0.5 * (1.0 + range.sample(&mut rng)),
-                                0.5 * (1.0 + range.sample(&mut rng)),
+                                0.5 * (1.0 + rng::scene_random()),
+                                0.5 * (1.0 + rng::scene_random()),
+                                0.5 * (1.0 + rng::scene_random()),
                             ))),
-                            0.5 * range.sample(&mut rng),
+                            0.5 * rng::scene_random(),
                         )),
                     })
                 } else if material_choice < 0.95 {
@@ -173,7 +282,9 @@ fn create_rand_scene(
         )),
     }));
 
-    let ref mut list = HitableList { list: sphere_list };
+    let ref mut list = HitableList {
+        list: ObjectTag::tag_all(sphere_list),
+    };
     BvhNode::new(list, 0.0, 1.0)
 }
 
@@ -264,10 +375,26 @@ fn create_cornell_box() -> BvhNode {
         }),
     ));
 
+    // The low-poly teapot is smoothed by two levels of Loop subdivision
     let teapot = load_obj_file(
         &Path::new("object-files/teapot.obj"),
         Arc::new(Dielectric::new(1.54)),
-    );
+        Some(Subdivision::new(SubdivisionScheme::Loop, 2)),
+        None,
+    )
+    .expect("Failed to load the teapot");
+    // or given a hammered finish by displacing it with a noise texture
+    // let teapot = load_obj_file(
+    //     &Path::new("object-files/teapot.obj"),
+    //     Arc::new(Dielectric::new(1.54)),
+    //     Some(Subdivision::new(SubdivisionScheme::Loop, 1)),
+    //     Some(mesh::displacement::Displacement::new(
+    //         Arc::new(NoiseTexture::new(4.0, 7)),
+    //         0.1,
+    //         0.05,
+    //     )),
+    // )
+    // .expect("Failed to load the teapot");
     let list: Vec<Arc<Hitable>> = vec![
         left_wall,
         Arc::new(FlipNormals::new(right_wall)),
@@ -280,7 +407,13 @@ fn create_cornell_box() -> BvhNode {
         pedestal,
     ];
 
-    BvhNode::new(&mut HitableList { list }, 0.0, 0.0)
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        0.0,
+    )
 }
 
 fn create_debug_scene() -> BvhNode {
@@ -298,16 +431,23 @@ fn create_debug_scene() -> BvhNode {
         Arc::new(Lambertian {
             albedo: Arc::new(ConstantTexture::new(Vec3::new(0.6, 0.4, 0.7528))),
         }),
-    );
+        None,
+        None,
+    )
+    .expect("Failed to load the house");
 
     let list: Vec<Arc<Hitable>> = vec![light, Arc::new(RotateY::new(Arc::new(house), 15.0))];
-    BvhNode::new(&mut HitableList { list }, 0.0, 1.0)
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
 }
 
 fn create_final_scene() -> BvhNode {
     #![allow(dead_code)]
-    let mut rng = rand::thread_rng();
-
     // Ground definition
     let num_boxes = 20;
     let mut box_list: Vec<Arc<Hitable>> = vec![];
@@ -318,7 +458,11 @@ fn create_final_scene() -> BvhNode {
         for j in 0..num_boxes {
             let width = 100.0;
             let (x_0, y_0, z_0) = (-1000.0 + i as f64 * width, 0.0, -1000.0 + j as f64 * width);
-            let (x_1, y_1, z_1) = (x_0 + width, 100.0 * (0.01 + rng.gen::<f64>()), z_0 + width);
+            let (x_1, y_1, z_1) = (
+                x_0 + width,
+                100.0 * (0.01 + rng::scene_random()),
+                z_0 + width,
+            );
             box_list.push(Arc::new(AxisAlignedBlock::new(
                 Vec3::new(x_0, y_0, z_0),
                 Vec3::new(x_1, y_1, z_1),
@@ -371,15 +515,16 @@ fn create_final_scene() -> BvhNode {
     });
 
     // Volume definitions
-    let subsurface_boundary = Arc::new(Sphere {
-        material: Arc::new(Dielectric::new(1.5)),
-        center: Vec3::new(360.0, 150.0, 145.0),
-        radius: 70.0,
-    }) as Arc<Hitable>;
-    let subsurface_volume = Arc::new(ConstantMedium::new(
-        Arc::clone(&subsurface_boundary),
-        0.5,
+    // A translucent blue sphere, through which light scatters beneath its surface
+    let subsurface_ball = Arc::new(Subsurface::new(
+        Arc::new(Sphere {
+            material: Arc::new(Dielectric::new(1.5)), // replaced by the subsurface material
+            center: Vec3::new(360.0, 150.0, 145.0),
+            radius: 70.0,
+        }),
         Arc::new(ConstantTexture::new(Vec3::new(0.2, 0.4, 0.9))),
+        Arc::new(ConstantTexture::new(Vec3::new(20.0, 20.0, 20.0))),
+        1.5,
     ));
     let mist_boundary = Arc::new(Sphere {
         material: Arc::new(Dielectric::new(1.5)), // arbitrary material
@@ -401,9 +546,9 @@ fn create_final_scene() -> BvhNode {
             Arc::new(Sphere {
                 material: Arc::new(white.clone()),
                 center: Vec3::new(
-                    165.0 * rng.gen::<f64>(),
-                    165.0 * rng.gen::<f64>(),
-                    165.0 * rng.gen::<f64>(),
+                    165.0 * rng::scene_random(),
+                    165.0 * rng::scene_random(),
+                    165.0 * rng::scene_random(),
                 ),
                 radius: 10.0,
             }) as Arc<Hitable>
@@ -428,14 +573,17 @@ fn create_final_scene() -> BvhNode {
         metal_ball,
         marble_ball,
         sphere_cube,
-        // Note that the combination of the dielectric sphere and the
-        // constant volume results in an emulation of a subsurface material.
-        subsurface_boundary,
-        subsurface_volume,
+        subsurface_ball,
         mist,
         Arc::new(BvhNode::new(&mut HitableList { list: box_list }, 0.0, 1.0)),
     ];
-    BvhNode::new(&mut HitableList { list }, 0.0, 1.0)
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
 }
 
 /// Recreates the "wada2" scene from smallpt (http://www.kevinbeason.com/smallpt/)
@@ -498,142 +646,1461 @@ fn wada() -> BvhNode {
             )),
         }),
     ];
-    BvhNode::new(&mut HitableList { list }, 0.0, 0.0)
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        0.0,
+    )
 }
 
-/// Loads all the meshes defined in an OBJ file, and returns them in a
-/// constructed BVH.
-fn load_obj_file(file_path: &Path, mut material: Arc<Material>) -> BvhNode {
-    match tobj::load_obj(file_path) {
-        Ok((models, materials)) => {
-            let mut meshes: Vec<Arc<Hitable>> = vec![];
-            let mut img_textures = HashMap::new();
-            for model in models {
-                let mesh = model.mesh;
-                if mesh.material_id.is_some() {
-                    let mtl = &materials[mesh.material_id.unwrap()];
-                    // FIXME: This is a hack to prevent trying to map to a transparent image
-                    if !mtl.diffuse_texture.is_empty() && mtl.dissolve_texture.is_empty() {
-                        if !img_textures.contains_key(&mtl.diffuse_texture) {
-                            img_textures.insert(
-                                mtl.diffuse_texture.to_string(),
-                                Arc::new(ImageTexture::new(mtl.diffuse_texture.as_str()))
-                                    as Arc<Texture>,
-                            );
-                        }
-                        material = Arc::new(Lambertian {
-                            albedo: Arc::clone(img_textures.get(&mtl.diffuse_texture).unwrap()),
-                        });
-                    } else {
-                        material = Arc::new(Lambertian {
-                            albedo: Arc::new(ConstantTexture::new(Vec3::new(
-                                mtl.diffuse[0] as f64,
-                                mtl.diffuse[1] as f64,
-                                mtl.diffuse[2] as f64,
-                            ))),
-                        });
-                    }
-                }
-                // all vertices in the mesh
-                let vertices: Vec<Vec3> = mesh
-                    .positions
-                    .chunks(3)
-                    .map(|i| Vec3::new(i[0] as f64, i[1] as f64, i[2] as f64))
-                    .collect();
-                // all vertex-normals in the mesh
-                let mut normals: Vec<Vec3> = vec![];
-                if !mesh.normals.is_empty() {
-                    normals = mesh
-                        .normals
-                        .chunks(3)
-                        .map(|i| Vec3::new(i[0] as f64, i[1] as f64, i[2] as f64))
-                        .collect();
-                }
-                // all texture coordinates in the mesh
-                let mut texcoords: Vec<(f64, f64)> = vec![];
-                if !mesh.texcoords.is_empty() {
-                    texcoords = mesh
-                        .texcoords
-                        .chunks(2)
-                        .map(|i| (i[0] as f64, i[1] as f64))
-                        .collect();
+fn create_animated_scene(start_time: f64, end_time: f64) -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let floor = Arc::new(XZRect {
+        material: white.clone(),
+        x_0: -20.0,
+        x_1: 20.0,
+        z_0: -20.0,
+        z_1: 20.0,
+        k: 0.0,
+    });
+    let back_wall = Arc::new(XYRect {
+        material: white.clone(),
+        x_0: -20.0,
+        x_1: 20.0,
+        y_0: 0.0,
+        y_1: 20.0,
+        k: 10.0,
+    });
+    let light = Arc::new(FlipNormals::new(Arc::new(XZRect {
+        material: Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+            Vec3::new(4.0, 4.0, 4.0),
+        )))),
+        x_0: -5.0,
+        x_1: 5.0,
+        z_0: -5.0,
+        z_1: 5.0,
+        k: 20.0,
+    })));
+
+    // A ball which bounces twice per second, easing in and out of the top of each bounce
+    let mut bounce_keys = vec![];
+    for bounce in 0..4 {
+        let time = bounce as f64 * 0.5;
+        bounce_keys.push(Keyframe::new(
+            time,
+            Vec3::new(-5.0 + 2.0 * time, 2.0, 0.0),
+            Interpolation::Bezier {
+                out_rate: Vec3::new(2.0, 24.0, 0.0),
+                in_rate: Vec3::new(2.0, -24.0, 0.0),
+            },
+        ));
+    }
+    // The ball's color is evaluated at the time of each ray, like its motion
+    let ball_color = AnimatedTexture::new(Track::new(vec![
+        Keyframe::linear(0.0, Vec3::new(0.8, 0.1, 0.1)),
+        Keyframe::linear(1.5, Vec3::new(0.1, 0.1, 0.8)),
+    ]));
+    let ball = Arc::new(AnimatedTransform::translated(
+        Arc::new(Sphere {
+            center: Vec3::new(0.0, 0.0, 0.0),
+            radius: 2.0,
+            material: Arc::new(Lambertian {
+                albedo: Arc::new(ball_color),
+            }),
+        }),
+        Track::new(bounce_keys),
+    ));
+
+    // A block which spins about the Y axis while growing
+    let block = Arc::new(AnimatedTransform::new(
+        Arc::new(AxisAlignedBlock::new(
+            Vec3::new(-2.0, 0.0, -2.0),
+            Vec3::new(2.0, 4.0, 2.0),
+            Arc::new(Lambertian {
+                albedo: Arc::new(ConstantTexture::new(Vec3::new(0.12, 0.45, 0.15))),
+            }),
+        )),
+        Track::constant(Vec3::new(6.0, 0.0, 2.0)),
+        Track::new(vec![
+            Keyframe::linear(0.0, Vec3::new(0.0, 0.0, 0.0)),
+            Keyframe::linear(2.0, Vec3::new(0.0, 360.0, 0.0)),
+        ]),
+        Track::new(vec![
+            Keyframe::eased(0.0, Vec3::new(0.5, 0.5, 0.5)),
+            Keyframe::eased(2.0, Vec3::new(1.0, 1.5, 1.0)),
+        ]),
+    ));
+
+    // A flag which ripples in the wind, keyframed every quarter of a second
+    let (columns, rows) = (16, 8);
+    let mut flag = MeshKeyframes {
+        times: vec![],
+        positions: vec![],
+        normals: None,
+        texture_coords: Some(vec![]),
+    };
+    for key in 0..9 {
+        let time = key as f64 * 0.25;
+        let mut positions = vec![];
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let (u, v) = (column as f64 / columns as f64, row as f64 / rows as f64);
+                let ripple = u * (2.0 * std::f64::consts::PI * (u * 1.5 - time)).sin();
+                positions.push(Vec3::new(-4.0 - 8.0 * u, 8.0 + 4.0 * v, 4.0 + ripple));
+                if key == 0 {
+                    flag.texture_coords.as_mut().unwrap().push((u, v));
                 }
-                // Construct faces
-                let faces: Vec<Arc<Hitable>> = mesh
-                    .indices
-                    .chunks(3)
-                    .map(|i| {
-                        let mut face = Polygon::new(
-                            vec![
-                                vertices[i[0] as usize],
-                                vertices[i[1] as usize],
-                                vertices[i[2] as usize],
-                            ],
-                            Arc::clone(&material),
-                        );
-                        if !normals.is_empty() {
-                            face.vertex_normals = Some(vec![
-                                normals[i[0] as usize],
-                                normals[i[1] as usize],
-                                normals[i[2] as usize],
-                            ]);
-                        }
-                        if !texcoords.is_empty() {
-                            face.texture_coords = Some(vec![
-                                texcoords[i[0] as usize],
-                                texcoords[i[1] as usize],
-                                texcoords[i[2] as usize],
-                            ]);
-                        }
-                        Arc::new(face) as Arc<Hitable>
-                    })
-                    .collect();
-                meshes.push(Arc::new(PolygonMesh::new(faces)));
             }
-            BvhNode::new(&mut HitableList { list: meshes }, 0.0, 0.0)
         }
-        Err(e) => {
-            println!("Failed to load file {:?}: {:?}", file_path, e);
-            BvhNode::new(&mut HitableList { list: vec![] }, 0.0, 0.0)
+        flag.times.push(time);
+        flag.positions.push(positions);
+    }
+    let mut flag_indices = vec![];
+    for row in 0..rows {
+        for column in 0..columns {
+            let corner = row * (columns + 1) + column;
+            flag_indices.push([corner, corner + 1, corner + columns + 2]);
+            flag_indices.push([corner, corner + columns + 2, corner + columns + 1]);
         }
     }
+    let flag = Arc::new(
+        DeformingMesh::new(
+            flag,
+            &flag_indices,
+            Arc::new(Lambertian {
+                albedo: Arc::new(CheckerTexture::new(
+                    Arc::new(ConstantTexture::new(Vec3::new(0.9, 0.9, 0.9))),
+                    Arc::new(ConstantTexture::new(Vec3::new(0.1, 0.1, 0.6))),
+                )),
+            }),
+            start_time,
+            end_time,
+        )
+        .unwrap(),
+    );
+    // Alternatively, a deforming mesh can be loaded from a sequence of OBJ files
+    // let file_paths: Vec<std::path::PathBuf> = (1..=48)
+    //     .map(|frame| std::path::PathBuf::from(format!("object-files/cloth/cloth_{:04}.obj", frame)))
+    //     .collect();
+    // let cloth = Arc::new(
+    //     loaders::meshes::load_obj_sequence(&file_paths, 24.0, white.clone(), start_time, end_time)
+    //         .unwrap(),
+    // );
+
+    let list: Vec<Arc<Hitable>> = vec![
+        floor,
+        Arc::new(FlipNormals::new(back_wall)),
+        light,
+        ball,
+        block,
+        flag,
+    ];
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        start_time,
+        end_time,
+    )
+}
+
+/// Creates a scene showcasing the analytic primitives, several of which are only partially swept
+fn create_shapes_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let red = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.65, 0.05, 0.05))),
+    });
+    let checker = Arc::new(Lambertian {
+        albedo: Arc::new(CheckerTexture::new(
+            Arc::new(ConstantTexture::new(Vec3::new(0.2, 0.3, 0.1))),
+            Arc::new(ConstantTexture::new(Vec3::new(0.9, 0.9, 0.9))),
+        )),
+    });
+    let gold = Arc::new(Metal::new(
+        Arc::new(ConstantTexture::new(Vec3::new(0.8, 0.6, 0.2))),
+        0.1,
+    ));
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+
+    let list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white.clone(),
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+        Arc::new(Disk::new(
+            Vec3::new(-30.0, 0.1, 0.0),
+            8.0,
+            3.0,
+            270.0,
+            red.clone(),
+        )),
+        Arc::new(Cylinder::new(
+            Vec3::new(-15.0, 0.0, 0.0),
+            5.0,
+            12.0,
+            360.0,
+            true,
+            checker.clone(),
+        )),
+        Arc::new(Cone::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            6.0,
+            14.0,
+            360.0,
+            red.clone(),
+        )),
+        Arc::new(Torus::new(Vec3::new(15.0, 3.0, 0.0), 5.0, 2.0, 360.0, gold)),
+        Arc::new(Paraboloid::new(
+            Vec3::new(30.0, 0.0, 0.0),
+            6.0,
+            12.0,
+            300.0,
+            checker.clone(),
+        )),
+        Arc::new(Hyperboloid::new(
+            Vec3::new(-15.0, 7.0, 20.0),
+            3.0,
+            6.0,
+            14.0,
+            360.0,
+            white,
+        )),
+        Arc::new(Cylinder::new(
+            Vec3::new(15.0, 0.0, 20.0),
+            5.0,
+            12.0,
+            180.0,
+            false,
+            checker,
+        )),
+        Arc::new(Quad::new(
+            Vec3::new(-6.0, 0.0, 25.0),
+            Vec3::new(12.0, 0.0, -5.0),
+            Vec3::new(3.0, 14.0, 0.0),
+            red,
+        )),
+    ];
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+/// Creates a scene showcasing Constructive Solid Geometry: a glass lens, a hollowed-out
+/// block, a sphere with a cut-away revealing its core, and a union of glass spheres
+fn create_csg_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let red = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.65, 0.05, 0.05))),
+    });
+    let green = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.12, 0.45, 0.15))),
+    });
+    let glass = Arc::new(Dielectric::new(1.5));
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+
+    // A biconvex lens, formed by the intersection of two large spheres
+    let lens = Csg::intersection(
+        Arc::new(Sphere {
+            center: Vec3::new(22.0, 10.0, -17.0),
+            radius: 20.0,
+            material: glass.clone(),
+        }),
+        Arc::new(Sphere {
+            center: Vec3::new(22.0, 10.0, 17.0),
+            radius: 20.0,
+            material: glass.clone(),
+        }),
+    );
+    // A block hollowed out by a sphere which breaks through each of its faces
+    let hollow_block = Csg::difference(
+        Arc::new(AxisAlignedBlock::new(
+            Vec3::new(2.0, 0.0, -6.0),
+            Vec3::new(14.0, 12.0, 6.0),
+            red.clone(),
+        )),
+        Arc::new(Sphere {
+            center: Vec3::new(8.0, 6.0, 0.0),
+            radius: 7.5,
+            material: white.clone(),
+        }),
+    );
+    // A shell with one octant cut away, revealing the sphere within it
+    let shell = Csg::difference(
+        Arc::new(Csg::difference(
+            Arc::new(Sphere {
+                center: Vec3::new(-10.0, 7.0, 0.0),
+                radius: 7.0,
+                material: white.clone(),
+            }),
+            Arc::new(Sphere {
+                center: Vec3::new(-10.0, 7.0, 0.0),
+                radius: 6.0,
+                material: white.clone(),
+            }),
+        )),
+        Arc::new(AxisAlignedBlock::new(
+            Vec3::new(-20.0, 7.0, -20.0),
+            Vec3::new(-10.0, 20.0, 0.0),
+            white.clone(),
+        )),
+    );
+    let core = Sphere {
+        center: Vec3::new(-10.0, 7.0, 0.0),
+        radius: 3.5,
+        material: green,
+    };
+    // Overlapping glass spheres, which refract as a single object without internal surfaces
+    let glass_union = Csg::union(
+        Arc::new(Sphere {
+            center: Vec3::new(-28.0, 5.0, 0.0),
+            radius: 5.0,
+            material: glass.clone(),
+        }),
+        Arc::new(Sphere {
+            center: Vec3::new(-32.0, 9.0, 3.0),
+            radius: 4.0,
+            material: glass,
+        }),
+    );
+
+    let list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white,
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+        Arc::new(lens),
+        Arc::new(hollow_block),
+        Arc::new(shell),
+        Arc::new(core),
+        Arc::new(glass_union),
+    ];
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+/// Creates a scene of procedural shapes described by signed distance functions: a Mandelbulb
+/// fractal, smoothly blended spheres, a twisted rounded box and a repeated grid of tori
+fn create_sdf_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let red = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.65, 0.05, 0.05))),
+    });
+    let blue = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.1, 0.2, 0.6))),
+    });
+    let gold = Arc::new(Metal::new(
+        Arc::new(ConstantTexture::new(Vec3::new(0.8, 0.6, 0.2))),
+        0.2,
+    ));
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+
+    let mandelbulb = ImplicitSurface::new(
+        Arc::new(Offset {
+            shape: Arc::new(Scale {
+                shape: Arc::new(SdfMandelbulb {
+                    power: 8.0,
+                    iterations: 8,
+                }),
+                scale: 7.0,
+            }),
+            offset: Vec3::new(22.0, 8.5, 0.0),
+        }),
+        AxisAlignedBoundingBox::new(Vec3::new(13.0, -0.5, -9.0), Vec3::new(31.0, 17.5, 9.0)),
+        gold,
+    );
+    // Spheres which melt into each other
+    let blob = ImplicitSurface::new(
+        Arc::new(SmoothUnion {
+            a: Arc::new(Offset {
+                shape: Arc::new(SdfSphere { radius: 4.0 }),
+                offset: Vec3::new(5.0, 4.0, 0.0),
+            }),
+            b: Arc::new(Offset {
+                shape: Arc::new(SdfSphere { radius: 3.0 }),
+                offset: Vec3::new(5.0, 9.0, 1.0),
+            }),
+            smoothness: 2.0,
+        }),
+        AxisAlignedBoundingBox::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(10.0, 13.0, 5.0)),
+        red,
+    );
+    let mut twisted_box = ImplicitSurface::new(
+        Arc::new(Offset {
+            shape: Arc::new(Twist {
+                shape: Arc::new(SdfRoundedBox {
+                    half_extents: Vec3::new(3.0, 7.0, 3.0),
+                    radius: 1.0,
+                }),
+                rate: 0.15,
+            }),
+            offset: Vec3::new(-10.0, 8.0, 0.0),
+        }),
+        AxisAlignedBoundingBox::new(Vec3::new(-16.0, 0.0, -6.0), Vec3::new(-4.0, 17.0, 6.0)),
+        blue,
+    );
+    twisted_box.step_scale = 0.5;
+    // An infinite grid of tori, clipped to a slab by the bounding box
+    let tori = ImplicitSurface::new(
+        Arc::new(Repeat {
+            shape: Arc::new(SdfTorus {
+                major_radius: 1.5,
+                minor_radius: 0.5,
+            }),
+            spacing: Vec3::new(5.0, 0.0, 5.0),
+        }),
+        AxisAlignedBoundingBox::new(Vec3::new(-32.5, -0.5, -7.5), Vec3::new(-17.5, 0.5, 7.5)),
+        white.clone(),
+    );
+
+    let list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white,
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+        Arc::new(mandelbulb),
+        Arc::new(blob),
+        Arc::new(twisted_box),
+        Arc::new(tori),
+    ];
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+fn create_curves_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let grass = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.2, 0.5, 0.1))),
+    });
+    let rubber = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.65, 0.05, 0.05))),
+    });
+    let brown_hair = Arc::new(Hair::from_melanin(1.3, 0.0, 0.3, 0.3));
+    let blonde_hair = Arc::new(Hair::from_color(Vec3::new(0.8, 0.6, 0.3), 0.3, 0.3));
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+
+    // A patch of grass, whose blades bend over in random directions
+    let mut blades = vec![];
+    for _ in 0..3000 {
+        let root = Vec3::new(
+            -22.0 + 14.0 * rng::scene_random(),
+            0.0,
+            -7.0 + 14.0 * rng::scene_random(),
+        );
+        let height = 3.0 + 3.0 * rng::scene_random();
+        let angle = 2.0 * std::f64::consts::PI * rng::scene_random();
+        let bend = 0.4 * height * rng::scene_random() * Vec3::new(angle.cos(), 0.0, angle.sin());
+        blades.push(vec![
+            root,
+            root,
+            root + Vec3::new(0.0, 0.5 * height, 0.0),
+            root + Vec3::new(0.0, height, 0.0) + bend,
+            root + Vec3::new(0.0, height, 0.0) + 2.0 * bend,
+        ]);
+    }
+    let grass = Curves::new(
+        &blades,
+        CurveBasis::BSpline,
+        (0.25, 0.02),
+        CurveType::Flat,
+        grass,
+    );
+
+    // A ball of fur, whose hairs droop under their own weight
+    let center = Vec3::new(14.0, 6.0, 0.0);
+    let mut hairs = vec![];
+    for _ in 0..6000 {
+        // Choose a uniformly distributed direction for the hair's root
+        let y = 1.0 - 2.0 * rng::scene_random();
+        let phi = 2.0 * std::f64::consts::PI * rng::scene_random();
+        let radius = (1.0 - y * y).sqrt();
+        let direction = Vec3::new(radius * phi.cos(), y, radius * phi.sin());
+        let root = center + 4.0 * direction;
+        let droop = Vec3::new(0.0, -1.0, 0.0);
+        hairs.push(vec![
+            root,
+            root + 1.0 * direction,
+            root + 2.0 * direction + 0.5 * droop,
+            root + 2.5 * direction + 1.5 * droop,
+        ]);
+    }
+    let fur = Curves::new(
+        &hairs,
+        CurveBasis::Bezier,
+        (0.06, 0.02),
+        CurveType::Round,
+        brown_hair,
+    );
+
+    // A braid-like lock of hair hanging over a cable
+    let mut lock = vec![];
+    for i in 0..400 {
+        let offset = Vec3::new(
+            0.6 * (rng::scene_random() - 0.5),
+            0.0,
+            0.6 * (rng::scene_random() - 0.5),
+        );
+        let sway = 0.5 * (f64::from(i) * 0.1).sin();
+        lock.push(vec![
+            Vec3::new(0.0, 16.0, 0.0) + offset,
+            Vec3::new(sway, 12.0, 1.0) + offset,
+            Vec3::new(-sway, 8.0, -1.0) + offset,
+            Vec3::new(0.0, 4.0, 0.0) + 2.0 * offset,
+        ]);
+    }
+    let lock = Curves::new(
+        &lock,
+        CurveBasis::Bezier,
+        (0.05, 0.03),
+        CurveType::Round,
+        blonde_hair,
+    );
+    let cable = Curves::new(
+        &[vec![
+            Vec3::new(-6.0, 0.0, 6.0),
+            Vec3::new(-4.0, 10.0, 4.0),
+            Vec3::new(4.0, 10.0, -4.0),
+            Vec3::new(6.0, 0.0, -6.0),
+        ]],
+        CurveBasis::Bezier,
+        (0.6, 0.6),
+        CurveType::Round,
+        rubber,
+    );
+
+    let list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white,
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+        Arc::new(grass),
+        Arc::new(fur),
+        Arc::new(lock),
+        Arc::new(cable),
+    ];
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+fn create_point_cloud_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    // The points' own colors, which are shaded diffusely
+    let point_colors = Arc::new(Lambertian {
+        albedo: Arc::new(VertexColorTexture::new(Arc::new(ConstantTexture::new(
+            Vec3::new(0.73, 0.73, 0.73),
+        )))),
+    });
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+
+    // A spiral galaxy of particles, whose arms fade from yellow at the core to blue
+    let num_points = 500_000;
+    let mut positions = Vec::with_capacity(num_points);
+    let mut radii = Vec::with_capacity(num_points);
+    let mut colors = Vec::with_capacity(num_points);
+    for _ in 0..num_points {
+        let distance = 20.0 * rng::scene_random().powi(2);
+        let arm = if rng::scene_random() < 0.5 {
+            0.0
+        } else {
+            std::f64::consts::PI
+        };
+        let angle = arm + 0.35 * distance + 0.6 * (rng::scene_random() - 0.5);
+        let spread = 1.0 + 0.1 * distance;
+        positions.push(Vec3::new(
+            distance * angle.cos() + spread * (rng::scene_random() - 0.5),
+            10.0 + 0.6 * (rng::scene_random() - 0.5) * (20.0 - distance) / 10.0,
+            distance * angle.sin() + spread * (rng::scene_random() - 0.5),
+        ));
+        radii.push(0.04 + 0.08 * rng::scene_random());
+        let fade = distance / 20.0;
+        colors.push((1.0 - fade) * Vec3::new(1.0, 0.8, 0.3) + fade * Vec3::new(0.2, 0.4, 1.0));
+    }
+    let galaxy = PointCloud::new(&positions, &radii, Some(&colors), point_colors);
+    // Scanned or simulated points can be loaded from PLY, XYZ or CSV files instead, e.g.
+    // let galaxy = loaders::points::load_point_cloud(Path::new("points.ply"), 0.05, point_colors)
+    //     .unwrap();
+
+    let list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white,
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+        Arc::new(galaxy),
+    ];
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+fn create_smoke_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+
+    // A rising plume of smoke, baked into a voxel grid from layered noise, which widens
+    // and thins out as it rises
+    let noise = Perlin::new();
+    let fractal_noise = |point: Vec3| {
+        (0..4).fold(0.0, |sum, octave| {
+            let frequency = 2.0_f64.powi(octave);
+            sum + noise.noise(&(frequency * point)) / frequency
+        })
+    };
+    let plume_distance = |point: &Vec3| {
+        let radius = 0.12 + 0.25 * point.y();
+        let sway = 0.08 * (6.0 * point.y()).sin();
+        let offset = Vec3::new(point.x() - 0.5 - sway, 0.0, point.z() - 0.5);
+        offset.length() / radius
+    };
+    let resolution = [64, 128, 64];
+    let density = VoxelGrid::from_fn(resolution, |point| {
+        let distorted = point + 0.15 * Vec3::new(fractal_noise(6.0 * point), 0.0, 0.0);
+        let falloff = 1.0 - plume_distance(&distorted);
+        let wisps = fractal_noise(8.0 * point + Vec3::new(0.0, -3.0 * point.y(), 0.0));
+        ((1.5 * falloff + wisps - 0.2) * (1.0 - point.y()).sqrt()).max(0.0)
+    });
+    // The plume burns at its base, where it is hottest along its axis
+    let temperature = VoxelGrid::from_fn(resolution, |point| {
+        let core = 1.0 - plume_distance(&point) / 0.8;
+        let flicker = 0.3 * fractal_noise(10.0 * point);
+        ((core + flicker) * (-6.0 * point.y()).exp() - 0.1).max(0.0)
+    });
+    let mut smoke = GridMedium::new(
+        AxisAlignedBoundingBox::new(Vec3::new(-10.0, 0.0, -10.0), Vec3::new(10.0, 40.0, 10.0)),
+        density,
+        0.6,
+        Arc::new(ConstantTexture::new(Vec3::new(0.8, 0.8, 0.8))),
+    );
+    smoke.emission = Some(BlackbodyEmission::new(temperature, (500.0, 2500.0), 40.0));
+    // A grid loaded from a file (e.g. a simulation exported as a Mitsuba volume) is used
+    // in the same way:
+    // let density = loaders::voxels::read_vol_grid(Path::new("smoke.vol"))
+    //     .expect("Failed to load the smoke");
+
+    let list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white,
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+        Arc::new(smoke),
+    ];
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+fn create_phase_function_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+    let back_light: Arc<Material> = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(20.0, 20.0, 20.0),
+    ))));
+
+    // A row of identical spheres of fog, backlit so that media which scatter light forwards
+    // glow, while those which scatter it backwards stay dim
+    let phase_functions: Vec<Arc<PhaseFunction>> = vec![
+        Arc::new(HenyeyGreenstein::new(-0.6)),
+        Arc::new(Rayleigh),
+        Arc::new(IsotropicPhase),
+        Arc::new(DoubleHenyeyGreenstein::new(0.8, -0.3, 0.9)),
+        Arc::new(HenyeyGreenstein::new(0.7)),
+        Arc::new(Mie::new(10.0)),
+    ];
+    let count = phase_functions.len();
+    let mut list: Vec<Arc<Hitable>> = phase_functions
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, phase_function)| {
+            let center = Vec3::new(7.0 * (i as f64 - (count - 1) as f64 / 2.0), 4.0, 0.0);
+            let boundary = Arc::new(Sphere {
+                material: white.clone(),
+                center,
+                radius: 3.0,
+            });
+            let fog = Arc::new(ConstantMedium::with_phase_function(
+                boundary,
+                1.0,
+                Arc::new(ConstantTexture::new(Vec3::new(0.95, 0.95, 0.95))),
+                phase_function,
+            )) as Arc<Hitable>;
+            // A light hidden directly behind the sphere
+            let back_light = Arc::new(Quad::new(
+                center + Vec3::new(-1.0, -1.0, 4.0),
+                Vec3::new(2.0, 0.0, 0.0),
+                Vec3::new(0.0, 2.0, 0.0),
+                Arc::clone(&back_light),
+            )) as Arc<Hitable>;
+            vec![fog, back_light]
+        })
+        .collect();
+
+    list.push(Arc::new(XZRect {
+        material: white,
+        x_0: -60.0,
+        x_1: 60.0,
+        z_0: -60.0,
+        z_1: 60.0,
+        k: 0.0,
+    }));
+    list.push(Arc::new(Quad::new(
+        Vec3::new(-20.0, 60.0, -10.0),
+        Vec3::new(40.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 20.0),
+        light,
+    )));
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+fn create_chromatic_media_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+
+    // A glass of amber liquid, which only absorbs light (mostly blue and green)
+    let amber = Arc::new(MediumBoundary::new(
+        Arc::new(Sphere {
+            material: Arc::new(Dielectric::new(1.5)),
+            center: Vec3::new(10.0, 4.0, 0.0),
+            radius: 4.0,
+        }),
+        Arc::new(HomogeneousMedium::from_color(
+            Vec3::new(0.9, 0.35, 0.1),
+            4.0,
+            Vec3::new(0.0, 0.0, 0.0),
+        )),
+    ));
+    // A cloud of fine particles, which scatter blue light more strongly than red (like the
+    // sky), so that it looks blue where lit but passes orange light through
+    let haze = Arc::new(MediumBoundary::new_invisible(
+        Arc::new(Sphere {
+            material: white.clone(),
+            center: Vec3::new(0.0, 4.0, 0.0),
+            radius: 4.0,
+        }),
+        Arc::new(HomogeneousMedium::new(
+            Vec3::new(0.01, 0.01, 0.01),
+            Vec3::new(0.15, 0.35, 0.8),
+        )),
+    ));
+    // A ball of glowing gas, which emits light where it absorbs it
+    let mut gas = HomogeneousMedium::new(Vec3::new(0.4, 0.4, 0.4), Vec3::new(0.1, 0.1, 0.1));
+    gas.emission = Vec3::new(2.5, 0.9, 0.25);
+    let glow = Arc::new(MediumBoundary::new_invisible(
+        Arc::new(Sphere {
+            material: white.clone(),
+            center: Vec3::new(-10.0, 4.0, 0.0),
+            radius: 4.0,
+        }),
+        Arc::new(gas),
+    ));
+
+    let list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white,
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+        amber,
+        haze,
+        glow,
+    ];
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+fn create_nested_media_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+
+    // A thin haze filling the whole scene, including the space around the camera
+    let haze = Arc::new(MediumBoundary::new_invisible(
+        Arc::new(Sphere {
+            material: white.clone(),
+            center: Vec3::new(0.0, 0.0, 0.0),
+            radius: 150.0,
+        }),
+        Arc::new(HomogeneousMedium::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.004, 0.004, 0.004),
+        )),
+    ));
+    // A hollow glass ball, whose walls are slightly tinted
+    let glass_material = Arc::new(Dielectric::new(1.5));
+    let mut glass = MediumBoundary::new(
+        Arc::new(HitableList {
+            list: vec![
+                Arc::new(Sphere {
+                    material: glass_material.clone(),
+                    center: Vec3::new(-8.0, 5.0, 0.0),
+                    radius: 5.0,
+                }),
+                Arc::new(FlipNormals::new(Arc::new(Sphere {
+                    material: glass_material,
+                    center: Vec3::new(-8.0, 5.0, 0.0),
+                    radius: 4.6,
+                }))),
+            ],
+        }),
+        Arc::new(HomogeneousMedium::from_color(
+            Vec3::new(0.8, 0.95, 0.85),
+            1.0,
+            Vec3::new(0.0, 0.0, 0.0),
+        )),
+    );
+    glass.priority = 2;
+    // The wine filling the glass, whose boundary lies within the glass's walls (so its
+    // surface is never seen, and it meets the glass without a gap)
+    let mut wine = MediumBoundary::new(
+        Arc::new(Sphere {
+            material: Arc::new(Dielectric::new(1.33)),
+            center: Vec3::new(-8.0, 5.0, 0.0),
+            radius: 4.8,
+        }),
+        Arc::new(HomogeneousMedium::from_color(
+            Vec3::new(0.7, 0.08, 0.15),
+            3.0,
+            Vec3::new(0.0, 0.0, 0.0),
+        )),
+    );
+    wine.priority = 1;
+    // A ring of fog, whose (non-convex) boundary rays may enter and leave several times
+    let fog = Arc::new(ConstantMedium::new(
+        Arc::new(Torus::new(
+            Vec3::new(8.0, 2.5, 0.0),
+            4.0,
+            2.5,
+            360.0,
+            white.clone(),
+        )),
+        0.6,
+        Arc::new(ConstantTexture::new(Vec3::new(0.9, 0.6, 0.3))),
+    ));
+
+    let list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white,
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+        haze,
+        Arc::new(glass),
+        Arc::new(wine),
+        fog,
+    ];
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+fn create_subsurface_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+
+    // Skin, through which red light travels much further than green or blue
+    let skin = Arc::new(Subsurface::new(
+        Arc::new(Sphere {
+            material: white.clone(),
+            center: Vec3::new(10.0, 4.0, 0.0),
+            radius: 4.0,
+        }),
+        Arc::new(ConstantTexture::new(Vec3::new(0.8, 0.55, 0.45))),
+        Arc::new(ConstantTexture::new(Vec3::new(1.0, 0.4, 0.2))),
+        1.4,
+    ));
+    // A block of marble, whose veins are given by a noise texture
+    let marble = Arc::new(Subsurface::new(
+        Arc::new(AxisAlignedBlock::new(
+            Vec3::new(-3.0, 0.0, -3.0),
+            Vec3::new(3.0, 7.0, 3.0),
+            white.clone(),
+        )),
+        Arc::new(NoiseTexture::new(1.0, 7)),
+        Arc::new(ConstantTexture::new(Vec3::new(0.6, 0.5, 0.4))),
+        1.5,
+    ));
+    // A teapot of milk
+    let teapot = load_obj_file(
+        &Path::new("object-files/teapot.obj"),
+        white.clone(),
+        Some(Subdivision::new(SubdivisionScheme::Loop, 1)),
+        None,
+    )
+    .expect("Failed to load the teapot");
+    let milk = Arc::new(Subsurface::new(
+        Arc::new(Translate::new(Arc::new(teapot), Vec3::new(-10.0, 0.0, 0.0))),
+        Arc::new(ConstantTexture::new(Vec3::new(0.95, 0.93, 0.88))),
+        Arc::new(ConstantTexture::new(Vec3::new(0.4, 0.3, 0.2))),
+        1.35,
+    ));
+
+    let list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white,
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+        skin,
+        marble,
+        milk,
+    ];
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+fn create_layered_materials_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+    let gold = Arc::new(Metal::new(
+        Arc::new(ConstantTexture::new(Vec3::new(0.9, 0.7, 0.3))),
+        0.1,
+    ));
+
+    // Clear-coated car paint
+    let car_paint = Arc::new(LayeredMaterial::new(
+        Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture::new(Vec3::new(0.7, 0.05, 0.05))),
+        }),
+        1.5,
+        0.0,
+    ));
+    // A thick amber lacquer over a marbled base, which deepens in color towards the edges
+    let lacquer = Arc::new(LayeredMaterial::new_absorbing(
+        Arc::new(Lambertian {
+            albedo: Arc::new(NoiseTexture::new(1.0, 7)),
+        }),
+        1.5,
+        0.1,
+        Vec3::new(0.1, 0.4, 1.2),
+        1.0,
+    ));
+    // Metal with patches of dust
+    let dusty_metal = Arc::new(MixMaterial::new(
+        gold.clone(),
+        Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture::new(Vec3::new(0.5, 0.45, 0.4))),
+        }),
+        Arc::new(NoiseTexture::new(2.0, 7)),
+    ));
+    // A checkered blend of metal and a matte material
+    let checkered = Arc::new(MixMaterial::new(
+        gold,
+        white.clone(),
+        Arc::new(CheckerTexture::new(
+            Arc::new(ConstantTexture::new(Vec3::new(0.0, 0.0, 0.0))),
+            Arc::new(ConstantTexture::new(Vec3::new(1.0, 1.0, 1.0))),
+        )),
+    ));
+
+    let mut list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white,
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+    ];
+    let materials: Vec<Arc<Material>> = vec![car_paint, lacquer, dusty_metal, checkered];
+    for (i, material) in materials.into_iter().enumerate() {
+        list.push(Arc::new(Sphere {
+            material,
+            center: Vec3::new(12.0 - 8.0 * i as f64, 3.0, 0.0),
+            radius: 3.0,
+        }));
+    }
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
+}
+
+fn create_principled_scene() -> BvhNode {
+    #![allow(dead_code)]
+    let white = Arc::new(Lambertian {
+        albedo: Arc::new(ConstantTexture::new(Vec3::new(0.73, 0.73, 0.73))),
+    });
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+        Vec3::new(6.0, 6.0, 6.0),
+    ))));
+    let color = |r, g, b| Arc::new(ConstantTexture::new(Vec3::new(r, g, b)));
+
+    let plastic = Principled::new(color(0.8, 0.1, 0.1));
+    let mut brushed_metal = Principled::new(color(0.9, 0.9, 0.9));
+    brushed_metal.metallic = 1.0;
+    brushed_metal.roughness = 0.3;
+    brushed_metal.anisotropic = 0.9;
+    let mut gold = Principled::new(color(1.0, 0.78, 0.34));
+    gold.metallic = 1.0;
+    gold.roughness = 0.2;
+    let mut velvet = Principled::new(color(0.3, 0.05, 0.4));
+    velvet.roughness = 1.0;
+    velvet.sheen = 1.0;
+    let mut car_paint = Principled::new(color(0.05, 0.2, 0.6));
+    car_paint.metallic = 0.5;
+    car_paint.roughness = 0.4;
+    car_paint.clearcoat = 1.0;
+    let mut frosted_glass = Principled::new(color(0.9, 1.0, 0.95));
+    frosted_glass.transmission = 1.0;
+    frosted_glass.roughness = 0.2;
+    let mut wax = Principled::new(color(0.9, 0.8, 0.6));
+    wax.subsurface = 1.0;
+    wax.roughness = 0.3;
+
+    let mut list: Vec<Arc<Hitable>> = vec![
+        Arc::new(XZRect {
+            material: white,
+            x_0: -60.0,
+            x_1: 60.0,
+            z_0: -60.0,
+            z_1: 60.0,
+            k: 0.0,
+        }),
+        Arc::new(Quad::new(
+            Vec3::new(-20.0, 60.0, -10.0),
+            Vec3::new(40.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            light,
+        )),
+    ];
+    let materials = vec![
+        plastic,
+        brushed_metal,
+        gold,
+        velvet,
+        car_paint,
+        frosted_glass,
+        wax,
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        list.push(Arc::new(Sphere {
+            material: Arc::new(material),
+            center: Vec3::new(18.0 - 6.0 * i as f64, 2.5, 0.0),
+            radius: 2.5,
+        }));
+    }
+
+    BvhNode::new(
+        &mut HitableList {
+            list: ObjectTag::tag_all(list),
+        },
+        0.0,
+        1.0,
+    )
 }
 
 fn main() {
-    let num_threads: usize = num_cpus::get() - 1;
+    let num_threads: usize = (num_cpus::get() - 1).max(1);
+    // Every random value used by the render (during both scene construction and tracing)
+    // is derived from this seed, so rendering the same scene with the same seed always
+    // produces the same image.
+    let seed: u64 = 0;
     let num_x = 264 * 2;
     let num_y = 180 * 2;
     // let num_x = 600;
     // let num_y = 600;
-    // n and m are the dimensions of the subpixel grid generated for anti-aliasing
-    // let (n, m) = (38, 38);
-    let (n, m) = (5, 5);
-    let range = Uniform::new(0.0, 1.0);
-    let mut img_buff = image::ImageBuffer::new(num_x, num_y);
-    let look_from = Vec3::new(0.0, 75.0, -100.0);
-    let look_in = Vec3::new(0.0, -0.6, 1.0);
-    let camera = Camera::new(
-        look_from,                   // Camera origin
-        look_in,                     // Camera view direction
-        Vec3::new(0.0, 1.0, 0.0),    // Camera "up" direction
-        40.0,                        // Vertical FOV
-        num_x as f64 / num_y as f64, // Aspect ratio
-        0.0,                         // Aperture
-        10.0,                        // Focus Distance
-        0.0,                         // Shutter open time
-        1.0,                         // Shutter close time
-    );
+    // The sampler generates the sample values for every dimension of each pixel sample
+    // (e.g. film position, lens position, time, and scattering directions). Each
+    // worker thread is given its own copy.
+    let sampler = CorrelatedMultiJitteredSampler::new(16, 16, seed);
+    // let sampler = sampler::samplers::SobolSampler::new(256, seed);
+    // let sampler = sampler::samplers::HaltonSampler::new(256, seed);
+    // let sampler = sampler::samplers::StratifiedSampler::new(16, 16, true, seed);
+    // let sampler = sampler::samplers::IndependentSampler::new(256, seed);
+    // Each pixel takes at least 16 samples, after which batches of 16 further samples
+    // are only taken while its estimated noise remains above the threshold.
+    // Samples are never taken beyond the sampler's own samples per pixel.
+    let adaptive_sampling = AdaptiveSampling::new(16, sampler.samples_per_pixel(), 16, 0.05);
+    // let adaptive_sampling = AdaptiveSampling::fixed(sampler.samples_per_pixel());
+    // Whether to write a heatmap of the number of samples spent on each pixel
+    let write_sample_heatmap = true;
+    // The reconstruction filter used to weight each sample's contribution to nearby pixels
+    let filter = Arc::new(MitchellFilter::new(2.0, 1.0 / 3.0, 1.0 / 3.0));
+    // let filter = Arc::new(film::filters::BoxFilter::new(0.5));
+    // let filter = Arc::new(film::filters::TentFilter::new(1.0));
+    // let filter = Arc::new(film::filters::GaussianFilter::new(1.5, 2.0));
+    // let filter = Arc::new(film::filters::LanczosFilter::new(3.0, 3.0));
+    let settings = RenderSettings {
+        num_threads,
+        num_x,
+        num_y,
+        sampler: Box::new(sampler),
+        adaptive_sampling,
+        filter,
+        write_sample_heatmap,
+    };
+    // Renders a single image. Alternatively, render a numbered image sequence
+    // (e.g. two seconds at 24 frames per second, using a 180° shutter).
+    let frame_range = FrameRange::single();
+    // let frame_range = FrameRange::new(0, 47, 24.0, 0.5);
 
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner().template("{spinner} {msg}: [{elapsed_precise}] "),
-    );
-    spinner.set_message("Performing Scene Construction");
-    spinner.enable_steady_tick(100);
-    let world = Arc::new(create_debug_scene());
-    // let world = Arc::new(create_cornell_box());
-    spinner.finish_with_message("Scene Construction Completed");
+    for frame in frame_range.frames() {
+        let (shutter_open_time, shutter_close_time) = frame_range.shutter_interval(frame);
+        let spinner = ProgressBar::new_spinner();
+        spinner.set_style(
+            ProgressStyle::default_spinner().template("{spinner} {msg}: [{elapsed_precise}] "),
+        );
+        spinner.set_message("Performing Scene Construction");
+        spinner.enable_steady_tick(100);
+        rng::seed_scene_rng(seed);
+        let world = Arc::new(create_debug_scene());
+        // let world = Arc::new(create_cornell_box());
+        // let world = Arc::new(create_animated_scene(shutter_open_time, shutter_close_time));
+        // let world = Arc::new(create_shapes_scene());
+        // let world = Arc::new(create_csg_scene());
+        // let world = Arc::new(create_sdf_scene());
+        // let world = Arc::new(create_curves_scene());
+        // let world = Arc::new(create_point_cloud_scene());
+        // let world = Arc::new(create_smoke_scene());
+        // let world = Arc::new(create_phase_function_scene());
+        // let world = Arc::new(create_chromatic_media_scene());
+        // let world = Arc::new(create_nested_media_scene());
+        // let world = Arc::new(create_subsurface_scene());
+        // let world = Arc::new(create_layered_materials_scene());
+        // let world = Arc::new(create_principled_scene());
+        spinner.finish_with_message("Scene Construction Completed");
+
+        let look_from = Vec3::new(0.0, 75.0, -100.0);
+        let look_in = Vec3::new(0.0, -0.6, 1.0);
+        #[allow(unused_mut)]
+        let mut perspective_camera = PerspectiveCamera::new(
+            look_from,                   // Camera origin
+            look_in,                     // Camera view direction
+            Vec3::new(0.0, 1.0, 0.0),    // Camera "up" direction
+            40.0,                        // Vertical FOV
+            num_x as f64 / num_y as f64, // Aspect ratio
+            0.0,                         // Aperture
+            10.0,                        // Focus Distance
+            shutter_open_time,           // Shutter open time
+            shutter_close_time,          // Shutter close time
+        );
+        // Alternatively, the camera can be aimed at a target point (on which it is focused)
+        // let mut perspective_camera = PerspectiveCamera::look_at(
+        //     look_from,
+        //     Vec3::new(0.0, 0.0, 0.0),
+        //     Vec3::new(0.0, 1.0, 0.0),
+        //     40.0,
+        //     num_x as f64 / num_y as f64,
+        //     0.0,
+        //     shutter_open_time,
+        //     shutter_close_time,
+        // );
+        // or positioned to fit the entire scene within its field of view
+        // let mut perspective_camera = PerspectiveCamera::frame_object(
+        //     &*world,
+        //     look_in,
+        //     Vec3::new(0.0, 1.0, 0.0),
+        //     40.0,
+        //     num_x as f64 / num_y as f64,
+        //     0.0,
+        //     shutter_open_time,
+        //     shutter_close_time,
+        // )
+        // .unwrap();
+        // The camera can also be configured using real-world camera settings.
+        // A 35mm lens at f/16, 1/100 second shutter speed and ISO 100 gives an exposure of 1.
+        // let perspective_camera = PerspectiveCamera::photographic(
+        //     look_from,
+        //     look_in,
+        //     Vec3::new(0.0, 1.0, 0.0),
+        //     num_x as f64 / num_y as f64,
+        //     10.0,
+        //     shutter_open_time,
+        //     &camera::cameras::PhotographicSettings::new(35.0, 16.0, 1.0 / 100.0, 100.0),
+        // );
+        // Focus on whatever is visible at the center of the image
+        // perspective_camera.auto_focus(&*world, 0.5, 0.5, &mut *settings.sampler.clone_sampler());
+        // Shaped bokeh and vignetting (only visible with a non-zero aperture)
+        // perspective_camera.aperture_shape =
+        //     Arc::new(camera::apertures::PolygonalAperture::new(6, 0.0));
+        // perspective_camera.aperture_shape =
+        //     Arc::new(camera::apertures::ImageAperture::new(Path::new("aperture.png")).unwrap());
+        // perspective_camera.cats_eye = 0.5;
+        let camera: Arc<Camera> = Arc::new(perspective_camera);
+        // let camera: Arc<Camera> = Arc::new(camera::cameras::OrthographicCamera::new(
+        //     look_from, look_in, Vec3::new(0.0, 1.0, 0.0), 100.0, num_x as f64 / num_y as f64, shutter_open_time, shutter_close_time,
+        // ));
+        // let camera: Arc<Camera> = Arc::new(camera::cameras::EquirectangularCamera::new(
+        //     look_from, look_in, Vec3::new(0.0, 1.0, 0.0), shutter_open_time, shutter_close_time,
+        // ));
+        // let camera: Arc<Camera> = Arc::new(camera::cameras::FisheyeCamera::new(
+        //     look_from, look_in, Vec3::new(0.0, 1.0, 0.0), 180.0, num_x as f64 / num_y as f64, shutter_open_time, shutter_close_time,
+        // ));
+        // let camera: Arc<Camera> = Arc::new(camera::cameras::CylindricalCamera::new(
+        //     look_from, look_in, Vec3::new(0.0, 1.0, 0.0), 180.0, 60.0, shutter_open_time, shutter_close_time,
+        // ));
+        // A camera which circles the animated scene, pulling back as it goes
+        // let camera: Arc<Camera> = Arc::new(animation::camera::AnimatedCamera::new(
+        //     Track::new(vec![
+        //         Keyframe::eased(0.0, Vec3::new(-10.0, 10.0, -30.0)),
+        //         Keyframe::eased(2.0, Vec3::new(15.0, 12.0, -40.0)),
+        //     ]),
+        //     Track::constant(Vec3::new(0.0, 3.0, 0.0)),
+        //     Vec3::new(0.0, 1.0, 0.0),
+        //     Track::constant(40.0),
+        //     num_x as f64 / num_y as f64,
+        //     shutter_open_time,
+        //     shutter_close_time,
+        // ));
+
+        if let Err(e) = render_frame(world, camera, &settings, &frame_range, frame) {
+            println!("Failed to render frame {}: {}", frame, e);
+            return;
+        }
+    }
+}
+
+/// The settings shared by every frame of a render
+struct RenderSettings {
+    num_threads: usize,
+    num_x: u32,
+    num_y: u32,
+    /// The sampler, of which each worker thread is given its own copy
+    sampler: Box<Sampler>,
+    adaptive_sampling: AdaptiveSampling,
+    /// The reconstruction filter used to weight each sample's contribution to nearby pixels
+    filter: Arc<Filter>,
+    /// Whether to write a heatmap of the number of samples spent on each pixel
+    write_sample_heatmap: bool,
+}
 
+/// Traces a single frame of the scene, and writes its image and render passes.
+/// #### Arguments:
+/// - `world`: The scene, constructed for the frame's shutter interval
+/// - `camera`: The camera through which the scene is viewed
+/// - `settings`: The settings shared by every frame
+/// - `frame_range`: The range of frames being rendered, which determines the output file names
+/// - `frame`: The frame being rendered
+///
+/// #### Returns
+/// - An error if any worker thread panicked, in which case no output is written (as
+///   the rows it was tracing are missing)
+fn render_frame(
+    world: Arc<BvhNode>,
+    camera: Arc<Camera>,
+    settings: &RenderSettings,
+    frame_range: &FrameRange,
+    frame: u32,
+) -> Result<(), String> {
+    let (num_threads, num_x, num_y) = (settings.num_threads, settings.num_x, settings.num_y);
+    let adaptive_sampling = settings.adaptive_sampling;
+    let sampler = &settings.sampler;
+    let mut film = Film::new(num_x, num_y, Arc::clone(&settings.filter));
+    let mut img_buff = image::ImageBuffer::new(num_x, num_y);
+    let mut heatmap_buff = image::ImageBuffer::new(num_x, num_y);
+    let mut aov_buff = AovBuffer::new(num_x, num_y);
     let progress_bar = ProgressBar::new((num_x * num_y) as u64);
     progress_bar.set_style(
         ProgressStyle::default_bar()
@@ -644,63 +2111,120 @@ fn main() {
         "Beginning scene tracing using {} CPU cores.",
         num_threads
     ));
-    for y in 0..num_y {
-        for x in 0..num_x {
-            let mut child_threads = vec![];
-            let mut color = Vec3::new(0.0, 0.0, 0.0);
-            for _ in 0..num_threads {
-                let _world = Arc::clone(&world);
-                child_threads.push(thread::spawn(move || -> Vec3 {
-                    let mut _color = Vec3::new(0.0, 0.0, 0.0);
-                    let mut rng = rand::thread_rng();
-                    // Correlated Multi-Jittered Sampling
-                    // Source: (http://graphics.pixar.com/library/MultiJitteredSampling/paper.pdf)
-                    // Step 1: Produce the canonical arrangement
-                    let mut sample_pattern: Vec<(f64, f64)> = vec![(0.0, 0.0); n * m];
-                    for j in 0..n {
-                        for i in 0..m {
-                            sample_pattern[j * m + i].0 = (i as f64
-                                + (j as f64 * range.sample(&mut rng)) / n as f64)
-                                / m as f64;
-                            sample_pattern[j * m + i].1 = (j as f64
-                                + (i as f64 * range.sample(&mut rng)) / m as f64)
-                                / n as f64;
-                        }
-                    }
-                    // Step 2: Shuffle the arrangement
-                    for j in 0..n {
-                        for k in 0..m {
-                            let i = (j as f64 + range.sample(&mut rng) * (n - j) as f64) as usize;
-                            let a = sample_pattern[j * m + i].0;
-                            let b = sample_pattern[k * m + i].0;
-                            sample_pattern[j * m + i].0 = b;
-                            sample_pattern[k * m + i].0 = a;
-                        }
-                    }
-                    for i in 0..m {
-                        for k in 0..n {
-                            let j = (i as f64 + range.sample(&mut rng) * (m - i) as f64) as usize;
-                            let a = sample_pattern[j * m + i].1;
-                            let b = sample_pattern[j * m + k].1;
-                            sample_pattern[j * m + i].1 = b;
-                            sample_pattern[j * m + k].1 = a;
-                        }
+    // Each worker thread traces entire rows of pixels at a time, and sends back the
+    // samples of each row. The rows are then splatted into the film strictly in order,
+    // as the filter allows neighbouring rows to contribute to the same pixels.
+    // The media enclosing the camera are found once, from the center of its lens
+    let camera_media = {
+        let mut sampler = sampler.clone_sampler();
+        sampler.start_pixel_sample((num_x / 2, num_y / 2), 0);
+        match camera.create_ray(0.5, 0.5, &mut *sampler) {
+            Some(ray) => MediumStack::enclosing(&*world, ray.origin, ray.time, &mut *sampler),
+            None => MediumStack::new(),
+        }
+    };
+    let next_row = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel();
+    let mut workers = vec![];
+    for _ in 0..num_threads {
+        let _world = Arc::clone(&world);
+        let camera = Arc::clone(&camera);
+        let next_row = Arc::clone(&next_row);
+        let camera_media = camera_media.clone();
+        let sender = sender.clone();
+        let mut sampler = sampler.clone_sampler();
+        workers.push(thread::spawn(move || loop {
+            let y = next_row.fetch_add(1, Ordering::SeqCst) as u32;
+            if y >= num_y {
+                break;
+            }
+            let mut row = vec![];
+            for x in 0..num_x {
+                let mut samples = vec![];
+                let mut aov_pixel = AovPixel::new();
+                let mut variance = PixelVariance::new();
+                // Keep taking batches of samples until the pixel has converged
+                loop {
+                    let batch_size = adaptive_sampling.next_batch(&variance);
+                    if batch_size == 0 {
+                        break;
                     }
-                    // Step 3: Use the sample arrangement
-                    for sample in sample_pattern {
+                    for _ in 0..batch_size {
+                        let sample_index = variance.num_samples;
+                        sampler.start_pixel_sample((x, y), sample_index);
+                        let (offset_x, offset_y) = sampler.get_2d();
+                        let (film_x, film_y) = (x as f64 + offset_x, y as f64 + offset_y);
                         let ray = camera.create_ray(
-                            (x as f64 + sample.0) / (num_x as f64),
-                            (y as f64 + sample.1) / (num_y as f64),
+                            film_x / (num_x as f64),
+                            film_y / (num_y as f64),
+                            &mut *sampler,
                         );
-                        _color += get_color(&ray, &_world, 0);
+                        // Film positions that receive no light still count as (black) samples
+                        let (mut color, mut sample_aov) = match ray {
+                            Some(ray) => get_color(&ray, &_world, &camera_media, &mut *sampler),
+                            None => (Vec3::new(0.0, 0.0, 0.0), AovSample::new()),
+                        };
+                        let exposure =
+                            camera.exposure(film_x / (num_x as f64), film_y / (num_y as f64));
+                        color = exposure * color;
+                        sample_aov.scale_lighting(exposure);
+                        variance.add(&color);
+                        samples.push(FilmSample {
+                            x: film_x,
+                            y: film_y,
+                            color,
+                        });
+                        aov_pixel.add(&sample_aov);
                     }
-                    _color
-                }));
+                }
+                row.push((samples, aov_pixel));
             }
-            for thread in child_threads {
-                color += thread.join().unwrap();
+            sender.send((y, row)).unwrap();
+        }));
+    }
+    drop(sender);
+
+    let mut pending_rows = BTreeMap::new();
+    let mut next_splat_row = 0;
+    for (y, row) in receiver {
+        pending_rows.insert(y, row);
+        while let Some(row) = pending_rows.remove(&next_splat_row) {
+            for (x, (samples, aov_pixel)) in row.into_iter().enumerate() {
+                for sample in samples.iter() {
+                    film.add_sample(sample);
+                }
+                let heat =
+                    adaptive::heatmap_color(aov_pixel.num_samples as usize, &adaptive_sampling);
+                let heat_pixel = image::Rgb([
+                    (heat.r() * 255.99) as u8,
+                    (heat.g() * 255.99) as u8,
+                    (heat.b() * 255.99) as u8,
+                ]);
+                // Invert y coordinate
+                heatmap_buff.put_pixel(x as u32, (num_y - 1) - next_splat_row, heat_pixel);
+                aov_buff.put_pixel(x as u32, (num_y - 1) - next_splat_row, aov_pixel);
             }
-            color /= (n * m * num_threads) as f64;
+            next_splat_row += 1;
+            progress_bar.inc(num_x as u64);
+        }
+    }
+    // A worker which panicked never sends the row it was tracing, so the rows from
+    // there onwards can't be splatted
+    let num_panicked = workers
+        .into_iter()
+        .map(|worker| worker.join())
+        .filter(|result| result.is_err())
+        .count();
+    if num_panicked > 0 || next_splat_row != num_y {
+        progress_bar.finish_and_clear();
+        return Err(format!(
+            "{} worker thread(s) panicked, and only {} of {} rows were traced",
+            num_panicked, next_splat_row, num_y
+        ));
+    }
+    for y in 0..num_y {
+        for x in 0..num_x {
+            let color = film.get_pixel(x, y);
             let r = (color.r().min(1.0).sqrt() * 255.99) as u8;
             let g = (color.g().min(1.0).sqrt() * 255.99) as u8;
             let b = (color.b().min(1.0).sqrt() * 255.99) as u8;
@@ -708,16 +2232,29 @@ fn main() {
             // Invert y coordinate
             img_buff.put_pixel(x, (num_y - 1) - y, pixel);
         }
-        progress_bar.inc(num_x as u64);
     }
     progress_bar.println("Scene Tracing Completed.");
     progress_bar.finish();
 
-    let path = &Path::new("output.png");
+    let path_name = &frame_range.frame_path("output.png", frame);
+    let path = &Path::new(path_name);
     match File::create(path) {
         Ok(_) => {
             let _ = image::ImageRgb8(img_buff).save(path);
         }
         Err(e) => println!("Failed to open file: {:?}", e),
     }
+    if settings.write_sample_heatmap {
+        let heatmap_path_name = &frame_range.frame_path("output_samples.png", frame);
+        let heatmap_path = &Path::new(heatmap_path_name);
+        if let Err(e) = image::ImageRgb8(heatmap_buff).save(heatmap_path) {
+            println!("Failed to write sample heatmap: {:?}", e);
+        }
+    }
+    let aov_path_name = &frame_range.frame_path("output_aovs.exr", frame);
+    let aov_path = &Path::new(aov_path_name);
+    if let Err(e) = aov_buff.write_exr(aov_path) {
+        println!("Failed to write render passes to {:?}: {:?}", aov_path, e);
+    }
+    Ok(())
 }
@@ -1,5 +1,6 @@
 use hitable::hit_record::HitRecord;
 use ray::Ray;
+use sampler::sampler::Sampler;
 use std::marker::{Send, Sync};
 use vec3::Vec3;
 
@@ -11,7 +12,16 @@ pub trait Material: Send + Sync {
     ///   - Ray: The scattered ray,
     ///   - Vec3: The attenuation of the scattered ray,
     ///   - bool: Whether or not the input ray was successfully scattered
-    fn scatter(&self, input_ray: &Ray, hit_record: &HitRecord) -> (Ray, Vec3, bool);
+    /// #### Arguments
+    /// - `input_ray`: The ray that hit the material
+    /// - `hit_record`: A record of the ray's collision with the material
+    /// - `sampler`: Provides the sample values used to choose the scattered direction
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool);
     /// Calculates a light's emitted color value.
     /// #### Arguments
     /// - `u`: Texture coordinate (u,_)
@@ -21,4 +31,15 @@ pub trait Material: Send + Sync {
         #![allow(unused_variables)]
         Vec3::new(0.0, 0.0, 0.0)
     }
+    /// Calculates a light's emitted color value at a Ray's hit, which gives lights access
+    /// to the surface's attributes (e.g. its color) as well as its texture coordinates
+    fn emit_at_hit(&self, hit_record: &HitRecord) -> Vec3 {
+        self.emit(hit_record.u, hit_record.v, &hit_record.hit_point)
+    }
+    /// Calculates the base color of the material at a given hit, as written to the
+    /// albedo render pass.
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        #![allow(unused_variables)]
+        Vec3::new(0.0, 0.0, 0.0)
+    }
 }
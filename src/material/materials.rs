@@ -1,12 +1,17 @@
 use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
 use hitable::utils;
 use material::material::Material;
-use rand::distributions::{Distribution, Uniform};
+use medium::phase_function::PhaseFunction;
+use medium::phase_functions::IsotropicPhase;
 use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::consts::PI;
+use std::f64::MAX as FLOAT_MAX;
 use std::sync::Arc;
 use texture::texture::Texture;
 use texture::textures::ConstantTexture;
-use vec3::{dot, unit_vector, Vec3};
+use vec3::{cross, dot, unit_vector, Vec3};
 
 /// A Lambertian material is a "matte", or diffusely reflecting, surface.
 #[derive(Clone)]
@@ -23,19 +28,26 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, input_ray: &Ray, hit_record: &HitRecord) -> (Ray, Vec3, bool) {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
         let target =
-            hit_record.hit_point + hit_record.normal + utils::random_point_in_unit_sphere();
+            hit_record.hit_point + hit_record.normal + utils::random_point_in_unit_sphere(sampler);
         let scattered_ray = Ray::new(
             hit_record.hit_point,
             target - hit_record.hit_point,
             input_ray.time,
         );
-        let attenuation = self
-            .albedo
-            .value(hit_record.u, hit_record.v, &hit_record.hit_point);
+        let attenuation = self.albedo.value_at_hit(hit_record);
         (scattered_ray, attenuation, true)
     }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        self.albedo.value_at_hit(hit_record)
+    }
 }
 
 /// A metallic surface. The fuzziness field dictates how polished the surface appears.
@@ -65,16 +77,19 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, input_ray: &Ray, hit_record: &HitRecord) -> (Ray, Vec3, bool) {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
         let scattered_ray = Ray::new(
             hit_record.hit_point,
             utils::reflect(&unit_vector(input_ray.direction), &hit_record.normal)
-                + self.fuzziness * utils::random_point_in_unit_sphere(),
+                + self.fuzziness * utils::random_point_in_unit_sphere(sampler),
             input_ray.time,
         );
-        let attenuation = self
-            .albedo
-            .value(hit_record.u, hit_record.v, &hit_record.hit_point);
+        let attenuation = self.albedo.value_at_hit(hit_record);
         // If the cosine of the angle between the scattered ray and the surface normal is <= 0,
         // the ray has been scattered under the object's surface.
         let did_scatter = dot(&scattered_ray.direction, &hit_record.normal) > 0.0;
@@ -84,6 +99,10 @@ impl Material for Metal {
     fn emit(&self, u: f64, v: f64, hit_point: &Vec3) -> Vec3 {
         self.emittance_albedo.value(u, v, hit_point)
     }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        self.albedo.value_at_hit(hit_record)
+    }
 }
 
 /// A surface that splits a light ray into both a refracted and reflected ray (e.g. glass, water, etc.).
@@ -102,9 +121,12 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, input_ray: &Ray, hit_record: &HitRecord) -> (Ray, Vec3, bool) {
-        let range = Uniform::new_inclusive(0.0, 1.0);
-        let mut rng = rand::thread_rng();
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
         // The glass surface does not absorb anything, so attenuation is set to 1
         let attenuation = Vec3::new(1.0, 1.0, 1.0);
         let dot_prod = dot(&input_ray.direction, &hit_record.normal);
@@ -138,7 +160,7 @@ impl Material for Dielectric {
             };
 
         let scattered_ray: Ray;
-        if range.sample(&mut rng) <= reflect_probability {
+        if sampler.get_1d() <= reflect_probability {
             scattered_ray = Ray::new(
                 hit_record.hit_point,
                 utils::reflect(&input_ray.direction, &hit_record.normal),
@@ -150,6 +172,10 @@ impl Material for Dielectric {
 
         (scattered_ray, attenuation, true)
     }
+
+    fn albedo(&self, _hit_record: &HitRecord) -> Vec3 {
+        Vec3::new(1.0, 1.0, 1.0)
+    }
 }
 
 /// A material that emits diffused (i.e. non-concentrated) light
@@ -165,7 +191,12 @@ impl DiffuseLight {
 }
 
 impl Material for DiffuseLight {
-    fn scatter(&self, input_ray: &Ray, _hit_record: &HitRecord) -> (Ray, Vec3, bool) {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        _hit_record: &HitRecord,
+        _sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
         let blank_ray = Ray::new(input_ray.direction, input_ray.origin, 0.0);
         (blank_ray, Vec3::new(0.0, 0.0, 0.0), false)
     }
@@ -173,6 +204,10 @@ impl Material for DiffuseLight {
     fn emit(&self, u: f64, v: f64, hit_point: &Vec3) -> Vec3 {
         self.texture.value(u, v, hit_point)
     }
+
+    fn emit_at_hit(&self, hit_record: &HitRecord) -> Vec3 {
+        self.texture.value_at_hit(hit_record)
+    }
 }
 
 /// A material that uniformly scatters light in all directions
@@ -182,17 +217,194 @@ pub struct Isotropic {
 }
 
 impl Material for Isotropic {
-    fn scatter(&self, input_ray: &Ray, hit_record: &HitRecord) -> (Ray, Vec3, bool) {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
         let scattered_ray = Ray::new(
             hit_record.hit_point,
-            utils::random_point_in_unit_sphere(),
+            utils::random_point_in_unit_sphere(sampler),
             input_ray.time,
         );
-        let attenuation = self
-            .albedo
-            .value(hit_record.u, hit_record.v, &hit_record.hit_point);
+        let attenuation = self.albedo.value_at_hit(hit_record);
+        (scattered_ray, attenuation, true)
+    }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        self.albedo.value_at_hit(hit_record)
+    }
+}
+
+/// A material that scatters light within a participating medium, in directions distributed
+/// according to a phase function
+#[derive(Clone)]
+pub struct MediumScattering {
+    pub albedo: Arc<Texture>,
+    pub phase_function: Arc<PhaseFunction>,
+}
+
+impl Material for MediumScattering {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
+        let (direction, _pdf) = self
+            .phase_function
+            .sample(&unit_vector(input_ray.direction), sampler);
+        let scattered_ray = Ray::new(hit_record.hit_point, direction, input_ray.time);
+        // Directions are sampled in proportion to the phase function, so only the albedo
+        // attenuates the scattered light
+        let attenuation = self.albedo.value_at_hit(hit_record);
         (scattered_ray, attenuation, true)
     }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        self.albedo.value_at_hit(hit_record)
+    }
+}
+
+/// The most scattering events in a random walk beneath a surface, after which the light
+/// is considered absorbed
+const MAX_SUBSURFACE_STEPS: usize = 256;
+
+/// Weights a path sampled with one of the channels, by the balance heuristic over the
+/// densities with which each channel would have sampled it
+fn balance_weight(throughput: &Vec3, pdf: &Vec3) -> Vec3 {
+    let mean_pdf = (pdf.x() + pdf.y() + pdf.z()) / 3.0;
+    if mean_pdf > 0.0 {
+        *throughput / mean_pdf
+    } else {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+}
+
+/// A translucent material (e.g. skin, wax, marble or milk), through which light scatters
+/// beneath the surface before leaving it again, possibly far from where it entered.
+///
+/// Light is traced on a random walk through the interior of the closed object, so the
+/// material must know the object it is applied to; `Subsurface` wraps an object with it.
+/// (Source: Chiang et al., `Practical and Controllable Subsurface Scattering for Production
+/// Path Tracing`, with the albedo inversion of Cycles' random walk)
+#[derive(Clone)]
+pub struct SubsurfaceScattering {
+    /// The object through whose interior light scatters
+    pub object: Arc<Hitable>,
+    /// The color of the surface, after light has scattered any number of times beneath it
+    pub albedo: Arc<Texture>,
+    /// The average distance that light of each channel travels between scattering events
+    /// (so that, for skin, red light travels further than green or blue)
+    pub mean_free_path: Arc<Texture>,
+    /// The smooth dielectric surface through which light enters and leaves the object
+    pub interface: Dielectric,
+    pub phase_function: Arc<PhaseFunction>,
+}
+
+impl SubsurfaceScattering {
+    /// Constructs a new subsurface scattering material which scatters light isotropically
+    /// #### Arguments:
+    /// - `object`: The closed object (with outward-facing normals) to which it is applied
+    /// - `albedo`: The color of the surface
+    /// - `mean_free_path`: The distance light of each channel travels between scattering events
+    /// - `refractive_index`: The refractive index of the surface
+    pub fn new(
+        object: Arc<Hitable>,
+        albedo: Arc<Texture>,
+        mean_free_path: Arc<Texture>,
+        refractive_index: f64,
+    ) -> Self {
+        SubsurfaceScattering {
+            object,
+            albedo,
+            mean_free_path,
+            interface: Dielectric::new(refractive_index),
+            phase_function: Arc::new(IsotropicPhase),
+        }
+    }
+
+    /// Calculates the scattering and extinction coefficients of the object beneath a hit,
+    /// choosing the albedo of each single scattering event so that light scattered any
+    /// number of times takes on the albedo of the surface
+    fn interior_coefficients(&self, hit_record: &HitRecord) -> (Vec3, Vec3) {
+        let albedo = self.albedo.value_at_hit(hit_record);
+        let mean_free_path = self.mean_free_path.value_at_hit(hit_record);
+        let mut sigma_s = Vec3::new(0.0, 0.0, 0.0);
+        let mut sigma_t = Vec3::new(0.0, 0.0, 0.0);
+        for channel in 0..3 {
+            let a = albedo[channel].max(0.0).min(0.999);
+            let root = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+            sigma_t[channel] = 1.0 / mean_free_path[channel].max(1e-6);
+            sigma_s[channel] = (1.0 - root * root) * sigma_t[channel];
+        }
+        (sigma_s, sigma_t)
+    }
+}
+
+impl Material for SubsurfaceScattering {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
+        let (mut ray, _, _) = self.interface.scatter(input_ray, hit_record, sampler);
+        if dot(&ray.direction, &hit_record.normal) >= 0.0 {
+            // The light is reflected by the surface, without entering the object
+            return (ray, Vec3::new(1.0, 1.0, 1.0), true);
+        }
+
+        let (sigma_s, sigma_t) = self.interior_coefficients(hit_record);
+        // Every distance of the walk is sampled with the extinction of a single (hero)
+        // channel, while the densities of sampling the walk with each channel are tracked
+        let hero = ((3.0 * sampler.get_1d()) as usize).min(2);
+        let mut throughput = Vec3::new(1.0, 1.0, 1.0);
+        let mut pdf = Vec3::new(1.0, 1.0, 1.0);
+        for _ in 0..MAX_SUBSURFACE_STEPS {
+            let ref mut exit = HitRecord::new();
+            if !self.object.hit(&ray, 0.00001, FLOAT_MAX, exit, sampler) {
+                // The walk has escaped through a hole in the object
+                return (ray, balance_weight(&throughput, &pdf), true);
+            }
+            let speed = ray.direction.length();
+            let distance = -(1.0 - sampler.get_1d()).ln() / sigma_t[hero];
+            if distance < exit.t * speed {
+                let transmittance = Vec3::new(
+                    (-sigma_t.x() * distance).exp(),
+                    (-sigma_t.y() * distance).exp(),
+                    (-sigma_t.z() * distance).exp(),
+                );
+                throughput *= sigma_s * transmittance;
+                pdf *= sigma_t * transmittance;
+                let (direction, _pdf) = self
+                    .phase_function
+                    .sample(&unit_vector(ray.direction), sampler);
+                ray = Ray::new(ray.point_at_param(distance / speed), direction, ray.time);
+            } else {
+                let distance = exit.t * speed;
+                let transmittance = Vec3::new(
+                    (-sigma_t.x() * distance).exp(),
+                    (-sigma_t.y() * distance).exp(),
+                    (-sigma_t.z() * distance).exp(),
+                );
+                throughput *= transmittance;
+                pdf *= transmittance;
+                ray = self.interface.scatter(&ray, exit, sampler).0;
+                if dot(&ray.direction, &exit.normal) > 0.0 {
+                    return (ray, balance_weight(&throughput, &pdf), true);
+                }
+                // Otherwise the light is reflected back into the object
+            }
+        }
+
+        (ray, Vec3::new(0.0, 0.0, 0.0), false)
+    }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        self.albedo.value_at_hit(hit_record)
+    }
 }
 
 /// A (simulated) glossy material.
@@ -219,10 +431,15 @@ impl Glossy {
 }
 
 impl Material for Glossy {
-    fn scatter(&self, input_ray: &Ray, hit_record: &HitRecord) -> (Ray, Vec3, bool) {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
         let attenuation;
         let scattered_ray;
-        if Uniform::new(0.0, 1.0).sample(&mut rand::thread_rng())
+        if sampler.get_1d()
             <= utils::schlick_approx(
                 -dot(&input_ray.direction, &hit_record.normal) / input_ray.direction.length(),
                 1.75,
@@ -232,23 +449,21 @@ impl Material for Glossy {
             scattered_ray = Ray::new(
                 hit_record.hit_point,
                 utils::reflect(&unit_vector(input_ray.direction), &hit_record.normal)
-                    + self.glossiness * utils::random_point_in_unit_sphere(),
+                    + self.glossiness * utils::random_point_in_unit_sphere(sampler),
                 input_ray.time,
             );
-            attenuation =
-                self.specular_albedo
-                    .value(hit_record.u, hit_record.v, &hit_record.hit_point);
+            attenuation = self.specular_albedo.value_at_hit(hit_record);
         } else {
             // Diffuse Ray
             scattered_ray = Ray::new(
                 hit_record.hit_point,
-                hit_record.hit_point + hit_record.normal + utils::random_point_in_unit_sphere()
+                hit_record.hit_point
+                    + hit_record.normal
+                    + utils::random_point_in_unit_sphere(sampler)
                     - hit_record.hit_point,
                 input_ray.time,
             );
-            attenuation = self
-                .albedo
-                .value(hit_record.u, hit_record.v, &hit_record.hit_point);
+            attenuation = self.albedo.value_at_hit(hit_record);
         }
         (
             scattered_ray,
@@ -258,4 +473,1010 @@ impl Material for Glossy {
             dot(&scattered_ray.direction, &hit_record.normal) > 0.0,
         )
     }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        self.albedo.value_at_hit(hit_record)
+    }
+}
+
+/// A blend of two materials, in proportions given by a texture (e.g. patches of dust or
+/// rust over a metal, or a painted pattern). At each hit, one of the materials is chosen
+/// to scatter the light, with the mask's value as the probability of choosing `second`.
+#[derive(Clone)]
+pub struct MixMaterial {
+    pub first: Arc<Material>,
+    pub second: Arc<Material>,
+    /// The proportion of `second` in the blend, as the average of the texture's channels
+    pub mask: Arc<Texture>,
+}
+
+impl MixMaterial {
+    pub fn new(first: Arc<Material>, second: Arc<Material>, mask: Arc<Texture>) -> Self {
+        MixMaterial {
+            first,
+            second,
+            mask,
+        }
+    }
+
+    /// Calculates the proportion of `second` in the blend at a hit
+    fn weight(&self, hit_record: &HitRecord) -> f64 {
+        let mask = self.mask.value_at_hit(hit_record);
+        ((mask.x() + mask.y() + mask.z()) / 3.0).max(0.0).min(1.0)
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
+        if sampler.get_1d() < self.weight(hit_record) {
+            self.second.scatter(input_ray, hit_record, sampler)
+        } else {
+            self.first.scatter(input_ray, hit_record, sampler)
+        }
+    }
+
+    fn emit_at_hit(&self, hit_record: &HitRecord) -> Vec3 {
+        let weight = self.weight(hit_record);
+        (1.0 - weight) * self.first.emit_at_hit(hit_record)
+            + weight * self.second.emit_at_hit(hit_record)
+    }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        let weight = self.weight(hit_record);
+        (1.0 - weight) * self.first.albedo(hit_record) + weight * self.second.albedo(hit_record)
+    }
+}
+
+/// The most times light is reflected between the coat and the base of a layered material,
+/// after which it is considered absorbed
+const MAX_LAYER_BOUNCES: usize = 16;
+
+/// A material coated with a thin dielectric layer (e.g. clear-coated car paint, varnished
+/// wood or lacquer), which reflects some light off its surface and tints the light that
+/// passes through it to the base material beneath.
+///
+/// Light is traced on a random walk between the coat and the base, so it may be reflected
+/// between them several times before leaving the surface.
+#[derive(Clone)]
+pub struct LayeredMaterial {
+    /// The material beneath the coat
+    pub base: Arc<Material>,
+    /// The refractive index of the coat
+    pub refractive_index: f64,
+    /// How rough the surface of the coat is, in [0, 1] (where 0 is perfectly smooth)
+    pub roughness: f64,
+    /// The fraction of light absorbed per unit of distance within the coat, in each channel
+    pub absorption: Vec3,
+    /// The thickness of the coat
+    pub thickness: f64,
+}
+
+impl LayeredMaterial {
+    /// Constructs a new material with a clear (non-absorbing) coat
+    /// #### Arguments:
+    /// - `base`: The material beneath the coat
+    /// - `refractive_index`: The refractive index of the coat
+    /// - `roughness`: How rough the surface of the coat is, in [0, 1]
+    pub fn new(base: Arc<Material>, refractive_index: f64, roughness: f64) -> Self {
+        LayeredMaterial::new_absorbing(
+            base,
+            refractive_index,
+            roughness,
+            Vec3::new(0.0, 0.0, 0.0),
+            0.0,
+        )
+    }
+
+    /// Constructs a new material with a tinted coat, which absorbs more light the further
+    /// light travels through it (so the tint deepens at grazing angles)
+    /// #### Arguments:
+    /// - `base`: The material beneath the coat
+    /// - `refractive_index`: The refractive index of the coat
+    /// - `roughness`: How rough the surface of the coat is, in [0, 1]
+    /// - `absorption`: The absorption coefficient of the coat in each channel
+    /// - `thickness`: The thickness of the coat
+    pub fn new_absorbing(
+        base: Arc<Material>,
+        refractive_index: f64,
+        roughness: f64,
+        absorption: Vec3,
+        thickness: f64,
+    ) -> Self {
+        LayeredMaterial {
+            base,
+            refractive_index,
+            roughness: roughness.max(0.0).min(1.0),
+            absorption,
+            thickness,
+        }
+    }
+
+    /// Samples the normal of the (rough) coat's surface at a point, around the normal of
+    /// the smooth surface
+    fn coat_normal(&self, normal: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        if self.roughness > 0.0 {
+            unit_vector(*normal + self.roughness * utils::random_point_in_unit_sphere(sampler))
+        } else {
+            *normal
+        }
+    }
+
+    /// Calculates the fraction of light which passes through the coat in a given direction
+    fn coat_transmittance(&self, direction: &Vec3, normal: &Vec3) -> Vec3 {
+        let distance = self.thickness / dot(direction, normal).abs().max(1e-3);
+        Vec3::new(
+            (-self.absorption.x() * distance).exp(),
+            (-self.absorption.y() * distance).exp(),
+            (-self.absorption.z() * distance).exp(),
+        )
+    }
+}
+
+impl Material for LayeredMaterial {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
+        let normal = hit_record.normal;
+        let incoming = unit_vector(input_ray.direction);
+        if dot(&incoming, &normal) >= 0.0 {
+            // The coat only covers the outside of the surface
+            return self.base.scatter(input_ray, hit_record, sampler);
+        }
+        let point = hit_record.hit_point;
+        let time = input_ray.time;
+
+        // The light is either reflected by the coat, or refracted into it
+        let coat_normal = self.coat_normal(&normal, sampler);
+        let cosine = -dot(&incoming, &coat_normal);
+        let mut direction =
+            match utils::refract(&incoming, &coat_normal, 1.0 / self.refractive_index) {
+                Some(refracted)
+                    if sampler.get_1d() > utils::schlick_approx(cosine, self.refractive_index) =>
+                {
+                    refracted
+                }
+                _ => {
+                    let reflected = utils::reflect(&incoming, &coat_normal);
+                    let did_scatter = dot(&reflected, &normal) > 0.0;
+                    return (
+                        Ray::new(point, reflected, time),
+                        Vec3::new(1.0, 1.0, 1.0),
+                        did_scatter,
+                    );
+                }
+            };
+
+        let mut attenuation = Vec3::new(1.0, 1.0, 1.0);
+        for _ in 0..MAX_LAYER_BOUNCES {
+            // The light crosses the coat down to the base, which scatters it
+            attenuation *= self.coat_transmittance(&direction, &normal);
+            let (base_ray, base_attenuation, did_scatter) =
+                self.base
+                    .scatter(&Ray::new(point, direction, time), hit_record, sampler);
+            if !did_scatter {
+                break;
+            }
+            attenuation *= base_attenuation;
+            direction = unit_vector(base_ray.direction);
+            if dot(&direction, &normal) <= 0.0 {
+                // The base transmits the light through the surface (e.g. a glass base)
+                return (Ray::new(point, direction, time), attenuation, true);
+            }
+
+            // The light crosses the coat back up, and is either refracted out of it or
+            // reflected back down to the base
+            attenuation *= self.coat_transmittance(&direction, &normal);
+            let coat_normal = self.coat_normal(&normal, sampler);
+            let cosine = self.refractive_index * dot(&direction, &coat_normal);
+            direction = match utils::refract(&direction, &-coat_normal, self.refractive_index) {
+                Some(refracted)
+                    if sampler.get_1d() > utils::schlick_approx(cosine, self.refractive_index) =>
+                {
+                    let did_scatter = dot(&refracted, &normal) > 0.0;
+                    return (Ray::new(point, refracted, time), attenuation, did_scatter);
+                }
+                _ => {
+                    // Reflections off a rough coat are kept heading down towards the base
+                    let reflected = utils::reflect(&direction, &coat_normal);
+                    if dot(&reflected, &normal) < 0.0 {
+                        reflected
+                    } else {
+                        utils::reflect(&direction, &normal)
+                    }
+                }
+            };
+        }
+
+        (
+            Ray::new(point, direction, time),
+            Vec3::new(0.0, 0.0, 0.0),
+            false,
+        )
+    }
+
+    fn emit_at_hit(&self, hit_record: &HitRecord) -> Vec3 {
+        self.base.emit_at_hit(hit_record)
+    }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        self.base.albedo(hit_record)
+    }
+}
+
+/// A physically based "uber" material following the Disney principled BSDF, whose
+/// parameters blend between diffuse, specular, sheen, clearcoat and transmission lobes so
+/// that most materials (e.g. plastic, metal, cloth, car paint or glass) can be described
+/// with the one parameter set. Parameters are in [0, 1] unless noted otherwise.
+///
+/// At each hit, one lobe is chosen (in proportion to an estimate of the light it
+/// reflects) and importance sampled, with the anisotropic specular lobes sampled by their
+/// distribution of visible normals.
+/// (Source: Burley, `Physically-Based Shading at Disney` and `Extending the Disney BRDF to
+/// a BSDF with Integrated Subsurface Scattering`, with the sampling of Heitz, `Sampling
+/// the GGX Distribution of Visible Normals`)
+#[derive(Clone)]
+pub struct Principled {
+    /// The color of the surface (the diffuse color of dielectrics, or the specular color
+    /// of metals)
+    pub base_color: Arc<Texture>,
+    /// Flattens the diffuse lobe, approximating the appearance of subsurface scattering
+    pub subsurface: f64,
+    /// Blends between a dielectric (0) and a metal (1)
+    pub metallic: f64,
+    /// The strength of the specular reflection of dielectrics (where 0.5 is a reflectance
+    /// of 4% at normal incidence)
+    pub specular: f64,
+    /// Tints the specular reflection of dielectrics towards the base color
+    pub specular_tint: f64,
+    pub roughness: f64,
+    /// Stretches the specular highlights along the surface's tangent
+    pub anisotropic: f64,
+    /// The strength of a soft sheen at grazing angles (e.g. of cloth)
+    pub sheen: f64,
+    /// Tints the sheen towards the base color
+    pub sheen_tint: f64,
+    /// The strength of a second, clear specular layer (e.g. the lacquer of car paint)
+    pub clearcoat: f64,
+    /// The glossiness of the clearcoat (where 1 is the glossiest)
+    pub clearcoat_gloss: f64,
+    /// Blends between an opaque surface (0) and a transmissive dielectric such as glass (1)
+    pub transmission: f64,
+    /// The refractive index of the surface (used by the transmission lobe)
+    pub refractive_index: f64,
+}
+
+impl Principled {
+    /// Constructs a new principled material with the default parameters of the Disney BSDF
+    /// (a rough dielectric with a 4% specular reflectance), which can then be adjusted
+    pub fn new(base_color: Arc<Texture>) -> Self {
+        Principled {
+            base_color,
+            subsurface: 0.0,
+            metallic: 0.0,
+            specular: 0.5,
+            specular_tint: 0.0,
+            roughness: 0.5,
+            anisotropic: 0.0,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_gloss: 1.0,
+            transmission: 0.0,
+            refractive_index: 1.5,
+        }
+    }
+
+    /// Constructs the frame of the surface at a hit (with the normal as its Z axis), in
+    /// which the tangent (if the surface has one) sets the direction of anisotropy
+    fn shading_frame(&self, hit_record: &HitRecord, normal: &Vec3) -> (Vec3, Vec3) {
+        let tangent = hit_record.tangent - dot(&hit_record.tangent, normal) * *normal;
+        if tangent.squared_length() > 1e-12 {
+            let tangent = unit_vector(tangent);
+            (tangent, cross(normal, &tangent))
+        } else {
+            utils::orthonormal_basis(normal)
+        }
+    }
+
+    /// Samples the diffuse and sheen lobe, returning the scattered direction and its weight
+    fn sample_diffuse(&self, wo: &Vec3, base_color: &Vec3, sampler: &mut Sampler) -> (Vec3, Vec3) {
+        // Directions are sampled in proportion to their cosine
+        let mut wi = utils::random_unit_vector(sampler) + Vec3::new(0.0, 0.0, 1.0);
+        if wi.squared_length() < 1e-12 {
+            wi = Vec3::new(0.0, 0.0, 1.0);
+        }
+        let wi = unit_vector(wi);
+        let h = unit_vector(*wo + wi);
+        let cos_d = dot(&wi, &h);
+        let (fresnel_o, fresnel_i) = (schlick_weight(wo.z()), schlick_weight(wi.z()));
+        let fd_90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let diffuse = (1.0 + (fd_90 - 1.0) * fresnel_i) * (1.0 + (fd_90 - 1.0) * fresnel_o);
+        let fss_90 = self.roughness * cos_d * cos_d;
+        let fss = (1.0 + (fss_90 - 1.0) * fresnel_i) * (1.0 + (fss_90 - 1.0) * fresnel_o);
+        let subsurface = 1.25 * (fss * (1.0 / (wi.z() + wo.z()).max(1e-6) - 0.5) + 0.5);
+        let sheen_color = lerp_color(
+            &Vec3::new(1.0, 1.0, 1.0),
+            &tint(base_color),
+            self.sheen_tint,
+        );
+        let sheen = self.sheen * schlick_weight(cos_d) * sheen_color;
+        let flattened = (1.0 - self.subsurface) * diffuse + self.subsurface * subsurface;
+        (wi, flattened * *base_color + PI * sheen)
+    }
+
+    /// Samples the anisotropic specular reflection lobe
+    fn sample_specular(
+        &self,
+        wo: &Vec3,
+        specular_color: &Vec3,
+        sampler: &mut Sampler,
+    ) -> Option<(Vec3, Vec3)> {
+        let (alpha_x, alpha_y) = self.specular_roughness();
+        let (u, v) = sampler.get_2d();
+        let h = sample_ggx_visible_normal(wo, alpha_x, alpha_y, u, v);
+        let wi = 2.0 * dot(wo, &h) * h - *wo;
+        if wi.z() <= 0.0 {
+            return None;
+        }
+        let fresnel = *specular_color
+            + schlick_weight(dot(&wi, &h)) * (Vec3::new(1.0, 1.0, 1.0) - *specular_color);
+        let shadowing = smith_g2(wo, &wi, alpha_x, alpha_y) / smith_g1(wo, alpha_x, alpha_y);
+        Some((wi, shadowing * fresnel))
+    }
+
+    /// Samples the transmission lobe of a rough dielectric, which either reflects light or
+    /// refracts it through the surface. The base color tints the light at each refraction,
+    /// by its square root, so light passing into and back out of an object takes it on.
+    fn sample_transmission(
+        &self,
+        wo: &Vec3,
+        base_color: &Vec3,
+        entering: bool,
+        sampler: &mut Sampler,
+    ) -> Option<(Vec3, Vec3)> {
+        let (alpha_x, alpha_y) = self.specular_roughness();
+        let (u, v) = sampler.get_2d();
+        let h = sample_ggx_visible_normal(wo, alpha_x, alpha_y, u, v);
+        let cos_o = dot(wo, &h);
+        // The refractive index on the far side of the surface, relative to the near side
+        let eta = if entering {
+            self.refractive_index
+        } else {
+            1.0 / self.refractive_index
+        };
+        let sin_2_t = (1.0 - cos_o * cos_o) / (eta * eta);
+        let reflectance = fresnel_dielectric(cos_o, eta);
+        let (wi, tint) = if sin_2_t >= 1.0 || sampler.get_1d() < reflectance {
+            let wi = 2.0 * cos_o * h - *wo;
+            if wi.z() <= 0.0 {
+                return None;
+            }
+            (wi, Vec3::new(1.0, 1.0, 1.0))
+        } else {
+            let cos_t = (1.0 - sin_2_t).sqrt();
+            let wi = -*wo / eta + (cos_o / eta - cos_t) * h;
+            if wi.z() >= 0.0 {
+                return None;
+            }
+            let tint = Vec3::new(
+                base_color.x().sqrt(),
+                base_color.y().sqrt(),
+                base_color.z().sqrt(),
+            );
+            (unit_vector(wi), tint)
+        };
+        let shadowing = smith_g2(wo, &wi, alpha_x, alpha_y) / smith_g1(wo, alpha_x, alpha_y);
+        Some((wi, shadowing * tint))
+    }
+
+    /// Samples the clearcoat lobe, whose highlights have the long tails of the GTR1
+    /// distribution
+    fn sample_clearcoat(&self, wo: &Vec3, sampler: &mut Sampler) -> Option<(Vec3, Vec3)> {
+        let alpha = 0.1 + (0.001 - 0.1) * self.clearcoat_gloss;
+        let alpha_2 = alpha * alpha;
+        let (u, v) = sampler.get_2d();
+        let cos_h = ((1.0 - alpha_2.powf(1.0 - u)) / (1.0 - alpha_2))
+            .max(0.0)
+            .sqrt();
+        let sin_h = (1.0 - cos_h * cos_h).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let h = Vec3::new(sin_h * phi.cos(), sin_h * phi.sin(), cos_h);
+        let cos_oh = dot(wo, &h);
+        let wi = 2.0 * cos_oh * h - *wo;
+        if wi.z() <= 0.0 || cos_oh <= 0.0 {
+            return None;
+        }
+        let fresnel = 0.04 + 0.96 * schlick_weight(dot(&wi, &h));
+        let shadowing = separable_smith_ggx(wo.z(), 0.25) * separable_smith_ggx(wi.z(), 0.25);
+        // The distribution cancels with the density of sampling it
+        let weight = 0.25 * self.clearcoat * fresnel * shadowing * 4.0 * cos_oh * wi.z() / cos_h;
+        Some((wi, Vec3::new(weight, weight, weight)))
+    }
+
+    /// Calculates the roughness of the specular lobes along the tangent and bitangent
+    fn specular_roughness(&self) -> (f64, f64) {
+        let aspect = (1.0 - 0.9 * self.anisotropic).sqrt();
+        let alpha = self.roughness * self.roughness;
+        ((alpha / aspect).max(0.001), (alpha * aspect).max(0.001))
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
+        let base_color = self.base_color.value_at_hit(hit_record);
+        let outgoing = -unit_vector(input_ray.direction);
+        // Lobes are evaluated on the side of the surface from which the ray arrives
+        let entering = dot(&outgoing, &hit_record.normal) > 0.0;
+        let normal = if entering {
+            hit_record.normal
+        } else {
+            -hit_record.normal
+        };
+        let (tangent, bitangent) = self.shading_frame(hit_record, &normal);
+        let wo = Vec3::new(
+            dot(&outgoing, &tangent),
+            dot(&outgoing, &bitangent),
+            dot(&outgoing, &normal),
+        );
+
+        // The weights of the lobes, and the probabilities of choosing them
+        let diffuse_weight = (1.0 - self.metallic) * (1.0 - self.transmission);
+        let transmission_weight = (1.0 - self.metallic) * self.transmission;
+        let specular_weight = 1.0 - transmission_weight;
+        let dielectric_specular = 0.08
+            * self.specular
+            * lerp_color(
+                &Vec3::new(1.0, 1.0, 1.0),
+                &tint(&base_color),
+                self.specular_tint,
+            );
+        let specular_color = lerp_color(&dielectric_specular, &base_color, self.metallic);
+        let fresnel_o = schlick_weight(wo.z());
+        let probabilities = [
+            diffuse_weight * (luminance(&base_color) + self.sheen),
+            specular_weight * (luminance(&specular_color) * (1.0 - fresnel_o) + fresnel_o),
+            transmission_weight,
+            0.25 * self.clearcoat * (0.04 + 0.96 * fresnel_o),
+        ];
+        let total: f64 = probabilities.iter().sum();
+        let failed = (
+            Ray::new(hit_record.hit_point, input_ray.direction, input_ray.time),
+            Vec3::new(0.0, 0.0, 0.0),
+            false,
+        );
+        if total <= 0.0 {
+            return failed;
+        }
+        let mut choice = sampler.get_1d() * total;
+        let mut lobe = 0;
+        while lobe < 3 && (choice >= probabilities[lobe] || probabilities[lobe] == 0.0) {
+            choice -= probabilities[lobe];
+            lobe += 1;
+        }
+        // Rounding may carry the choice past the last lobe with any weight
+        while probabilities[lobe] <= 0.0 {
+            lobe -= 1;
+        }
+
+        let sample = match lobe {
+            0 => {
+                let (wi, weight) = self.sample_diffuse(&wo, &base_color, sampler);
+                Some((wi, diffuse_weight * weight))
+            }
+            1 => self
+                .sample_specular(&wo, &specular_color, sampler)
+                .map(|(wi, weight)| (wi, specular_weight * weight)),
+            2 => self
+                .sample_transmission(&wo, &base_color, entering, sampler)
+                .map(|(wi, weight)| (wi, transmission_weight * weight)),
+            _ => self.sample_clearcoat(&wo, sampler),
+        };
+        match sample {
+            Some((wi, weight)) => {
+                let direction = wi.x() * tangent + wi.y() * bitangent + wi.z() * normal;
+                (
+                    Ray::new(hit_record.hit_point, direction, input_ray.time),
+                    weight * total / probabilities[lobe],
+                    true,
+                )
+            }
+            None => failed,
+        }
+    }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        self.base_color.value_at_hit(hit_record)
+    }
+}
+
+/// The number of scattering lobes modelled explicitly by the hair material
+/// (R, TT and TRT); the remaining paths are combined into a single lobe
+const HAIR_MAX_LOBE: usize = 3;
+
+/// A hair (or fur) fibre, modelled as a rough dielectric cylinder with an absorbing interior.
+///
+/// The material is intended for `Curves`, whose `v` texture coordinate gives the offset of
+/// a hit across the fibre's width and whose tangent runs along the fibre.
+/// (Source: Chiang et al., `A Practical and Controllable Hair and Fur Model for Production
+/// Path Tracing` and d'Eon et al., `An Energy-Conserving Hair Reflectance Model`, following
+/// the implementation in Pharr et al., `Physically Based Rendering`)
+#[derive(Clone)]
+pub struct Hair {
+    /// The absorption coefficient of the fibre's interior (per unit of its diameter)
+    pub sigma_a: Vec3,
+    /// The refractive index of the fibre
+    pub eta: f64,
+    /// The longitudinal roughness of the fibre, in [0, 1]
+    pub beta_m: f64,
+    /// The azimuthal roughness of the fibre, in [0, 1]
+    pub beta_n: f64,
+    /// The angle (in degrees) by which the scales on the fibre's surface are tilted
+    pub alpha: f64,
+}
+
+impl Hair {
+    /// Constructs a new hair material with the given absorption coefficient
+    /// #### Arguments:
+    /// - `sigma_a`: The absorption coefficient of the fibre's interior
+    /// - `beta_m`: The longitudinal roughness of the fibre, in [0, 1]
+    /// - `beta_n`: The azimuthal roughness of the fibre, in [0, 1]
+    pub fn new(sigma_a: Vec3, beta_m: f64, beta_n: f64) -> Self {
+        Hair {
+            sigma_a,
+            eta: 1.55,
+            beta_m,
+            beta_n,
+            alpha: 2.0,
+        }
+    }
+
+    /// Constructs a hair material whose color is given by the concentrations of the
+    /// pigments in human hair
+    /// #### Arguments:
+    /// - `eumelanin`: The concentration of eumelanin, which makes hair brown or black
+    ///   (roughly 0.3 for blonde, 1.3 for brown and 8 for black hair)
+    /// - `pheomelanin`: The concentration of pheomelanin, which makes hair red or orange
+    /// - `beta_m`: The longitudinal roughness of the fibre, in [0, 1]
+    /// - `beta_n`: The azimuthal roughness of the fibre, in [0, 1]
+    pub fn from_melanin(eumelanin: f64, pheomelanin: f64, beta_m: f64, beta_n: f64) -> Self {
+        let sigma_a =
+            eumelanin * Vec3::new(0.419, 0.697, 1.37) + pheomelanin * Vec3::new(0.187, 0.4, 1.05);
+        Hair::new(sigma_a, beta_m, beta_n)
+    }
+
+    /// Constructs a hair material whose overall color (after light has scattered through
+    /// many fibres) is approximately `color`
+    /// #### Arguments:
+    /// - `color`: The color of the hair, whose channels are in (0, 1]
+    /// - `beta_m`: The longitudinal roughness of the fibre, in [0, 1]
+    /// - `beta_n`: The azimuthal roughness of the fibre, in [0, 1]
+    pub fn from_color(color: Vec3, beta_m: f64, beta_n: f64) -> Self {
+        let scale = hair_color_scale(beta_n);
+        let mut sigma_a = Vec3::new(0.0, 0.0, 0.0);
+        for channel in 0..3 {
+            sigma_a[channel] = (color[channel].max(1e-4).ln() / scale).powi(2);
+        }
+        Hair::new(sigma_a, beta_m, beta_n)
+    }
+
+    /// The variances of the longitudinal scattering of each lobe
+    fn lobe_variances(&self) -> [f64; HAIR_MAX_LOBE + 1] {
+        let v = (0.726 * self.beta_m + 0.812 * self.beta_m.powi(2) + 3.7 * self.beta_m.powi(20))
+            .powi(2);
+        [v, 0.25 * v, 4.0 * v, 4.0 * v]
+    }
+
+    /// The scale of the logistic distribution of the azimuthal scattering
+    fn azimuthal_scale(&self) -> f64 {
+        0.626_657_069
+            * (0.265 * self.beta_n + 1.194 * self.beta_n.powi(2) + 5.372 * self.beta_n.powi(22))
+    }
+
+    /// Tilts the outgoing direction's longitudinal angle to account for the fibre's scales,
+    /// which shift each lobe by a different multiple of `alpha`
+    /// #### Returns:
+    /// - The sine and cosine of the tilted angle
+    fn tilt(&self, lobe: usize, sin_theta_o: f64, cos_theta_o: f64) -> (f64, f64) {
+        let mut sin_2k_alpha = [self.alpha.to_radians().sin(), 0.0, 0.0];
+        let mut cos_2k_alpha = [safe_sqrt(1.0 - sin_2k_alpha[0].powi(2)), 0.0, 0.0];
+        for i in 1..3 {
+            sin_2k_alpha[i] = 2.0 * cos_2k_alpha[i - 1] * sin_2k_alpha[i - 1];
+            cos_2k_alpha[i] = cos_2k_alpha[i - 1].powi(2) - sin_2k_alpha[i - 1].powi(2);
+        }
+        let (sin_theta, cos_theta) = match lobe {
+            0 => (
+                sin_theta_o * cos_2k_alpha[1] - cos_theta_o * sin_2k_alpha[1],
+                cos_theta_o * cos_2k_alpha[1] + sin_theta_o * sin_2k_alpha[1],
+            ),
+            1 => (
+                sin_theta_o * cos_2k_alpha[0] + cos_theta_o * sin_2k_alpha[0],
+                cos_theta_o * cos_2k_alpha[0] - sin_theta_o * sin_2k_alpha[0],
+            ),
+            2 => (
+                sin_theta_o * cos_2k_alpha[2] + cos_theta_o * sin_2k_alpha[2],
+                cos_theta_o * cos_2k_alpha[2] - sin_theta_o * sin_2k_alpha[2],
+            ),
+            _ => (sin_theta_o, cos_theta_o),
+        };
+        (sin_theta, cos_theta.abs())
+    }
+
+    /// Calculates the fraction of light carried by each lobe, along with the angle
+    /// (gamma_t) at which light travels through the fibre
+    /// #### Arguments:
+    /// - `h`: The offset of the hit across the fibre's width, in [-1, 1]
+    /// - `sin_theta_o`, `cos_theta_o`: The longitudinal angle of the outgoing direction
+    fn attenuations(&self, h: f64, sin_theta_o: f64, cos_theta_o: f64) -> ([Vec3; 4], f64) {
+        let sin_theta_t = sin_theta_o / self.eta;
+        let cos_theta_t = safe_sqrt(1.0 - sin_theta_t.powi(2));
+        let eta_p = safe_sqrt(self.eta.powi(2) - sin_theta_o.powi(2)) / cos_theta_o;
+        let sin_gamma_t = h / eta_p;
+        let cos_gamma_t = safe_sqrt(1.0 - sin_gamma_t.powi(2));
+        let distance = 2.0 * cos_gamma_t / cos_theta_t;
+        let transmittance = Vec3::new(
+            (-self.sigma_a.x() * distance).exp(),
+            (-self.sigma_a.y() * distance).exp(),
+            (-self.sigma_a.z() * distance).exp(),
+        );
+
+        let fresnel = fresnel_dielectric(cos_theta_o * safe_sqrt(1.0 - h * h), self.eta);
+        let r = Vec3::new(fresnel, fresnel, fresnel);
+        let tt = (1.0 - fresnel).powi(2) * transmittance;
+        let trt = fresnel * tt * transmittance;
+        let mut rest = Vec3::new(0.0, 0.0, 0.0);
+        for channel in 0..3 {
+            rest[channel] = fresnel * trt[channel] * transmittance[channel]
+                / (1.0 - transmittance[channel] * fresnel);
+        }
+        ([r, tt, trt, rest], safe_asin(sin_gamma_t))
+    }
+
+    /// Evaluates the fibre's scattering function (multiplied by the cosine of the incoming
+    /// direction), along with the pdf with which `sample` chooses the incoming direction
+    /// #### Arguments:
+    /// - `h`: The offset of the hit across the fibre's width, in [-1, 1]
+    /// - `wo`, `wi`: The outgoing and incoming directions, in the fibre's coordinate system
+    ///   (in which X runs along the fibre and Z faces the outgoing direction)
+    fn evaluate(&self, h: f64, wo: &Vec3, wi: &Vec3) -> (Vec3, f64) {
+        let sin_theta_o = wo.x();
+        let cos_theta_o = safe_sqrt(1.0 - sin_theta_o.powi(2));
+        let sin_theta_i = wi.x();
+        let cos_theta_i = safe_sqrt(1.0 - sin_theta_i.powi(2));
+        let phi = wi.z().atan2(wi.y()) - wo.z().atan2(wo.y());
+        let gamma_o = safe_asin(h);
+
+        let variances = self.lobe_variances();
+        let scale = self.azimuthal_scale();
+        let (attenuations, gamma_t) = self.attenuations(h, sin_theta_o, cos_theta_o);
+        let lobe_pdfs = lobe_pdfs(&attenuations);
+        let mut value = Vec3::new(0.0, 0.0, 0.0);
+        let mut pdf = 0.0;
+        for lobe in 0..HAIR_MAX_LOBE + 1 {
+            let (sin_theta_op, cos_theta_op) = self.tilt(lobe, sin_theta_o, cos_theta_o);
+            let longitudinal = longitudinal_scattering(
+                cos_theta_i,
+                cos_theta_op,
+                sin_theta_i,
+                sin_theta_op,
+                variances[lobe],
+            );
+            let azimuthal = if lobe < HAIR_MAX_LOBE {
+                azimuthal_scattering(phi, lobe, scale, gamma_o, gamma_t)
+            } else {
+                1.0 / (2.0 * PI)
+            };
+            value += longitudinal * azimuthal * attenuations[lobe];
+            pdf += longitudinal * azimuthal * lobe_pdfs[lobe];
+        }
+        (value, pdf)
+    }
+
+    /// Chooses an incoming direction (in the fibre's coordinate system) by first choosing a
+    /// lobe and then sampling its longitudinal and azimuthal scattering
+    fn sample(&self, h: f64, wo: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        let sin_theta_o = wo.x();
+        let cos_theta_o = safe_sqrt(1.0 - sin_theta_o.powi(2));
+        let phi_o = wo.z().atan2(wo.y());
+        let gamma_o = safe_asin(h);
+
+        let (attenuations, gamma_t) = self.attenuations(h, sin_theta_o, cos_theta_o);
+        let lobe_pdfs = lobe_pdfs(&attenuations);
+        let mut choice = sampler.get_1d();
+        let mut lobe = 0;
+        while lobe < HAIR_MAX_LOBE && choice >= lobe_pdfs[lobe] {
+            choice -= lobe_pdfs[lobe];
+            lobe += 1;
+        }
+
+        let (u_0, u_1) = sampler.get_2d();
+        let variance = self.lobe_variances()[lobe];
+        let (sin_theta_op, cos_theta_op) = self.tilt(lobe, sin_theta_o, cos_theta_o);
+        let u_0 = u_0.max(1e-5);
+        let cos_theta = 1.0 + variance * (u_0 + (1.0 - u_0) * (-2.0 / variance).exp()).ln();
+        let sin_theta = safe_sqrt(1.0 - cos_theta.powi(2));
+        let cos_phi = (2.0 * PI * u_1).cos();
+        let sin_theta_i = -cos_theta * sin_theta_op + sin_theta * cos_phi * cos_theta_op;
+        let cos_theta_i = safe_sqrt(1.0 - sin_theta_i.powi(2));
+
+        let u_2 = sampler.get_1d();
+        let delta_phi = if lobe < HAIR_MAX_LOBE {
+            lobe_azimuth(lobe, gamma_o, gamma_t)
+                + sample_trimmed_logistic(u_2, self.azimuthal_scale(), -PI, PI)
+        } else {
+            2.0 * PI * u_2
+        };
+        let phi_i = phi_o + delta_phi;
+        Vec3::new(
+            sin_theta_i,
+            cos_theta_i * phi_i.cos(),
+            cos_theta_i * phi_i.sin(),
+        )
+    }
+}
+
+impl Material for Hair {
+    fn scatter(
+        &self,
+        input_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut Sampler,
+    ) -> (Ray, Vec3, bool) {
+        // Build the fibre's coordinate system, in which X runs along the fibre
+        // and Z faces the outgoing direction
+        let wo = -unit_vector(input_ray.direction);
+        let normal = hit_record.normal;
+        let tangent = if hit_record.tangent.length() > 0.0 {
+            hit_record.tangent
+        } else if normal.x().abs() > 0.9 {
+            unit_vector(cross(&normal, &Vec3::new(0.0, 1.0, 0.0)))
+        } else {
+            unit_vector(cross(&normal, &Vec3::new(1.0, 0.0, 0.0)))
+        };
+        let facing = wo - dot(&wo, &tangent) * tangent;
+        let z_axis = if facing.length() > 1e-9 {
+            unit_vector(facing)
+        } else {
+            normal
+        };
+        let y_axis = cross(&z_axis, &tangent);
+        let to_local = |w: &Vec3| Vec3::new(dot(w, &tangent), dot(w, &y_axis), dot(w, &z_axis));
+
+        let h = (2.0 * hit_record.v - 1.0).max(-1.0).min(1.0);
+        let wo_local = to_local(&wo);
+        let wi_local = self.sample(h, &wo_local, sampler);
+        let (value, pdf) = self.evaluate(h, &wo_local, &wi_local);
+        let wi = wi_local.x() * tangent + wi_local.y() * y_axis + wi_local.z() * z_axis;
+        (
+            Ray::new(hit_record.hit_point, wi, input_ray.time),
+            if pdf > 0.0 {
+                value / pdf
+            } else {
+                Vec3::new(0.0, 0.0, 0.0)
+            },
+            pdf > 0.0,
+        )
+    }
+
+    fn albedo(&self, _hit_record: &HitRecord) -> Vec3 {
+        // Invert the mapping used by `from_color`
+        let scale = hair_color_scale(self.beta_n);
+        Vec3::new(
+            (-self.sigma_a.x().sqrt() * scale).exp(),
+            (-self.sigma_a.y().sqrt() * scale).exp(),
+            (-self.sigma_a.z().sqrt() * scale).exp(),
+        )
+    }
+}
+
+/// Relates the absorption coefficient of hair to its color, for a given azimuthal roughness
+fn hair_color_scale(beta_n: f64) -> f64 {
+    5.969 - 0.215 * beta_n + 2.532 * beta_n.powi(2) - 10.73 * beta_n.powi(3)
+        + 5.574 * beta_n.powi(4)
+        + 0.245 * beta_n.powi(5)
+}
+
+fn safe_sqrt(x: f64) -> f64 {
+    x.max(0.0).sqrt()
+}
+
+fn safe_asin(x: f64) -> f64 {
+    x.max(-1.0).min(1.0).asin()
+}
+
+/// The modified Bessel function of the first kind, of order zero
+fn bessel_i0(x: f64) -> f64 {
+    let mut value = 0.0;
+    let mut x_2i = 1.0;
+    let mut factorial = 1.0;
+    let mut four_i = 1.0;
+    for i in 0..10 {
+        if i > 1 {
+            factorial *= f64::from(i);
+        }
+        value += x_2i / (four_i * factorial * factorial);
+        x_2i *= x * x;
+        four_i *= 4.0;
+    }
+    value
+}
+
+/// The natural logarithm of `bessel_i0`, which avoids overflow for large `x`
+fn log_bessel_i0(x: f64) -> f64 {
+    if x > 12.0 {
+        x + 0.5 * (-(2.0 * PI).ln() + (1.0 / x).ln() + 1.0 / (8.0 * x))
+    } else {
+        bessel_i0(x).ln()
+    }
+}
+
+/// The longitudinal scattering function of a lobe with the given variance
+fn longitudinal_scattering(
+    cos_theta_i: f64,
+    cos_theta_o: f64,
+    sin_theta_i: f64,
+    sin_theta_o: f64,
+    variance: f64,
+) -> f64 {
+    let a = cos_theta_i * cos_theta_o / variance;
+    let b = sin_theta_i * sin_theta_o / variance;
+    if variance <= 0.1 {
+        (log_bessel_i0(a) - b - 1.0 / variance + 0.6931 + (1.0 / (2.0 * variance)).ln()).exp()
+    } else {
+        (-b).exp() * bessel_i0(a) / ((1.0 / variance).sinh() * 2.0 * variance)
+    }
+}
+
+/// The change in azimuth of light following a lobe's path through the fibre
+fn lobe_azimuth(lobe: usize, gamma_o: f64, gamma_t: f64) -> f64 {
+    let p = lobe as f64;
+    2.0 * p * gamma_t - 2.0 * gamma_o + p * PI
+}
+
+fn logistic(x: f64, scale: f64) -> f64 {
+    let x = x.abs();
+    (-x / scale).exp() / (scale * (1.0 + (-x / scale).exp()).powi(2))
+}
+
+fn logistic_cdf(x: f64, scale: f64) -> f64 {
+    1.0 / (1.0 + (-x / scale).exp())
+}
+
+/// Samples the logistic distribution, restricted to [a, b]
+fn sample_trimmed_logistic(u: f64, scale: f64, a: f64, b: f64) -> f64 {
+    let k = logistic_cdf(b, scale) - logistic_cdf(a, scale);
+    let x = -scale * (1.0 / (u * k + logistic_cdf(a, scale)) - 1.0).ln();
+    x.max(a).min(b)
+}
+
+/// The azimuthal scattering function of a lobe
+fn azimuthal_scattering(phi: f64, lobe: usize, scale: f64, gamma_o: f64, gamma_t: f64) -> f64 {
+    let mut delta_phi = phi - lobe_azimuth(lobe, gamma_o, gamma_t);
+    // Wrap the difference into [-pi, pi]
+    while delta_phi > PI {
+        delta_phi -= 2.0 * PI;
+    }
+    while delta_phi < -PI {
+        delta_phi += 2.0 * PI;
+    }
+    logistic(delta_phi, scale) / (logistic_cdf(PI, scale) - logistic_cdf(-PI, scale))
+}
+
+/// The probabilities with which each lobe is sampled, in proportion to their luminance
+fn lobe_pdfs(attenuations: &[Vec3; HAIR_MAX_LOBE + 1]) -> [f64; HAIR_MAX_LOBE + 1] {
+    let luminance = |c: &Vec3| 0.212_671 * c.x() + 0.715_160 * c.y() + 0.072_169 * c.z();
+    let total: f64 = attenuations.iter().map(|a| luminance(a)).sum();
+    let mut pdfs = [0.0; HAIR_MAX_LOBE + 1];
+    if total > 0.0 {
+        for (pdf, attenuation) in pdfs.iter_mut().zip(attenuations.iter()) {
+            *pdf = luminance(attenuation) / total;
+        }
+    }
+    pdfs
+}
+
+/// The fraction of light reflected by a dielectric (entered from outside, with a
+/// refractive index of 1) at an angle whose cosine is `cos_theta_i`
+fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let (mut cos_theta_i, mut eta_i, mut eta_t) = (cos_theta_i.max(-1.0).min(1.0), 1.0, eta);
+    if cos_theta_i < 0.0 {
+        ::std::mem::swap(&mut eta_i, &mut eta_t);
+        cos_theta_i = -cos_theta_i;
+    }
+    let sin_theta_t = eta_i / eta_t * safe_sqrt(1.0 - cos_theta_i * cos_theta_i);
+    if sin_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = safe_sqrt(1.0 - sin_theta_t * sin_theta_t);
+    let parallel =
+        (eta_t * cos_theta_i - eta_i * cos_theta_t) / (eta_t * cos_theta_i + eta_i * cos_theta_t);
+    let perpendicular =
+        (eta_i * cos_theta_i - eta_t * cos_theta_t) / (eta_i * cos_theta_i + eta_t * cos_theta_t);
+    0.5 * (parallel * parallel + perpendicular * perpendicular)
+}
+
+/// The weight of Schlick's approximation of the Fresnel term, (1 - cos)^5
+fn schlick_weight(cos_theta: f64) -> f64 {
+    (1.0 - cos_theta).max(0.0).min(1.0).powi(5)
+}
+
+fn luminance(color: &Vec3) -> f64 {
+    0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z()
+}
+
+fn lerp_color(a: &Vec3, b: &Vec3, t: f64) -> Vec3 {
+    (1.0 - t) * *a + t * *b
+}
+
+/// The hue of a color, normalized to a luminance of 1
+fn tint(color: &Vec3) -> Vec3 {
+    let luminance = luminance(color);
+    if luminance > 0.0 {
+        *color / luminance
+    } else {
+        Vec3::new(1.0, 1.0, 1.0)
+    }
+}
+
+/// The Smith auxiliary function of the anisotropic GGX distribution, for a direction in
+/// the frame of the surface
+fn smith_lambda(w: &Vec3, alpha_x: f64, alpha_y: f64) -> f64 {
+    let cos_2 = w.z() * w.z();
+    if cos_2 <= 0.0 {
+        return 0.0;
+    }
+    let tan_2 = (alpha_x * alpha_x * w.x() * w.x() + alpha_y * alpha_y * w.y() * w.y()) / cos_2;
+    0.5 * (-1.0 + (1.0 + tan_2).sqrt())
+}
+
+/// The fraction of microfacets visible from a direction
+fn smith_g1(w: &Vec3, alpha_x: f64, alpha_y: f64) -> f64 {
+    1.0 / (1.0 + smith_lambda(w, alpha_x, alpha_y))
+}
+
+/// The fraction of microfacets visible from both of two directions
+fn smith_g2(wo: &Vec3, wi: &Vec3, alpha_x: f64, alpha_y: f64) -> f64 {
+    1.0 / (1.0 + smith_lambda(wo, alpha_x, alpha_y) + smith_lambda(wi, alpha_x, alpha_y))
+}
+
+/// The separable Smith shadowing term of an isotropic GGX distribution, in the form used by
+/// the Disney BRDF (already divided by twice the cosine)
+fn separable_smith_ggx(cos_theta: f64, alpha: f64) -> f64 {
+    let (a, b) = (alpha * alpha, cos_theta * cos_theta);
+    1.0 / (cos_theta + (a + b - a * b).sqrt())
+}
+
+/// Samples a microfacet normal of the anisotropic GGX distribution, in proportion to how
+/// visible it is from `wo` (a direction in the frame of the surface)
+fn sample_ggx_visible_normal(wo: &Vec3, alpha_x: f64, alpha_y: f64, u: f64, v: f64) -> Vec3 {
+    // The view direction is stretched into the configuration of a hemisphere
+    let view = unit_vector(Vec3::new(alpha_x * wo.x(), alpha_y * wo.y(), wo.z()));
+    let length_2 = view.x() * view.x() + view.y() * view.y();
+    let t_1 = if length_2 > 0.0 {
+        Vec3::new(-view.y(), view.x(), 0.0) / length_2.sqrt()
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let t_2 = cross(&view, &t_1);
+    // A point is sampled on the projected hemisphere
+    let r = u.sqrt();
+    let phi = 2.0 * PI * v;
+    let p_1 = r * phi.cos();
+    let s = 0.5 * (1.0 + view.z());
+    let p_2 = (1.0 - s) * (1.0 - p_1 * p_1).max(0.0).sqrt() + s * r * phi.sin();
+    let n = p_1 * t_1 + p_2 * t_2 + (1.0 - p_1 * p_1 - p_2 * p_2).max(0.0).sqrt() * view;
+    // and the normal is unstretched
+    unit_vector(Vec3::new(alpha_x * n.x(), alpha_y * n.y(), n.z().max(1e-6)))
 }
@@ -0,0 +1,61 @@
+use vec3::Vec3;
+
+/// The shortest wavelength (in nanometres) at which emission is integrated
+const MIN_WAVELENGTH: f64 = 360.0;
+/// The longest wavelength (in nanometres) at which emission is integrated
+const MAX_WAVELENGTH: f64 = 830.0;
+/// The spacing (in nanometres) of the wavelengths at which emission is integrated
+const WAVELENGTH_STEP: f64 = 5.0;
+
+/// Calculates the color of the light emitted by a blackbody (e.g. glowing embers or a flame)
+/// at a given temperature, normalized to unit luminance.
+///
+/// Planck's law is integrated against the CIE 1931 color matching functions, and the
+/// resulting color is converted to linear sRGB (with out-of-gamut components clamped to 0).
+/// #### Arguments:
+/// - `kelvin`: The temperature of the blackbody, in Kelvin
+pub fn blackbody_color(kelvin: f64) -> Vec3 {
+    if kelvin <= 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    let mut xyz = Vec3::new(0.0, 0.0, 0.0);
+    let mut wavelength = MIN_WAVELENGTH;
+    while wavelength <= MAX_WAVELENGTH {
+        xyz += planck(wavelength, kelvin) * color_matching(wavelength);
+        wavelength += WAVELENGTH_STEP;
+    }
+    if xyz.y() <= 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    xyz /= xyz.y();
+    Vec3::new(
+        (3.2406 * xyz.x() - 1.5372 * xyz.y() - 0.4986 * xyz.z()).max(0.0),
+        (-0.9689 * xyz.x() + 1.8758 * xyz.y() + 0.0415 * xyz.z()).max(0.0),
+        (0.0557 * xyz.x() - 0.2040 * xyz.y() + 1.0570 * xyz.z()).max(0.0),
+    )
+}
+
+/// The spectral radiance of a blackbody (up to a constant factor) at a wavelength
+/// (in nanometres), following Planck's law
+fn planck(wavelength: f64, kelvin: f64) -> f64 {
+    // The second radiation constant (hc / k), in nanometre-Kelvins
+    let c_2 = 1.438_776_9e7;
+    let wavelength_um = wavelength * 1e-3;
+    1.0 / (wavelength_um.powi(5) * ((c_2 / (wavelength * kelvin)).exp() - 1.0))
+}
+
+/// The CIE 1931 color matching functions at a wavelength (in nanometres), using the
+/// multi-lobe Gaussian fit of Wyman et al. `Simple Analytic Approximations to the CIE XYZ
+/// Color Matching Functions`
+fn color_matching(wavelength: f64) -> Vec3 {
+    let lobe = |mean: f64, below: f64, above: f64| {
+        let spread = if wavelength < mean { below } else { above };
+        (-0.5 * ((wavelength - mean) / spread).powi(2)).exp()
+    };
+    Vec3::new(
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
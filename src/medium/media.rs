@@ -0,0 +1,112 @@
+use medium::medium::{Medium, MediumInteraction};
+use medium::phase_function::PhaseFunction;
+use medium::phase_functions::IsotropicPhase;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::INFINITY;
+use std::sync::Arc;
+use vec3::Vec3;
+
+/// Applies `f` to each of the three channels of a color
+fn per_channel<F: Fn(f64) -> f64>(color: &Vec3, f: F) -> Vec3 {
+    Vec3::new(f(color.x()), f(color.y()), f(color.z()))
+}
+
+fn mean(color: &Vec3) -> f64 {
+    (color.x() + color.y() + color.z()) / 3.0
+}
+
+/// A medium whose absorption, scattering and emission are the same throughout, but differ
+/// between the red, green and blue channels (e.g. a colored liquid, or skin).
+///
+/// Free paths are sampled chromatically: each interaction samples a distance using the
+/// extinction of a randomly chosen channel, and is weighted by the balance heuristic
+/// over all three channels, so that no channel is sampled poorly when they differ.
+#[derive(Clone)]
+pub struct HomogeneousMedium {
+    /// The fraction of light absorbed per unit of distance, in each channel
+    pub sigma_a: Vec3,
+    /// The fraction of light scattered per unit of distance, in each channel
+    pub sigma_s: Vec3,
+    /// The radiance emitted by the medium's absorbing particles (so the medium glows
+    /// in proportion to how strongly it absorbs, e.g. a hot gas)
+    pub emission: Vec3,
+    pub phase_function: Arc<PhaseFunction>,
+}
+
+impl HomogeneousMedium {
+    /// Constructs a new (non-emissive) homogeneous medium which scatters light isotropically
+    /// #### Arguments:
+    /// - `sigma_a`: The absorption coefficient of each channel
+    /// - `sigma_s`: The scattering coefficient of each channel
+    pub fn new(sigma_a: Vec3, sigma_s: Vec3) -> Self {
+        HomogeneousMedium {
+            sigma_a,
+            sigma_s,
+            emission: Vec3::new(0.0, 0.0, 0.0),
+            phase_function: Arc::new(IsotropicPhase),
+        }
+    }
+
+    /// Constructs a homogeneous medium from the color it takes on, which is convenient for
+    /// absorbing media such as tinted glass or liquids (following the Beer-Lambert law).
+    /// #### Arguments:
+    /// - `color`: The fraction of each channel that is transmitted over `distance`
+    /// - `distance`: The distance at which the medium's color is reached
+    /// - `sigma_s`: The scattering coefficient of each channel
+    pub fn from_color(color: Vec3, distance: f64, sigma_s: Vec3) -> Self {
+        let sigma_a = per_channel(&color, |c| -c.max(1e-6).min(1.0).ln() / distance);
+        HomogeneousMedium::new(sigma_a, sigma_s)
+    }
+}
+
+impl Medium for HomogeneousMedium {
+    fn sample_interaction(
+        &self,
+        ray: &Ray,
+        t_max: f64,
+        sampler: &mut Sampler,
+    ) -> MediumInteraction {
+        let sigma_t = self.sigma_a + self.sigma_s;
+        // Coefficients are given per unit of distance, so distances are converted to
+        // parameters along the ray by its speed
+        let speed = ray.direction.length();
+        let max_distance = t_max * speed;
+        let (u, v) = sampler.get_2d();
+        let channel = ((3.0 * u) as usize).min(2);
+        let distance = if sigma_t[channel] > 0.0 {
+            -(1.0 - v).ln() / sigma_t[channel]
+        } else {
+            INFINITY
+        };
+
+        if distance < max_distance {
+            let transmittance = per_channel(&sigma_t, |sigma| (-sigma * distance).exp());
+            // The density of the distance, averaged over the choice of channel
+            let pdf = mean(&(sigma_t * transmittance));
+            MediumInteraction {
+                t: Some(distance / speed),
+                weight: transmittance * self.sigma_s / pdf,
+                emission: transmittance * self.sigma_a * self.emission / pdf,
+            }
+        } else {
+            let transmittance = per_channel(&sigma_t, |sigma| (-sigma * max_distance).exp());
+            // The probability of passing through, averaged over the choice of channel
+            let probability = mean(&transmittance);
+            let weight = if probability > 0.0 {
+                transmittance / probability
+            } else {
+                Vec3::new(0.0, 0.0, 0.0)
+            };
+            MediumInteraction {
+                t: None,
+                weight,
+                emission: Vec3::new(0.0, 0.0, 0.0),
+            }
+        }
+    }
+
+    fn phase_function(&self) -> &PhaseFunction {
+        &*self.phase_function
+    }
+}
@@ -0,0 +1,34 @@
+use medium::phase_function::PhaseFunction;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::marker::{Send, Sync};
+use vec3::Vec3;
+
+/// The outcome of a ray travelling through a medium towards the next surface along it
+#[derive(Copy, Clone, Debug)]
+pub struct MediumInteraction {
+    /// The parameter (along the ray) of the point at which the ray scatters within the
+    /// medium, or None if it travels through the medium to the next surface
+    pub t: Option<f64>,
+    /// The factor by which the throughput of the ray's path is multiplied (the ratio of
+    /// the transmittance, and scattering if the ray scatters, to the sampling probability)
+    pub weight: Vec3,
+    /// The light emitted by the medium along the ray, as seen from the ray's origin (and
+    /// already divided by the sampling probability)
+    pub emission: Vec3,
+}
+
+/// A participating medium filling the space enclosed by a boundary (e.g. the liquid in a
+/// glass, or the gas in a cloud), through which rays travel between surfaces.
+pub trait Medium: Send + Sync {
+    /// Samples how a ray interacts with the medium before reaching `t_max` (the parameter
+    /// of the next surface along it).
+    /// #### Arguments
+    /// - `ray`: The ray travelling through the medium (whose origin lies within it)
+    /// - `t_max`: The parameter along the ray at which it leaves the medium
+    /// - `sampler`: Provides the sample values used to choose the point of interaction
+    fn sample_interaction(&self, ray: &Ray, t_max: f64, sampler: &mut Sampler)
+        -> MediumInteraction;
+    /// The distribution of the directions into which the medium scatters light
+    fn phase_function(&self) -> &PhaseFunction;
+}
@@ -0,0 +1,280 @@
+use hitable::hit_record::HitRecord;
+use hitable::hitable::Hitable;
+use medium::medium::Medium;
+use ray::Ray;
+use sampler::sampler::Sampler;
+use std::f64::MAX as FLOAT_MAX;
+use std::sync::Arc;
+use vec3::{dot, Vec3};
+
+/// The most boundaries crossed when finding the media enclosing a point
+const MAX_ENCLOSING_CROSSINGS: usize = 256;
+/// The direction in which rays are traced to find the media enclosing a point (chosen to
+/// be unlikely to graze the edges of axis-aligned boundaries)
+const ENCLOSING_DIRECTION: [f64; 3] = [0.2312, 0.9301, 0.2853];
+
+/// A medium, along with the priority of the boundary that encloses it
+#[derive(Clone)]
+pub struct MediumEntry {
+    pub medium: Arc<Medium>,
+    /// Where the boundaries of media overlap (e.g. a liquid whose boundary overlaps the walls
+    /// of its glass), the space they share is filled by the medium with the highest priority
+    pub priority: i32,
+}
+
+/// The media that a ray is travelling through, as the boundaries it has entered but not
+/// yet left. Of these, the ray is only affected by the medium with the highest priority
+/// (or, between equal priorities, the one entered last).
+#[derive(Clone, Default)]
+pub struct MediumStack {
+    entries: Vec<MediumEntry>,
+}
+
+impl MediumStack {
+    /// Constructs an empty stack, for a ray which is not inside any medium
+    pub fn new() -> Self {
+        MediumStack { entries: vec![] }
+    }
+
+    /// Finds the media which enclose a point (e.g. the position of a camera underwater), by
+    /// tracing a ray away from the point and finding the boundaries it leaves without
+    /// having entered them.
+    /// #### Arguments:
+    /// - `world`: The scene, whose medium boundaries have outward-facing normals
+    /// - `point`: The point whose enclosing media are found
+    /// - `time`: The time at which the scene is sampled
+    /// - `sampler`: Provides the sample values of any stochastic surfaces along the ray
+    pub fn enclosing(world: &Hitable, point: Vec3, time: f64, sampler: &mut Sampler) -> Self {
+        let direction = Vec3::new(
+            ENCLOSING_DIRECTION[0],
+            ENCLOSING_DIRECTION[1],
+            ENCLOSING_DIRECTION[2],
+        );
+        let mut stack = MediumStack::new();
+        // The boundaries that the ray has entered along the way
+        let mut entered = MediumStack::new();
+        let mut ray = Ray::new(point, direction, time);
+        for _ in 0..MAX_ENCLOSING_CROSSINGS {
+            let ref mut rec = HitRecord::new();
+            if !world.hit(&ray, 0.00001, FLOAT_MAX, rec, sampler) {
+                break;
+            }
+            if let Some(ref entry) = rec.medium {
+                if dot(&direction, &rec.normal) < 0.0 {
+                    entered.entries.push(entry.clone());
+                } else if !entered.leave(&entry.medium) {
+                    stack.entries.push(entry.clone());
+                }
+            }
+            ray = Ray::new(rec.hit_point, direction, time);
+        }
+        // The boundaries were left from the innermost outwards
+        stack.entries.reverse();
+        stack
+    }
+
+    /// The medium that a ray on the stack is travelling through, if any
+    pub fn current(&self) -> Option<&Arc<Medium>> {
+        self.current_entry().map(|entry| &entry.medium)
+    }
+
+    fn current_entry(&self) -> Option<&MediumEntry> {
+        let mut current: Option<&MediumEntry> = None;
+        for entry in self.entries.iter() {
+            if current.map_or(true, |c| entry.priority >= c.priority) {
+                current = Some(entry);
+            }
+        }
+        current
+    }
+
+    /// Whether crossing the boundary of a medium would change nothing about the medium
+    /// the ray travels through, because a medium of a higher priority fills the space on
+    /// both sides of it. The surfaces of such boundaries are not rendered.
+    pub fn is_false_boundary(&self, entry: &MediumEntry) -> bool {
+        match self.current_entry() {
+            Some(current) => entry.priority < current.priority,
+            None => false,
+        }
+    }
+
+    /// Updates the stack for a ray which leaves the surface recorded in `rec`, travelling
+    /// in `outgoing` after arriving in `incoming`. Rays that cross the boundary of a medium
+    /// enter or leave it, while rays reflected back to the side they came from do neither.
+    pub fn cross(&mut self, rec: &HitRecord, incoming: &Vec3, outgoing: &Vec3) {
+        if let Some(ref entry) = rec.medium {
+            let arriving_inside = dot(incoming, &rec.normal) > 0.0;
+            let leaving_inside = dot(outgoing, &rec.normal) < 0.0;
+            if leaving_inside && !arriving_inside {
+                self.entries.push(entry.clone());
+            } else if arriving_inside && !leaving_inside {
+                self.leave(&entry.medium);
+            }
+        }
+    }
+
+    /// Removes the most recently entered occurrence of a medium from the stack, returning
+    /// whether it was found
+    fn leave(&mut self, medium: &Arc<Medium>) -> bool {
+        match self
+            .entries
+            .iter()
+            .rposition(|entry| Arc::ptr_eq(&entry.medium, medium))
+        {
+            Some(index) => {
+                self.entries.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hitable::hitable_list::HitableList;
+    use hitable::medium_boundary::MediumBoundary;
+    use hitable::sphere::Sphere;
+    use material::materials::Lambertian;
+    use medium::media::HomogeneousMedium;
+    use sampler::samplers::IndependentSampler;
+    use texture::textures::ConstantTexture;
+
+    fn medium() -> Arc<Medium> {
+        Arc::new(HomogeneousMedium::new(
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(0.0, 0.0, 0.0),
+        ))
+    }
+
+    fn entry(medium: &Arc<Medium>, priority: i32) -> MediumEntry {
+        MediumEntry {
+            medium: Arc::clone(medium),
+            priority,
+        }
+    }
+
+    /// A hit on the boundary of a medium, whose normal faces +Z
+    fn boundary_hit(entry: &MediumEntry) -> HitRecord {
+        let mut rec = HitRecord::new();
+        rec.normal = Vec3::new(0.0, 0.0, 1.0);
+        rec.medium = Some(entry.clone());
+        rec
+    }
+
+    fn enter(stack: &mut MediumStack, entry: &MediumEntry) {
+        let inwards = Vec3::new(0.0, 0.0, -1.0);
+        stack.cross(&boundary_hit(entry), &inwards, &inwards);
+    }
+
+    fn leave(stack: &mut MediumStack, entry: &MediumEntry) {
+        let outwards = Vec3::new(0.0, 0.0, 1.0);
+        stack.cross(&boundary_hit(entry), &outwards, &outwards);
+    }
+
+    fn is_current(stack: &MediumStack, medium: &Arc<Medium>) -> bool {
+        stack
+            .current()
+            .map_or(false, |current| Arc::ptr_eq(current, medium))
+    }
+
+    #[test]
+    fn crossing_boundaries_enters_and_leaves_media() {
+        let water = medium();
+        let mut stack = MediumStack::default();
+        assert!(stack.current().is_none());
+        enter(&mut stack, &entry(&water, 0));
+        assert!(is_current(&stack, &water));
+        leave(&mut stack, &entry(&water, 0));
+        assert!(stack.current().is_none());
+    }
+
+    #[test]
+    fn reflections_neither_enter_nor_leave() {
+        let water = medium();
+        let mut stack = MediumStack::new();
+        let rec = boundary_hit(&entry(&water, 0));
+        let (inwards, outwards) = (Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 0.0, 1.0));
+        stack.cross(&rec, &inwards, &outwards);
+        assert!(stack.current().is_none());
+        enter(&mut stack, &entry(&water, 0));
+        stack.cross(&rec, &outwards, &inwards);
+        assert!(is_current(&stack, &water));
+    }
+
+    #[test]
+    fn highest_priority_medium_is_current() {
+        let (glass, liquid) = (medium(), medium());
+        let mut stack = MediumStack::new();
+        enter(&mut stack, &entry(&glass, 2));
+        enter(&mut stack, &entry(&liquid, 1));
+        // The liquid overlaps the glass, which fills the space they share
+        assert!(is_current(&stack, &glass));
+        assert!(stack.is_false_boundary(&entry(&liquid, 1)));
+        assert!(!stack.is_false_boundary(&entry(&glass, 2)));
+        leave(&mut stack, &entry(&glass, 2));
+        assert!(is_current(&stack, &liquid));
+        assert!(!stack.is_false_boundary(&entry(&liquid, 1)));
+    }
+
+    #[test]
+    fn last_entered_medium_wins_between_equal_priorities() {
+        let (first, second) = (medium(), medium());
+        let mut stack = MediumStack::new();
+        enter(&mut stack, &entry(&first, 0));
+        enter(&mut stack, &entry(&second, 0));
+        assert!(is_current(&stack, &second));
+        // Leaving a medium which was entered twice only removes its most recent occurrence
+        enter(&mut stack, &entry(&first, 0));
+        assert!(is_current(&stack, &first));
+        leave(&mut stack, &entry(&first, 0));
+        assert!(is_current(&stack, &second));
+        leave(&mut stack, &entry(&second, 0));
+        assert!(is_current(&stack, &first));
+    }
+
+    #[test]
+    fn finds_enclosing_media() {
+        let (water, glass, bubble) = (medium(), medium(), medium());
+        let boundary = |center: Vec3, radius: f64, medium: &Arc<Medium>, priority: i32| {
+            let sphere = Sphere {
+                center,
+                radius,
+                material: Arc::new(Lambertian {
+                    albedo: Arc::new(ConstantTexture::new(Vec3::new(0.5, 0.5, 0.5))),
+                }),
+            };
+            let mut boundary = MediumBoundary::new(Arc::new(sphere), Arc::clone(medium));
+            boundary.priority = priority;
+            Arc::new(boundary) as Arc<Hitable>
+        };
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        let direction = Vec3::new(
+            ENCLOSING_DIRECTION[0],
+            ENCLOSING_DIRECTION[1],
+            ENCLOSING_DIRECTION[2],
+        );
+        let world = HitableList {
+            list: vec![
+                boundary(origin, 10.0, &water, 0),
+                boundary(origin, 2.0, &glass, 1),
+                // A bubble in the path of the traced ray, which doesn't enclose the origin
+                boundary(5.0 * direction, 1.0, &bubble, 2),
+            ],
+        };
+        let mut sampler = IndependentSampler::new(1, 0);
+
+        let stack = MediumStack::enclosing(&world, origin, 0.0, &mut sampler);
+        assert_eq!(stack.entries.len(), 2);
+        assert!(Arc::ptr_eq(&stack.entries[0].medium, &water));
+        assert!(is_current(&stack, &glass));
+
+        let stack = MediumStack::enclosing(&world, Vec3::new(0.0, 0.0, 5.0), 0.0, &mut sampler);
+        assert_eq!(stack.entries.len(), 1);
+        assert!(is_current(&stack, &water));
+
+        let stack = MediumStack::enclosing(&world, Vec3::new(50.0, 0.0, 0.0), 0.0, &mut sampler);
+        assert!(stack.current().is_none());
+    }
+}
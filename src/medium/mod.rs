@@ -0,0 +1,7 @@
+pub mod blackbody;
+pub mod media;
+pub mod medium;
+pub mod medium_stack;
+pub mod phase_function;
+pub mod phase_functions;
+pub mod voxel_grid;
@@ -0,0 +1,28 @@
+use sampler::sampler::Sampler;
+use std::marker::{Send, Sync};
+use vec3::Vec3;
+
+/// Describes the distribution of directions in which light is scattered by the particles of
+/// a participating medium.
+///
+/// Directions are given as the directions in which light travels, so the phase functions of
+/// media which scatter light forwards (e.g. fog or clouds) are largest when the outgoing
+/// direction is close to the incoming one.
+pub trait PhaseFunction: Send + Sync {
+    /// Calculates the density of the light travelling in `incoming` which is scattered into
+    /// `outgoing` (per unit solid angle, integrating to 1 over the sphere of directions).
+    /// #### Arguments
+    /// - `incoming`: The unit direction in which the light was travelling
+    /// - `outgoing`: The unit direction into which the light is scattered
+    fn evaluate(&self, incoming: &Vec3, outgoing: &Vec3) -> f64;
+    /// Samples the direction into which light is scattered, in proportion to the phase
+    /// function.
+    /// #### Returns
+    /// - Tuple (Vec3, f64):
+    ///   - Vec3: The (unit) scattered direction,
+    ///   - f64: The probability density of the direction (equal to the phase function's value)
+    /// #### Arguments
+    /// - `incoming`: The unit direction in which the light was travelling
+    /// - `sampler`: Provides the sample values used to choose the scattered direction
+    fn sample(&self, incoming: &Vec3, sampler: &mut Sampler) -> (Vec3, f64);
+}
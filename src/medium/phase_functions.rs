@@ -0,0 +1,283 @@
+use hitable::utils::{orthonormal_basis, random_unit_vector};
+use medium::phase_function::PhaseFunction;
+use sampler::sampler::Sampler;
+use std::f64::consts::PI;
+use vec3::{dot, Vec3};
+
+/// The largest magnitude of an asymmetry parameter, beyond which the Henyey-Greenstein
+/// function becomes numerically unstable
+const MAX_ASYMMETRY: f64 = 0.999;
+
+/// Constructs the unit direction at an angle (with the given cosine) from `axis`, rotated
+/// by `phi` around it
+fn direction_around(axis: &Vec3, cos_theta: f64, phi: f64) -> Vec3 {
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let (x, y) = orthonormal_basis(axis);
+    sin_theta * phi.cos() * x + sin_theta * phi.sin() * y + cos_theta * *axis
+}
+
+/// The Henyey-Greenstein function of the cosine of the scattering angle
+fn henyey_greenstein(cos_theta: f64, g: f64) -> f64 {
+    let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+}
+
+/// Samples the cosine of a scattering angle in proportion to the Henyey-Greenstein function
+fn sample_henyey_greenstein(g: f64, u: f64) -> f64 {
+    if g.abs() < 1e-3 {
+        return 1.0 - 2.0 * u;
+    }
+    let s = (1.0 - g * g) / (1.0 + g - 2.0 * g * u);
+    ((1.0 + g * g - s * s) / (2.0 * g)).max(-1.0).min(1.0)
+}
+
+/// A phase function which scatters light equally in all directions
+#[derive(Copy, Clone, Debug)]
+pub struct IsotropicPhase;
+
+impl PhaseFunction for IsotropicPhase {
+    fn evaluate(&self, _incoming: &Vec3, _outgoing: &Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn sample(&self, _incoming: &Vec3, sampler: &mut Sampler) -> (Vec3, f64) {
+        (random_unit_vector(sampler), 1.0 / (4.0 * PI))
+    }
+}
+
+/// The Henyey-Greenstein phase function, which scatters light forwards (for positive `g`)
+/// or backwards (for negative `g`) with a single smooth lobe.
+///
+/// `g` is the mean cosine of the scattering angle, so a value of 0 scatters light
+/// isotropically, while values approaching 1 scatter light almost straight through.
+#[derive(Copy, Clone, Debug)]
+pub struct HenyeyGreenstein {
+    pub g: f64,
+}
+
+impl HenyeyGreenstein {
+    /// Constructs a new Henyey-Greenstein phase function, with `g` in (-1, 1)
+    pub fn new(g: f64) -> Self {
+        HenyeyGreenstein {
+            g: g.max(-MAX_ASYMMETRY).min(MAX_ASYMMETRY),
+        }
+    }
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+    fn evaluate(&self, incoming: &Vec3, outgoing: &Vec3) -> f64 {
+        henyey_greenstein(dot(incoming, outgoing), self.g)
+    }
+
+    fn sample(&self, incoming: &Vec3, sampler: &mut Sampler) -> (Vec3, f64) {
+        let (u, v) = sampler.get_2d();
+        let cos_theta = sample_henyey_greenstein(self.g, u);
+        (
+            direction_around(incoming, cos_theta, 2.0 * PI * v),
+            henyey_greenstein(cos_theta, self.g),
+        )
+    }
+}
+
+/// A blend of a forward and a backward scattering Henyey-Greenstein lobe, which models
+/// media (e.g. clouds or skin) that scatter most light forwards, but also have a visible
+/// backscattering peak
+#[derive(Copy, Clone, Debug)]
+pub struct DoubleHenyeyGreenstein {
+    pub forward: HenyeyGreenstein,
+    pub backward: HenyeyGreenstein,
+    /// The fraction of the light scattered by the forward lobe
+    pub forward_weight: f64,
+}
+
+impl DoubleHenyeyGreenstein {
+    /// Constructs a new double Henyey-Greenstein phase function
+    /// #### Arguments:
+    /// - `forward_g`: The asymmetry of the forward lobe, in [0, 1)
+    /// - `backward_g`: The asymmetry of the backward lobe, in (-1, 0]
+    /// - `forward_weight`: The fraction of the light scattered by the forward lobe
+    pub fn new(forward_g: f64, backward_g: f64, forward_weight: f64) -> Self {
+        DoubleHenyeyGreenstein {
+            forward: HenyeyGreenstein::new(forward_g),
+            backward: HenyeyGreenstein::new(backward_g),
+            forward_weight: forward_weight.max(0.0).min(1.0),
+        }
+    }
+
+    fn evaluate_cosine(&self, cos_theta: f64) -> f64 {
+        self.forward_weight * henyey_greenstein(cos_theta, self.forward.g)
+            + (1.0 - self.forward_weight) * henyey_greenstein(cos_theta, self.backward.g)
+    }
+}
+
+impl PhaseFunction for DoubleHenyeyGreenstein {
+    fn evaluate(&self, incoming: &Vec3, outgoing: &Vec3) -> f64 {
+        self.evaluate_cosine(dot(incoming, outgoing))
+    }
+
+    fn sample(&self, incoming: &Vec3, sampler: &mut Sampler) -> (Vec3, f64) {
+        let (u, v) = sampler.get_2d();
+        // The sample value chooses a lobe, and is then remapped to [0, 1) to sample it
+        let cos_theta = if u < self.forward_weight {
+            sample_henyey_greenstein(self.forward.g, u / self.forward_weight)
+        } else {
+            sample_henyey_greenstein(
+                self.backward.g,
+                (u - self.forward_weight) / (1.0 - self.forward_weight),
+            )
+        };
+        (
+            direction_around(incoming, cos_theta, 2.0 * PI * v),
+            self.evaluate_cosine(cos_theta),
+        )
+    }
+}
+
+/// The phase function of Rayleigh scattering by particles much smaller than the wavelength
+/// of light (e.g. the molecules of the atmosphere), which scatters light equally forwards
+/// and backwards
+#[derive(Copy, Clone, Debug)]
+pub struct Rayleigh;
+
+impl Rayleigh {
+    fn evaluate_cosine(cos_theta: f64) -> f64 {
+        3.0 / (16.0 * PI) * (1.0 + cos_theta * cos_theta)
+    }
+}
+
+impl PhaseFunction for Rayleigh {
+    fn evaluate(&self, incoming: &Vec3, outgoing: &Vec3) -> f64 {
+        Rayleigh::evaluate_cosine(dot(incoming, outgoing))
+    }
+
+    fn sample(&self, incoming: &Vec3, sampler: &mut Sampler) -> (Vec3, f64) {
+        let (u, v) = sampler.get_2d();
+        // The inverse of the cumulative distribution (cos^3 + 3cos + 4) / 8 is the real root
+        // of a depressed cubic, found with Cardano's formula
+        let q = 4.0 * u - 2.0;
+        let a = (q + (q * q + 1.0).sqrt()).cbrt();
+        let cos_theta = (a - 1.0 / a).max(-1.0).min(1.0);
+        (
+            direction_around(incoming, cos_theta, 2.0 * PI * v),
+            Rayleigh::evaluate_cosine(cos_theta),
+        )
+    }
+}
+
+/// Draine's phase function, which generalizes the Henyey-Greenstein function with a term
+/// (weighted by `alpha`) that adds Rayleigh-like side and back scattering
+#[derive(Copy, Clone, Debug)]
+pub struct Draine {
+    pub g: f64,
+    pub alpha: f64,
+}
+
+impl Draine {
+    /// Constructs a new Draine phase function, with `g` in (-1, 1) and a non-negative `alpha`
+    pub fn new(g: f64, alpha: f64) -> Self {
+        Draine {
+            g: g.max(-MAX_ASYMMETRY).min(MAX_ASYMMETRY),
+            alpha: alpha.max(0.0),
+        }
+    }
+
+    fn evaluate_cosine(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        henyey_greenstein(cos_theta, g) * (1.0 + self.alpha * cos_theta * cos_theta)
+            / (1.0 + self.alpha * (1.0 + 2.0 * g * g) / 3.0)
+    }
+
+    /// Samples the cosine of a scattering angle, by rejection sampling the Henyey-Greenstein
+    /// function against the (bounded) factor by which Draine's function differs from it
+    fn sample_cosine(&self, sampler: &mut Sampler) -> f64 {
+        loop {
+            let (u, v) = sampler.get_2d();
+            let cos_theta = sample_henyey_greenstein(self.g, u);
+            if v * (1.0 + self.alpha) <= 1.0 + self.alpha * cos_theta * cos_theta {
+                return cos_theta;
+            }
+        }
+    }
+}
+
+impl PhaseFunction for Draine {
+    fn evaluate(&self, incoming: &Vec3, outgoing: &Vec3) -> f64 {
+        self.evaluate_cosine(dot(incoming, outgoing))
+    }
+
+    fn sample(&self, incoming: &Vec3, sampler: &mut Sampler) -> (Vec3, f64) {
+        let cos_theta = self.sample_cosine(sampler);
+        let phi = 2.0 * PI * sampler.get_1d();
+        (
+            direction_around(incoming, cos_theta, phi),
+            self.evaluate_cosine(cos_theta),
+        )
+    }
+}
+
+/// An approximation of the Mie scattering of light by water droplets (e.g. in fog, mist or
+/// clouds), which blends a Henyey-Greenstein and a Draine lobe fitted to the size of the
+/// droplets.
+///
+/// The fit is that of Jendersie and d'Eon, `An Approximate Mie Scattering Function for Fog
+/// and Cloud Rendering`, for droplets between 1.5 and 50 micrometres in diameter.
+#[derive(Copy, Clone, Debug)]
+pub struct Mie {
+    pub henyey_greenstein: HenyeyGreenstein,
+    pub draine: Draine,
+    /// The fraction of the light scattered by the Draine lobe
+    pub draine_weight: f64,
+}
+
+impl Mie {
+    /// Constructs the phase function of droplets with a given diameter (in micrometres),
+    /// which is clamped to [1.5, 50] (e.g. around 2 for haze, and 10 to 20 for fog or clouds)
+    pub fn new(droplet_diameter: f64) -> Self {
+        let d = droplet_diameter.max(1.5).min(50.0);
+        let (g_hg, g_d, alpha, w_d) = if d < 5.0 {
+            let ln_d = d.ln();
+            (
+                0.0604931 * ln_d.ln() + 0.940256,
+                0.500411 - 0.081287 / (-2.0 * ln_d + ln_d.tan() + 1.27551),
+                7.30354 * ln_d + 6.31675,
+                0.026914 * (ln_d - (5.68947 * (ln_d.ln() - 0.0292149)).cos()) + 0.376475,
+            )
+        } else {
+            (
+                (-0.0990567 / (d - 1.67154)).exp(),
+                (-2.20679 / (d + 3.91029) - 0.428934).exp(),
+                (3.62489 - 8.29288 / (d + 5.52825)).exp(),
+                (-0.599085 / (d - 0.641583) - 0.665888).exp(),
+            )
+        };
+        Mie {
+            henyey_greenstein: HenyeyGreenstein::new(g_hg),
+            draine: Draine::new(g_d, alpha),
+            draine_weight: w_d,
+        }
+    }
+
+    fn evaluate_cosine(&self, cos_theta: f64) -> f64 {
+        (1.0 - self.draine_weight) * henyey_greenstein(cos_theta, self.henyey_greenstein.g)
+            + self.draine_weight * self.draine.evaluate_cosine(cos_theta)
+    }
+}
+
+impl PhaseFunction for Mie {
+    fn evaluate(&self, incoming: &Vec3, outgoing: &Vec3) -> f64 {
+        self.evaluate_cosine(dot(incoming, outgoing))
+    }
+
+    fn sample(&self, incoming: &Vec3, sampler: &mut Sampler) -> (Vec3, f64) {
+        let cos_theta = if sampler.get_1d() < self.draine_weight {
+            self.draine.sample_cosine(sampler)
+        } else {
+            sample_henyey_greenstein(self.henyey_greenstein.g, sampler.get_1d())
+        };
+        let phi = 2.0 * PI * sampler.get_1d();
+        (
+            direction_around(incoming, cos_theta, phi),
+            self.evaluate_cosine(cos_theta),
+        )
+    }
+}
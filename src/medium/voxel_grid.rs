@@ -0,0 +1,178 @@
+use vec3::Vec3;
+
+/// A dense grid of voxels, each holding one or more channels of values (e.g. a density,
+/// or the three components of a color).
+///
+/// The grid spans the unit cube, with the value of each voxel stored at its center, and
+/// is looked up with trilinear interpolation between voxels.
+#[derive(Clone)]
+pub struct VoxelGrid {
+    /// The number of voxels along the x, y and z axes
+    pub resolution: [usize; 3],
+    /// The number of values stored in each voxel
+    pub channels: usize,
+    /// The values of the voxels, ordered by x, then y, then z (with the channels of each
+    /// voxel adjacent)
+    values: Vec<f32>,
+}
+
+impl VoxelGrid {
+    /// Constructs a new voxel grid from its values
+    /// #### Arguments:
+    /// - `resolution`: The number of voxels along the x, y and z axes
+    /// - `channels`: The number of values stored in each voxel
+    /// - `values`: The values of the voxels, ordered by x, then y, then z
+    pub fn new(resolution: [usize; 3], channels: usize, values: Vec<f32>) -> Self {
+        assert!(
+            resolution.iter().all(|&r| r > 0) && channels > 0,
+            "A voxel grid must have at least one voxel and channel"
+        );
+        assert_eq!(
+            values.len(),
+            resolution[0] * resolution[1] * resolution[2] * channels,
+            "The number of values does not match the grid's resolution"
+        );
+        VoxelGrid {
+            resolution,
+            channels,
+            values,
+        }
+    }
+
+    /// Constructs a single-channel voxel grid by evaluating a function at the center of
+    /// each voxel (given as a point in the unit cube)
+    pub fn from_fn<F: Fn(Vec3) -> f64>(resolution: [usize; 3], function: F) -> Self {
+        let mut values = Vec::with_capacity(resolution[0] * resolution[1] * resolution[2]);
+        for z in 0..resolution[2] {
+            for y in 0..resolution[1] {
+                for x in 0..resolution[0] {
+                    let center = Vec3::new(
+                        (x as f64 + 0.5) / resolution[0] as f64,
+                        (y as f64 + 0.5) / resolution[1] as f64,
+                        (z as f64 + 0.5) / resolution[2] as f64,
+                    );
+                    values.push(function(center) as f32);
+                }
+            }
+        }
+        VoxelGrid::new(resolution, 1, values)
+    }
+
+    /// Returns the value of a channel of the voxel at the given indices
+    pub fn voxel(&self, x: usize, y: usize, z: usize, channel: usize) -> f64 {
+        let index = (z * self.resolution[1] + y) * self.resolution[0] + x;
+        f64::from(self.values[index * self.channels + channel])
+    }
+
+    /// Interpolates a channel of the grid at a point in the unit cube (points outside
+    /// the cube take the value of the nearest voxel on its boundary)
+    pub fn lookup(&self, point: &Vec3, channel: usize) -> f64 {
+        let mut lower = [0; 3];
+        let mut upper = [0; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            let position = point[axis] * self.resolution[axis] as f64 - 0.5;
+            let floor = position.floor();
+            let last = self.resolution[axis] as f64 - 1.0;
+            lower[axis] = floor.max(0.0).min(last) as usize;
+            upper[axis] = (floor + 1.0).max(0.0).min(last) as usize;
+            fraction[axis] = position - floor;
+        }
+        let lerp = |a: f64, b: f64, t: f64| (1.0 - t) * a + t * b;
+        let along_x = |y: usize, z: usize| {
+            lerp(
+                self.voxel(lower[0], y, z, channel),
+                self.voxel(upper[0], y, z, channel),
+                fraction[0],
+            )
+        };
+        let along_y = |z: usize| lerp(along_x(lower[1], z), along_x(upper[1], z), fraction[1]);
+        lerp(along_y(lower[2]), along_y(upper[2]), fraction[2])
+    }
+
+    /// Interpolates the grid at a point in the unit cube as a color, which is gray for
+    /// single-channel grids, and otherwise taken from the first three channels
+    pub fn lookup_color(&self, point: &Vec3) -> Vec3 {
+        if self.channels < 3 {
+            let value = self.lookup(point, 0);
+            Vec3::new(value, value, value)
+        } else {
+            Vec3::new(
+                self.lookup(point, 0),
+                self.lookup(point, 1),
+                self.lookup(point, 2),
+            )
+        }
+    }
+
+    /// The largest value of the first channel of the grid
+    pub fn max_value(&self) -> f64 {
+        (0..self.values.len() / self.channels)
+            .map(|voxel| f64::from(self.values[voxel * self.channels]))
+            .fold(0.0, f64::max)
+    }
+
+    /// The largest value of the first channel within a region of the unit cube, which
+    /// includes every voxel that the region's interpolated values depend on
+    fn max_in_region(&self, min: &Vec3, max: &Vec3) -> f64 {
+        let mut lower = [0; 3];
+        let mut upper = [0; 3];
+        for axis in 0..3 {
+            let last = self.resolution[axis] as f64 - 1.0;
+            let to_voxel = |t: f64| t * self.resolution[axis] as f64 - 0.5;
+            lower[axis] = to_voxel(min[axis]).floor().max(0.0).min(last) as usize;
+            upper[axis] = to_voxel(max[axis]).ceil().max(0.0).min(last) as usize;
+        }
+        let mut maximum: f64 = 0.0;
+        for z in lower[2]..=upper[2] {
+            for y in lower[1]..=upper[1] {
+                for x in lower[0]..=upper[0] {
+                    maximum = maximum.max(self.voxel(x, y, z, 0));
+                }
+            }
+        }
+        maximum
+    }
+}
+
+/// A coarse grid over the unit cube storing the largest value of a voxel grid within each
+/// of its cells, which bounds the values that can be looked up anywhere in the cell.
+///
+/// Majorants let a ray traverse empty or thin regions of a volume in a few large steps,
+/// rather than being limited everywhere by the grid's single largest value.
+#[derive(Clone)]
+pub struct MajorantGrid {
+    /// The number of cells along the x, y and z axes
+    pub resolution: [usize; 3],
+    maxima: Vec<f64>,
+}
+
+impl MajorantGrid {
+    /// Constructs the majorant grid of the first channel of a voxel grid
+    /// #### Arguments:
+    /// - `grid`: The voxel grid whose values are bounded
+    /// - `resolution`: The number of cells along the x, y and z axes
+    pub fn new(grid: &VoxelGrid, resolution: [usize; 3]) -> Self {
+        let mut maxima = Vec::with_capacity(resolution[0] * resolution[1] * resolution[2]);
+        for z in 0..resolution[2] {
+            for y in 0..resolution[1] {
+                for x in 0..resolution[0] {
+                    let cell = [x, y, z];
+                    let mut min = Vec3::new(0.0, 0.0, 0.0);
+                    let mut max = Vec3::new(0.0, 0.0, 0.0);
+                    for axis in 0..3 {
+                        min[axis] = cell[axis] as f64 / resolution[axis] as f64;
+                        max[axis] = (cell[axis] + 1) as f64 / resolution[axis] as f64;
+                    }
+                    maxima.push(grid.max_in_region(&min, &max));
+                }
+            }
+        }
+        MajorantGrid { resolution, maxima }
+    }
+
+    /// Returns the largest value within the cell at the given indices
+    pub fn majorant(&self, cell: [usize; 3]) -> f64 {
+        self.maxima[(cell[2] * self.resolution[1] + cell[1]) * self.resolution[0] + cell[0]]
+    }
+}
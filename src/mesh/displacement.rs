@@ -0,0 +1,171 @@
+use mesh::mesh_data::{edge_key, MeshData};
+use std::collections::HashMap;
+use std::sync::Arc;
+use texture::texture::Texture;
+use vec3::{unit_vector, Vec3};
+
+/// The maximum number of times the edges of a mesh are halved while tessellating it
+const MAX_TESSELLATION_PASSES: usize = 12;
+
+/// Describes how a mesh is given geometric detail by moving its vertices along their normals
+/// by the value of a texture.
+///
+/// The mesh is first tessellated until none of its edges are longer than `max_edge_length`,
+/// so that the texture's detail can be resolved, and then displaced. As this happens before
+/// the mesh's BVH is built, the bounding boxes of its faces enclose the displaced surface.
+#[derive(Clone)]
+pub struct Displacement {
+    /// The texture whose value (averaged over its channels) drives the displacement
+    pub texture: Arc<Texture>,
+    /// The distance a vertex is moved per unit of the texture's value
+    pub scale: f64,
+    /// The texture value at which vertices are left in place (values below it move vertices inwards)
+    pub midpoint: f64,
+    /// The maximum length of any edge of the tessellated mesh
+    pub max_edge_length: f64,
+}
+
+impl Displacement {
+    /// Constructs a new displacement, which moves vertices outwards only
+    /// #### Arguments:
+    /// - `texture`: The texture driving the displacement
+    /// - `scale`: The distance a vertex is moved per unit of the texture's value
+    /// - `max_edge_length`: The maximum length of any edge of the tessellated mesh
+    pub fn new(texture: Arc<Texture>, scale: f64, max_edge_length: f64) -> Self {
+        Displacement {
+            texture,
+            scale,
+            midpoint: 0.0,
+            max_edge_length,
+        }
+    }
+
+    /// Tessellates and displaces a mesh, generating normals for the displaced surface
+    pub fn apply(&self, mesh: &MeshData) -> MeshData {
+        let mut mesh = mesh.clone();
+        // Vertices are displaced along the smoothed normals of the welded mesh, so that
+        // vertices along hard edges move together
+        mesh.weld_vertices();
+        mesh.compute_normals();
+        mesh.faces = mesh.triangles().iter().map(|t| t.to_vec()).collect();
+        for _ in 0..MAX_TESSELLATION_PASSES {
+            if !tessellate(&mut mesh, self.max_edge_length) {
+                break;
+            }
+        }
+
+        // Vertices sharing a position (i.e. split along a UV seam) must move in the same
+        // direction, or the surface would tear apart
+        let mut position_normals: HashMap<(u64, u64, u64), Vec3> = HashMap::new();
+        let position_key = |p: &Vec3| (p.x().to_bits(), p.y().to_bits(), p.z().to_bits());
+        let normals = mesh.normals.take().unwrap();
+        for (position, normal) in mesh.positions.iter().zip(normals.iter()) {
+            *position_normals
+                .entry(position_key(position))
+                .or_insert_with(|| Vec3::new(0.0, 0.0, 0.0)) += *normal;
+        }
+        for index in 0..mesh.positions.len() {
+            let position = mesh.positions[index];
+            let (u, v) = match mesh.texture_coords {
+                Some(ref texcoords) => texcoords[index],
+                None => (0.0, 0.0),
+            };
+            let value = self.texture.value(u, v, &position);
+            let height = (value.x() + value.y() + value.z()) / 3.0 - self.midpoint;
+            let direction = position_normals[&position_key(&position)];
+            if direction.length() > 0.0 {
+                mesh.positions[index] = position + self.scale * height * unit_vector(direction);
+            }
+        }
+        mesh.compute_normals();
+        mesh
+    }
+}
+
+/// Splits every edge of a triangle mesh which is longer than `max_edge_length` at its
+/// midpoint, dividing each triangle according to how many of its edges were split.
+/// As split edges are shared by the triangles on either side, no cracks are formed.
+///
+/// #### Returns:
+/// - Whether any edges were split
+fn tessellate(mesh: &mut MeshData, max_edge_length: f64) -> bool {
+    let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+    for face in mesh.faces.iter() {
+        for i in 0..3 {
+            let (a, b) = (face[i], face[(i + 1) % 3]);
+            let key = edge_key(a, b);
+            if midpoints.contains_key(&key)
+                || (mesh.positions[a] - mesh.positions[b]).length() <= max_edge_length
+            {
+                continue;
+            }
+            midpoints.insert(key, mesh.positions.len());
+            mesh.positions
+                .push(0.5 * (mesh.positions[a] + mesh.positions[b]));
+            if let Some(ref mut normals) = mesh.normals {
+                let normal = normals[a] + normals[b];
+                normals.push(if normal.length() > 0.0 {
+                    unit_vector(normal)
+                } else {
+                    normals[a]
+                });
+            }
+            if let Some(ref mut texcoords) = mesh.texture_coords {
+                let (u, v) = (
+                    0.5 * (texcoords[a].0 + texcoords[b].0),
+                    0.5 * (texcoords[a].1 + texcoords[b].1),
+                );
+                texcoords.push((u, v));
+            }
+            if let Some(ref mut colors) = mesh.colors {
+                let color = 0.5 * (colors[a] + colors[b]);
+                colors.push(color);
+            }
+        }
+    }
+    if midpoints.is_empty() {
+        return false;
+    }
+
+    let mut faces = Vec::with_capacity(mesh.faces.len() * 4);
+    for face in mesh.faces.iter() {
+        let split: Vec<Option<usize>> = (0..3)
+            .map(|i| {
+                midpoints
+                    .get(&edge_key(face[i], face[(i + 1) % 3]))
+                    .cloned()
+            })
+            .collect();
+        match split.iter().filter(|m| m.is_some()).count() {
+            0 => faces.push(face.clone()),
+            1 => {
+                // Rotate the triangle so that the split edge runs from its first vertex
+                let i = split.iter().position(|m| m.is_some()).unwrap();
+                let (a, b, c) = (face[i], face[(i + 1) % 3], face[(i + 2) % 3]);
+                let ab = split[i].unwrap();
+                faces.push(vec![a, ab, c]);
+                faces.push(vec![ab, b, c]);
+            }
+            2 => {
+                // Rotate the triangle so that the unsplit edge runs from its first vertex
+                let i = split.iter().position(|m| m.is_none()).unwrap();
+                let (a, b, c) = (face[i], face[(i + 1) % 3], face[(i + 2) % 3]);
+                let bc = split[(i + 1) % 3].unwrap();
+                let ca = split[(i + 2) % 3].unwrap();
+                faces.push(vec![ca, bc, c]);
+                faces.push(vec![a, b, bc]);
+                faces.push(vec![a, bc, ca]);
+            }
+            _ => {
+                let (a, b, c) = (face[0], face[1], face[2]);
+                let (ab, bc, ca) = (split[0].unwrap(), split[1].unwrap(), split[2].unwrap());
+                faces.push(vec![a, ab, ca]);
+                faces.push(vec![b, bc, ab]);
+                faces.push(vec![c, ca, bc]);
+                faces.push(vec![ab, bc, ca]);
+            }
+        }
+    }
+    mesh.faces = faces;
+    true
+}
@@ -0,0 +1,248 @@
+use hitable::hitable::Hitable;
+use hitable::polygon::{Polygon, PolygonMesh};
+use material::material::Material;
+use std::collections::HashMap;
+use std::sync::Arc;
+use vec3::{cross, dot, unit_vector, Vec3};
+
+/// The vertex and face data of a polygon mesh, prior to being built into a PolygonMesh.
+///
+/// Vertices are shared between the faces that use them, so that the mesh's connectivity
+/// can be used to refine it (e.g. by subdivision or tessellation).
+#[derive(Clone, Debug, Default)]
+pub struct MeshData {
+    pub positions: Vec<Vec3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub texture_coords: Option<Vec<(f64, f64)>>,
+    /// The color of each vertex, which is interpolated across faces and given to
+    /// textures as the hit's color attribute (see `VertexColorTexture`)
+    pub colors: Option<Vec<Vec3>>,
+    /// The indices of the vertices of each face, in counter-clockwise order
+    pub faces: Vec<Vec<usize>>,
+    /// The sharpness of each creased edge, keyed by `edge_key`. Edges with a sharpness of
+    /// at least 1 are kept sharp by subdivision (infinitely sharp edges remain sharp at
+    /// every level), while those between 0 and 1 are partially smoothed.
+    pub creases: HashMap<(usize, usize), f64>,
+}
+
+/// The key of the edge between two vertices (which is independent of their order)
+pub fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+impl MeshData {
+    /// Creates a new mesh from the given vertex positions and faces
+    pub fn new(positions: Vec<Vec3>, faces: Vec<Vec<usize>>) -> Self {
+        MeshData {
+            positions,
+            faces,
+            ..MeshData::default()
+        }
+    }
+
+    /// Sets the sharpness of the edge between two vertices
+    pub fn set_crease(&mut self, a: usize, b: usize, sharpness: f64) {
+        self.creases.insert(edge_key(a, b), sharpness);
+    }
+
+    /// The sharpness of the edge between two vertices (0 if it is smooth)
+    pub fn crease(&self, a: usize, b: usize) -> f64 {
+        *self.creases.get(&edge_key(a, b)).unwrap_or(&0.0)
+    }
+
+    /// Finds the faces adjacent to each edge of the mesh
+    pub fn edge_faces(&self) -> HashMap<(usize, usize), Vec<usize>> {
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (index, face) in self.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                edges
+                    .entry(edge_key(face[i], face[(i + 1) % face.len()]))
+                    .or_insert_with(Vec::new)
+                    .push(index);
+            }
+        }
+        edges
+    }
+
+    /// Merges vertices which share the same position (and texture coordinates and color).
+    ///
+    /// Mesh loaders duplicate vertices wherever their normals differ, which disconnects
+    /// the faces around them. Vertices whose texture coordinates or colors differ (i.e.
+    /// along a seam) are kept separate, so seams behave as boundaries. Faces left with
+    /// fewer than three distinct vertices by the welding are removed.
+    pub fn weld_vertices(&mut self) {
+        let mut welded: HashMap<[u64; 8], usize> = HashMap::new();
+        let mut remap = Vec::with_capacity(self.positions.len());
+        let mut positions = vec![];
+        let mut normals = vec![];
+        let mut texcoords = vec![];
+        let mut colors = vec![];
+        for (index, position) in self.positions.iter().enumerate() {
+            let (u, v) = match self.texture_coords {
+                Some(ref texcoords) => texcoords[index],
+                None => (0.0, 0.0),
+            };
+            let color = match self.colors {
+                Some(ref colors) => colors[index],
+                None => Vec3::new(0.0, 0.0, 0.0),
+            };
+            let key = [
+                position.x().to_bits(),
+                position.y().to_bits(),
+                position.z().to_bits(),
+                u.to_bits(),
+                v.to_bits(),
+                color.x().to_bits(),
+                color.y().to_bits(),
+                color.z().to_bits(),
+            ];
+            let next_index = positions.len();
+            let welded_index = *welded.entry(key).or_insert(next_index);
+            if welded_index == next_index {
+                positions.push(*position);
+                if let Some(ref mesh_normals) = self.normals {
+                    normals.push(mesh_normals[index]);
+                }
+                if self.texture_coords.is_some() {
+                    texcoords.push((u, v));
+                }
+                if self.colors.is_some() {
+                    colors.push(color);
+                }
+            }
+            remap.push(welded_index);
+        }
+        for face in self.faces.iter_mut() {
+            for vertex in face.iter_mut() {
+                *vertex = remap[*vertex];
+            }
+            // Edges whose vertices were merged collapse into a single vertex
+            face.dedup();
+            while face.len() > 1 && face.first() == face.last() {
+                face.pop();
+            }
+        }
+        // Faces which collapsed (or still repeat a vertex) are degenerate, and dropped
+        self.faces.retain(|face| {
+            face.len() >= 3
+                && face
+                    .iter()
+                    .enumerate()
+                    .all(|(i, vertex)| !face[i + 1..].contains(vertex))
+        });
+        self.creases = self
+            .creases
+            .iter()
+            .map(|(&(a, b), &sharpness)| (edge_key(remap[a], remap[b]), sharpness))
+            .collect();
+        self.positions = positions;
+        if self.normals.is_some() {
+            self.normals = Some(normals);
+        }
+        if self.texture_coords.is_some() {
+            self.texture_coords = Some(texcoords);
+        }
+        if self.colors.is_some() {
+            self.colors = Some(colors);
+        }
+    }
+
+    /// Marks every edge whose adjacent faces meet at more than `angle` degrees
+    /// (along with every non-manifold edge) as infinitely sharp
+    pub fn crease_by_angle(&mut self, angle: f64) {
+        let min_cosine = angle.to_radians().cos();
+        for (&(a, b), faces) in self.edge_faces().iter() {
+            let sharp = match faces.len() {
+                1 => false,
+                2 => {
+                    let normal_1 = unit_vector(self.face_normal(faces[0]));
+                    let normal_2 = unit_vector(self.face_normal(faces[1]));
+                    dot(&normal_1, &normal_2) < min_cosine
+                }
+                _ => true,
+            };
+            if sharp {
+                self.set_crease(a, b, ::std::f64::INFINITY);
+            }
+        }
+    }
+
+    /// Calculates the normal of a face, whose length is the face's area
+    /// (using Newell's method, which also handles non-planar faces)
+    pub fn face_normal(&self, face: usize) -> Vec3 {
+        let face = &self.faces[face];
+        let mut normal = Vec3::new(0.0, 0.0, 0.0);
+        for i in 0..face.len() {
+            normal += cross(
+                &self.positions[face[i]],
+                &self.positions[face[(i + 1) % face.len()]],
+            );
+        }
+        0.5 * normal
+    }
+
+    /// Generates smooth vertex normals, by weighting the normals of the faces around
+    /// each vertex by their areas
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vec3::new(0.0, 0.0, 0.0); self.positions.len()];
+        for face in 0..self.faces.len() {
+            let normal = self.face_normal(face);
+            for &vertex in self.faces[face].iter() {
+                normals[vertex] += normal;
+            }
+        }
+        self.normals = Some(
+            normals
+                .into_iter()
+                .map(|normal| {
+                    if normal.length() > 0.0 {
+                        unit_vector(normal)
+                    } else {
+                        normal
+                    }
+                })
+                .collect(),
+        );
+    }
+
+    /// Splits every face into triangles (as a fan around its first vertex)
+    pub fn triangles(&self) -> Vec<[usize; 3]> {
+        let mut triangles = vec![];
+        for face in self.faces.iter() {
+            for i in 1..face.len().saturating_sub(1) {
+                triangles.push([face[0], face[i], face[i + 1]]);
+            }
+        }
+        triangles
+    }
+
+    /// Builds the mesh into a PolygonMesh of triangles, which carry the mesh's vertex
+    /// normals, texture coordinates and colors
+    pub fn to_polygon_mesh(&self, material: Arc<Material>) -> PolygonMesh {
+        let faces: Vec<Arc<Hitable>> = self
+            .triangles()
+            .iter()
+            .map(|&[a, b, c]| {
+                let mut face = Polygon::new(
+                    vec![self.positions[a], self.positions[b], self.positions[c]],
+                    Arc::clone(&material),
+                );
+                if let Some(ref normals) = self.normals {
+                    face.vertex_normals = Some(vec![normals[a], normals[b], normals[c]]);
+                }
+                if let Some(ref texcoords) = self.texture_coords {
+                    face.texture_coords = Some(vec![texcoords[a], texcoords[b], texcoords[c]]);
+                }
+                if let Some(ref colors) = self.colors {
+                    face.vertex_colors = Some(vec![colors[a], colors[b], colors[c]]);
+                }
+                Arc::new(face) as Arc<Hitable>
+            })
+            .collect();
+        PolygonMesh::new(faces)
+    }
+}
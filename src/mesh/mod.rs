@@ -0,0 +1,3 @@
+pub mod displacement;
+pub mod mesh_data;
+pub mod subdivision;
@@ -0,0 +1,572 @@
+use mesh::mesh_data::{edge_key, MeshData};
+use std::collections::HashMap;
+use vec3::Vec3;
+
+/// The scheme used to subdivide a mesh
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SubdivisionScheme {
+    /// Catmull-Clark subdivision, which turns every face into quads (best suited to quad meshes)
+    CatmullClark,
+    /// Loop subdivision, which splits every triangle into four (non-triangular faces are
+    /// triangulated first)
+    Loop,
+}
+
+/// Describes how a mesh is smoothed by subdivision when it is loaded
+#[derive(Copy, Clone, Debug)]
+pub struct Subdivision {
+    pub scheme: SubdivisionScheme,
+    /// The number of times the mesh is subdivided (each level multiplies its faces by about four)
+    pub levels: usize,
+    /// If set, edges whose adjacent faces meet at more than this angle (in degrees) are kept sharp
+    pub crease_angle: Option<f64>,
+}
+
+impl Subdivision {
+    pub fn new(scheme: SubdivisionScheme, levels: usize) -> Self {
+        Subdivision {
+            scheme,
+            levels,
+            crease_angle: None,
+        }
+    }
+
+    /// Subdivides a mesh, generating smooth normals for the result.
+    ///
+    /// The mesh's vertices are welded first, so that faces which only share positions
+    /// (rather than vertices) are subdivided as a connected surface.
+    pub fn apply(&self, mesh: &MeshData) -> MeshData {
+        let mut mesh = mesh.clone();
+        if self.levels == 0 {
+            return mesh;
+        }
+        mesh.weld_vertices();
+        if let Some(angle) = self.crease_angle {
+            mesh.crease_by_angle(angle);
+        }
+        for _ in 0..self.levels {
+            mesh = match self.scheme {
+                SubdivisionScheme::CatmullClark => catmull_clark(&mesh),
+                SubdivisionScheme::Loop => loop_subdivision(&mesh),
+            };
+        }
+        mesh.compute_normals();
+        mesh
+    }
+}
+
+/// The connectivity of a mesh's vertices, edges and faces
+struct Topology {
+    /// Every edge of the mesh, in order of first appearance
+    edges: Vec<(usize, usize)>,
+    edge_indices: HashMap<(usize, usize), usize>,
+    /// The faces adjacent to each edge
+    edge_faces: Vec<Vec<usize>>,
+    /// The edges incident to each vertex
+    vertex_edges: Vec<Vec<usize>>,
+    /// The faces incident to each vertex
+    vertex_faces: Vec<Vec<usize>>,
+}
+
+impl Topology {
+    fn new(mesh: &MeshData) -> Self {
+        let mut topology = Topology {
+            edges: vec![],
+            edge_indices: HashMap::new(),
+            edge_faces: vec![],
+            vertex_edges: vec![vec![]; mesh.positions.len()],
+            vertex_faces: vec![vec![]; mesh.positions.len()],
+        };
+        for (index, face) in mesh.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                topology.vertex_faces[face[i]].push(index);
+                let key = edge_key(face[i], face[(i + 1) % face.len()]);
+                let edge = match topology.edge_indices.get(&key) {
+                    Some(&edge) => edge,
+                    None => {
+                        let edge = topology.edges.len();
+                        topology.edges.push(key);
+                        topology.edge_indices.insert(key, edge);
+                        topology.edge_faces.push(vec![]);
+                        topology.vertex_edges[key.0].push(edge);
+                        topology.vertex_edges[key.1].push(edge);
+                        edge
+                    }
+                };
+                topology.edge_faces[edge].push(index);
+            }
+        }
+        topology
+    }
+
+    /// The index of the edge between two vertices
+    fn edge(&self, a: usize, b: usize) -> usize {
+        self.edge_indices[&edge_key(a, b)]
+    }
+
+    /// The sharpness of an edge. Boundary and non-manifold edges are infinitely sharp.
+    fn sharpness(&self, mesh: &MeshData, edge: usize) -> f64 {
+        if self.edge_faces[edge].len() != 2 {
+            return ::std::f64::INFINITY;
+        }
+        let (a, b) = self.edges[edge];
+        mesh.crease(a, b)
+    }
+}
+
+/// Blends between the smooth and sharp rules for a given sharpness
+fn apply_sharpness(smooth: Vec3, sharp: Vec3, sharpness: f64) -> Vec3 {
+    if sharpness >= 1.0 {
+        sharp
+    } else if sharpness <= 0.0 {
+        smooth
+    } else {
+        (1.0 - sharpness) * smooth + sharpness * sharp
+    }
+}
+
+/// Calculates the new position of an edge's midpoint, given its smoothly subdivided position
+fn edge_point(mesh: &MeshData, topology: &Topology, edge: usize, smooth: Vec3) -> Vec3 {
+    let (a, b) = topology.edges[edge];
+    let midpoint = 0.5 * (mesh.positions[a] + mesh.positions[b]);
+    apply_sharpness(smooth, midpoint, topology.sharpness(mesh, edge))
+}
+
+/// Calculates the new position of a vertex, given its smoothly subdivided position.
+///
+/// Vertices on fewer than two creased edges are smooth, those on exactly two follow the
+/// crease (as do vertices on a boundary), and those on more are corners which do not move.
+/// Both schemes use the same crease rule.
+fn vertex_point(mesh: &MeshData, topology: &Topology, vertex: usize, smooth: Vec3) -> Vec3 {
+    let creased: Vec<usize> = topology.vertex_edges[vertex]
+        .iter()
+        .cloned()
+        .filter(|&edge| topology.sharpness(mesh, edge) > 0.0)
+        .collect();
+    if creased.len() < 2 {
+        return smooth;
+    }
+    let position = mesh.positions[vertex];
+    let sharp = if creased.len() == 2 {
+        let neighbours: Vec3 = creased
+            .iter()
+            .map(|&edge| {
+                let (a, b) = topology.edges[edge];
+                mesh.positions[if a == vertex { b } else { a }]
+            })
+            .fold(Vec3::new(0.0, 0.0, 0.0), |sum, neighbour| sum + neighbour);
+        0.75 * position + 0.125 * neighbours
+    } else {
+        position
+    };
+    let sharpness = creased
+        .iter()
+        .map(|&edge| topology.sharpness(mesh, edge))
+        .sum::<f64>()
+        / creased.len() as f64;
+    apply_sharpness(smooth, sharp, sharpness)
+}
+
+/// Carries the creases of the mesh over to the edges they are split into,
+/// whose sharpness is reduced by one
+fn subdivide_creases(
+    mesh: &MeshData,
+    topology: &Topology,
+    edge_vertex: &Fn(usize) -> usize,
+    subdivided: &mut MeshData,
+) {
+    for (&(a, b), &sharpness) in mesh.creases.iter() {
+        if sharpness <= 1.0 {
+            continue;
+        }
+        if let Some(&edge) = topology.edge_indices.get(&(a, b)) {
+            let midpoint = edge_vertex(edge);
+            subdivided.set_crease(a, midpoint, sharpness - 1.0);
+            subdivided.set_crease(midpoint, b, sharpness - 1.0);
+        }
+    }
+}
+
+/// Averages the texture coordinates of the given vertices
+fn average_texcoords(texcoords: &[(f64, f64)], vertices: &[usize]) -> (f64, f64) {
+    let (u, v) = vertices.iter().fold((0.0, 0.0), |(u, v), &vertex| {
+        (u + texcoords[vertex].0, v + texcoords[vertex].1)
+    });
+    (u / vertices.len() as f64, v / vertices.len() as f64)
+}
+
+/// Averages the colors of the given vertices
+fn average_colors(colors: &[Vec3], vertices: &[usize]) -> Vec3 {
+    let sum = vertices
+        .iter()
+        .fold(Vec3::new(0.0, 0.0, 0.0), |sum, &vertex| {
+            sum + colors[vertex]
+        });
+    sum / vertices.len() as f64
+}
+
+/// Performs a single level of Catmull-Clark subdivision
+/// (Source: Catmull and Clark, `Recursively generated B-spline surfaces on arbitrary
+/// topological meshes`, with the crease rules of DeRose et al., `Subdivision Surfaces
+/// in Character Animation`)
+///
+/// The new mesh's vertices are the moved original vertices, followed by a vertex
+/// for each edge, followed by a vertex for each face.
+pub fn catmull_clark(mesh: &MeshData) -> MeshData {
+    let topology = Topology::new(mesh);
+    let (num_vertices, num_edges) = (mesh.positions.len(), topology.edges.len());
+
+    let face_points: Vec<Vec3> = mesh
+        .faces
+        .iter()
+        .map(|face| {
+            face.iter()
+                .fold(Vec3::new(0.0, 0.0, 0.0), |sum, &v| sum + mesh.positions[v])
+                / face.len() as f64
+        })
+        .collect();
+    let edge_points: Vec<Vec3> = (0..num_edges)
+        .map(|edge| {
+            let (a, b) = topology.edges[edge];
+            let faces = &topology.edge_faces[edge];
+            let smooth = if faces.len() == 2 {
+                0.25 * (mesh.positions[a]
+                    + mesh.positions[b]
+                    + face_points[faces[0]]
+                    + face_points[faces[1]])
+            } else {
+                0.5 * (mesh.positions[a] + mesh.positions[b])
+            };
+            edge_point(mesh, &topology, edge, smooth)
+        })
+        .collect();
+    let vertex_points: Vec<Vec3> = (0..num_vertices)
+        .map(|vertex| {
+            let edges = &topology.vertex_edges[vertex];
+            let faces = &topology.vertex_faces[vertex];
+            if edges.is_empty() || faces.is_empty() {
+                return mesh.positions[vertex];
+            }
+            let valence = edges.len() as f64;
+            let face_average = faces
+                .iter()
+                .fold(Vec3::new(0.0, 0.0, 0.0), |sum, &f| sum + face_points[f])
+                / faces.len() as f64;
+            let edge_average = edges.iter().fold(Vec3::new(0.0, 0.0, 0.0), |sum, &e| {
+                let (a, b) = topology.edges[e];
+                sum + 0.5 * (mesh.positions[a] + mesh.positions[b])
+            }) / valence;
+            let smooth =
+                (face_average + 2.0 * edge_average + (valence - 3.0) * mesh.positions[vertex])
+                    / valence;
+            vertex_point(mesh, &topology, vertex, smooth)
+        })
+        .collect();
+
+    let mut subdivided = MeshData::new(
+        vertex_points
+            .into_iter()
+            .chain(edge_points)
+            .chain(face_points)
+            .collect(),
+        vec![],
+    );
+    let edge_vertex = |edge: usize| num_vertices + edge;
+    let face_vertex = |face: usize| num_vertices + num_edges + face;
+    for (index, face) in mesh.faces.iter().enumerate() {
+        let n = face.len();
+        for i in 0..n {
+            let (previous, vertex, next) = (face[(i + n - 1) % n], face[i], face[(i + 1) % n]);
+            subdivided.faces.push(vec![
+                vertex,
+                edge_vertex(topology.edge(vertex, next)),
+                face_vertex(index),
+                edge_vertex(topology.edge(previous, vertex)),
+            ]);
+        }
+    }
+    if let Some(ref texcoords) = mesh.texture_coords {
+        let mut new_texcoords = texcoords.clone();
+        new_texcoords.extend(
+            topology
+                .edges
+                .iter()
+                .map(|&(a, b)| average_texcoords(texcoords, &[a, b])),
+        );
+        new_texcoords.extend(
+            mesh.faces
+                .iter()
+                .map(|face| average_texcoords(texcoords, face)),
+        );
+        subdivided.texture_coords = Some(new_texcoords);
+    }
+    if let Some(ref colors) = mesh.colors {
+        let mut new_colors = colors.clone();
+        new_colors.extend(
+            topology
+                .edges
+                .iter()
+                .map(|&(a, b)| average_colors(colors, &[a, b])),
+        );
+        new_colors.extend(mesh.faces.iter().map(|face| average_colors(colors, face)));
+        subdivided.colors = Some(new_colors);
+    }
+    subdivide_creases(mesh, &topology, &edge_vertex, &mut subdivided);
+    subdivided
+}
+
+/// Performs a single level of Loop subdivision
+/// (Source: Charles Loop, `Smooth Subdivision Surfaces Based on Triangles`, using
+/// Warren's simplified vertex weights)
+///
+/// The new mesh's vertices are the moved original vertices, followed by a vertex
+/// for each edge.
+pub fn loop_subdivision(mesh: &MeshData) -> MeshData {
+    let mut triangulated = mesh.clone();
+    triangulated.faces = mesh.triangles().iter().map(|t| t.to_vec()).collect();
+    let mesh = &triangulated;
+    let topology = Topology::new(mesh);
+    let num_vertices = mesh.positions.len();
+
+    let edge_points: Vec<Vec3> = (0..topology.edges.len())
+        .map(|edge| {
+            let (a, b) = topology.edges[edge];
+            let faces = &topology.edge_faces[edge];
+            // The vertices opposite the edge in each of its triangles (which degenerate
+            // triangles don't have, so their edges are treated as boundaries)
+            let opposite: Option<Vec<usize>> = faces
+                .iter()
+                .map(|&f| mesh.faces[f].iter().cloned().find(|&v| v != a && v != b))
+                .collect();
+            let smooth = match opposite {
+                Some(ref opposite) if opposite.len() == 2 => {
+                    let opposite = mesh.positions[opposite[0]] + mesh.positions[opposite[1]];
+                    0.375 * (mesh.positions[a] + mesh.positions[b]) + 0.125 * opposite
+                }
+                _ => 0.5 * (mesh.positions[a] + mesh.positions[b]),
+            };
+            edge_point(mesh, &topology, edge, smooth)
+        })
+        .collect();
+    let vertex_points: Vec<Vec3> = (0..num_vertices)
+        .map(|vertex| {
+            let edges = &topology.vertex_edges[vertex];
+            if edges.is_empty() {
+                return mesh.positions[vertex];
+            }
+            let valence = edges.len() as f64;
+            let beta = if edges.len() == 3 {
+                3.0 / 16.0
+            } else {
+                3.0 / (8.0 * valence)
+            };
+            let neighbours = edges.iter().fold(Vec3::new(0.0, 0.0, 0.0), |sum, &e| {
+                let (a, b) = topology.edges[e];
+                sum + mesh.positions[if a == vertex { b } else { a }]
+            });
+            let smooth = (1.0 - valence * beta) * mesh.positions[vertex] + beta * neighbours;
+            vertex_point(mesh, &topology, vertex, smooth)
+        })
+        .collect();
+
+    let mut subdivided = MeshData::new(
+        vertex_points.into_iter().chain(edge_points).collect(),
+        vec![],
+    );
+    let edge_vertex = |edge: usize| num_vertices + edge;
+    for face in mesh.faces.iter() {
+        let (a, b, c) = (face[0], face[1], face[2]);
+        let ab = edge_vertex(topology.edge(a, b));
+        let bc = edge_vertex(topology.edge(b, c));
+        let ca = edge_vertex(topology.edge(c, a));
+        subdivided.faces.push(vec![a, ab, ca]);
+        subdivided.faces.push(vec![b, bc, ab]);
+        subdivided.faces.push(vec![c, ca, bc]);
+        subdivided.faces.push(vec![ab, bc, ca]);
+    }
+    if let Some(ref texcoords) = mesh.texture_coords {
+        let mut new_texcoords = texcoords.clone();
+        new_texcoords.extend(
+            topology
+                .edges
+                .iter()
+                .map(|&(a, b)| average_texcoords(texcoords, &[a, b])),
+        );
+        subdivided.texture_coords = Some(new_texcoords);
+    }
+    if let Some(ref colors) = mesh.colors {
+        let mut new_colors = colors.clone();
+        new_colors.extend(
+            topology
+                .edges
+                .iter()
+                .map(|&(a, b)| average_colors(colors, &[a, b])),
+        );
+        subdivided.colors = Some(new_colors);
+    }
+    subdivide_creases(mesh, &topology, &edge_vertex, &mut subdivided);
+    subdivided
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A cube spanning [-1, 1] on each axis, whose vertex `i` has its x, y and z coordinates
+    /// set by bits 0, 1 and 2 of `i`
+    fn cube() -> MeshData {
+        let positions = (0..8)
+            .map(|i| {
+                let coordinate = |bit: usize| if i & bit != 0 { 1.0 } else { -1.0 };
+                Vec3::new(coordinate(1), coordinate(2), coordinate(4))
+            })
+            .collect();
+        let faces = vec![
+            vec![0, 4, 6, 2],
+            vec![1, 3, 7, 5],
+            vec![0, 1, 5, 4],
+            vec![2, 6, 7, 3],
+            vec![0, 2, 3, 1],
+            vec![4, 5, 7, 6],
+        ];
+        MeshData::new(positions, faces)
+    }
+
+    fn tetrahedron() -> MeshData {
+        MeshData::new(
+            vec![
+                Vec3::new(1.0, 1.0, 1.0),
+                Vec3::new(1.0, -1.0, -1.0),
+                Vec3::new(-1.0, 1.0, -1.0),
+                Vec3::new(-1.0, -1.0, 1.0),
+            ],
+            vec![vec![0, 1, 2], vec![0, 3, 1], vec![0, 2, 3], vec![1, 3, 2]],
+        )
+    }
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    /// Finds the position of the vertex created for the edge between two vertices
+    fn edge_point_of(mesh: &MeshData, subdivided: &MeshData, a: usize, b: usize) -> Vec3 {
+        let topology = Topology::new(mesh);
+        subdivided.positions[mesh.positions.len() + topology.edge(a, b)]
+    }
+
+    #[test]
+    fn catmull_clark_cube_points() {
+        let cube = cube();
+        let subdivided = catmull_clark(&cube);
+        assert_eq!(subdivided.positions.len(), 8 + 12 + 6);
+        assert_eq!(subdivided.faces.len(), 24);
+        assert!(subdivided.faces.iter().all(|face| face.len() == 4));
+        // Corners have valence 3, so move to (F + 2R) / 3, where F = 1/3 and R = 2/3
+        let corner = 5.0 / 9.0;
+        assert_close(subdivided.positions[7], Vec3::new(corner, corner, corner));
+        assert_close(subdivided.positions[0], -Vec3::new(corner, corner, corner));
+        // Edge points average the edge's ends with the centers of its two faces
+        assert_close(
+            edge_point_of(&cube, &subdivided, 3, 7),
+            Vec3::new(0.75, 0.75, 0.0),
+        );
+        // Face points are the centers of the faces
+        assert_close(subdivided.positions[8 + 12 + 1], Vec3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn catmull_clark_sharp_creases() {
+        let mut cube = cube();
+        for face in cube.faces.clone() {
+            for i in 0..4 {
+                cube.set_crease(face[i], face[(i + 1) % 4], ::std::f64::INFINITY);
+            }
+        }
+        let subdivided = catmull_clark(&cube);
+        // Corners on three creases don't move, and creased edges are split at their midpoints
+        assert_close(subdivided.positions[7], Vec3::new(1.0, 1.0, 1.0));
+        assert_close(
+            edge_point_of(&cube, &subdivided, 3, 7),
+            Vec3::new(1.0, 1.0, 0.0),
+        );
+        // Infinitely sharp creases remain sharp at every level
+        assert_eq!(
+            subdivided.crease(7, 8 + Topology::new(&cube).edge(3, 7)),
+            ::std::f64::INFINITY
+        );
+    }
+
+    #[test]
+    fn catmull_clark_semi_sharp_creases() {
+        let mut cube = cube();
+        cube.set_crease(3, 7, 0.5);
+        let subdivided = catmull_clark(&cube);
+        // Halfway between the smooth (0.75, 0.75, 0) and sharp (1, 1, 0) edge points
+        assert_close(
+            edge_point_of(&cube, &subdivided, 3, 7),
+            Vec3::new(0.875, 0.875, 0.0),
+        );
+        // Vertices on a single crease are smooth
+        let corner = 5.0 / 9.0;
+        assert_close(subdivided.positions[7], Vec3::new(corner, corner, corner));
+        // Creases of sharpness 1 or less are not carried over
+        assert!(subdivided.creases.is_empty());
+
+        let mut cube = self::cube();
+        cube.set_crease(3, 7, 3.0);
+        let subdivided = catmull_clark(&cube);
+        let midpoint = 8 + Topology::new(&cube).edge(3, 7);
+        assert_eq!(subdivided.crease(3, midpoint), 2.0);
+        assert_eq!(subdivided.crease(midpoint, 7), 2.0);
+    }
+
+    #[test]
+    fn face_counts_after_several_levels() {
+        for levels in 0..4 {
+            let cube = Subdivision::new(SubdivisionScheme::CatmullClark, levels).apply(&cube());
+            assert_eq!(cube.faces.len(), 6 * 4usize.pow(levels as u32));
+            let tetrahedron =
+                Subdivision::new(SubdivisionScheme::Loop, levels).apply(&tetrahedron());
+            assert_eq!(tetrahedron.faces.len(), 4 * 4usize.pow(levels as u32));
+        }
+        // Loop subdivision triangulates quads first
+        let cube = Subdivision::new(SubdivisionScheme::Loop, 1).apply(&cube());
+        assert_eq!(cube.faces.len(), 12 * 4);
+    }
+
+    #[test]
+    fn subdivided_surface_shrinks_towards_limit() {
+        let cube = Subdivision::new(SubdivisionScheme::CatmullClark, 3).apply(&cube());
+        for position in cube.positions.iter() {
+            assert!(position.length() <= 3f64.sqrt());
+            assert!(
+                position
+                    .x()
+                    .abs()
+                    .max(position.y().abs())
+                    .max(position.z().abs())
+                    > 0.5
+            );
+        }
+        assert_eq!(cube.normals.as_ref().unwrap().len(), cube.positions.len());
+    }
+
+    #[test]
+    fn loop_tetrahedron_points() {
+        let tetrahedron = tetrahedron();
+        let subdivided = loop_subdivision(&tetrahedron);
+        assert_eq!(subdivided.positions.len(), 4 + 6);
+        // Valence 3 vertices use a beta of 3/16, and their neighbours sum to -position
+        let position = tetrahedron.positions[0];
+        assert_close(
+            subdivided.positions[0],
+            (1.0 - 9.0 / 16.0) * position - (3.0 / 16.0) * position,
+        );
+        // Edge points weigh the edge's ends by 3/8 and the opposite vertices by 1/8
+        assert_close(
+            edge_point_of(&tetrahedron, &subdivided, 0, 1),
+            0.375 * (tetrahedron.positions[0] + tetrahedron.positions[1])
+                + 0.125 * (tetrahedron.positions[2] + tetrahedron.positions[3]),
+        );
+    }
+}
@@ -0,0 +1,4 @@
+pub mod rng;
+pub mod sampler;
+pub mod samplers;
+pub mod utils;
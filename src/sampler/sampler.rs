@@ -0,0 +1,34 @@
+use std::marker::Send;
+
+/// A trait declaring that an object can generate the sample values used to
+/// estimate the integral of each pixel.
+///
+/// Every sample of a pixel is a point in a high-dimensional space; each call to
+/// `get_1d` or `get_2d` consumes the next dimension(s) of the current sample. Consumers
+/// should therefore request their values in a consistent order (e.g. film position,
+/// then lens position, then time, then the values needed at each bounce), so that
+/// each integrand dimension receives well-stratified values.
+///
+/// Samplers are constructed with a render-wide seed, and the values of each sample
+/// depend only on that seed, the pixel and the sample index. A render is therefore
+/// reproducible regardless of the number of threads or the order in which pixels are traced.
+pub trait Sampler: Send {
+    /// Prepares the sampler to generate the values of a given sample.
+    /// #### Arguments:
+    /// - `pixel`: The (x, y) coordinates of the pixel being sampled
+    /// - `sample_index`: The index of the sample within the pixel
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize);
+
+    /// Returns the value of the next dimension of the current sample, in [0, 1)
+    fn get_1d(&mut self) -> f64;
+
+    /// Returns the values of the next two dimensions of the current sample, in [0, 1)^2
+    fn get_2d(&mut self) -> (f64, f64);
+
+    /// The number of samples that will be generated for each pixel
+    fn samples_per_pixel(&self) -> usize;
+
+    /// Creates a new instance of the sampler with the same configuration,
+    /// so that each render thread can own its own sampler.
+    fn clone_sampler(&self) -> Box<Sampler>;
+}
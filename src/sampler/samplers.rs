@@ -0,0 +1,500 @@
+use sampler::rng::Pcg32;
+use sampler::sampler::Sampler;
+use sampler::utils;
+
+/// A sampler that generates independent, uniformly distributed random values
+/// (i.e. plain Monte Carlo sampling).
+#[derive(Clone, Debug)]
+pub struct IndependentSampler {
+    pub samples_per_pixel: usize,
+    seed: u64,
+    rng: Pcg32,
+}
+
+impl IndependentSampler {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        IndependentSampler {
+            samples_per_pixel,
+            seed,
+            rng: Pcg32::new(0, 0),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize) {
+        let pixel_seed = utils::hash(&[pixel.0 as u64, pixel.1 as u64, self.seed]);
+        self.rng = Pcg32::new(pixel_seed, sample_index as u64);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.next_f64()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.next_f64(), self.rng.next_f64())
+    }
+
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    fn clone_sampler(&self) -> Box<Sampler> {
+        Box::new(self.clone())
+    }
+}
+
+/// A sampler that divides each dimension of a pixel's sample space into strata,
+/// placing a single (optionally jittered) sample in each stratum.
+///
+/// The strata of each dimension are visited in an independently shuffled order,
+/// which prevents correlation between dimensions.
+#[derive(Clone, Debug)]
+pub struct StratifiedSampler {
+    pub x_strata: usize,
+    pub y_strata: usize,
+    /// Whether samples are randomly offset within their strata, or placed at their centers
+    pub jitter: bool,
+    seed: u64,
+    pixel: (u32, u32),
+    sample_index: usize,
+    dimension: u64,
+}
+
+impl StratifiedSampler {
+    /// Constructs a new StratifiedSampler, which generates `x_strata * y_strata` samples per pixel
+    pub fn new(x_strata: usize, y_strata: usize, jitter: bool, seed: u64) -> Self {
+        StratifiedSampler {
+            x_strata,
+            y_strata,
+            jitter,
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Determines the stratum of the current sample, along with a jitter offset
+    /// for each axis, and moves on to the next dimension
+    fn next_stratum(&mut self) -> (u32, f64, f64) {
+        let dimension_seed = utils::hash(&[
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            self.dimension,
+            self.seed,
+        ]);
+        self.dimension += 1;
+        let stratum = utils::permute(
+            self.sample_index as u32,
+            (self.x_strata * self.y_strata) as u32,
+            dimension_seed as u32,
+        );
+        if self.jitter {
+            (
+                stratum,
+                utils::rand_float(self.sample_index as u32, (dimension_seed >> 32) as u32),
+                utils::rand_float(
+                    self.sample_index as u32,
+                    dimension_seed as u32 ^ 0x5bd1_e995,
+                ),
+            )
+        } else {
+            (stratum, 0.5, 0.5)
+        }
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (stratum, delta, _) = self.next_stratum();
+        (stratum as f64 + delta) / (self.x_strata * self.y_strata) as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (stratum, delta_x, delta_y) = self.next_stratum();
+        let (x, y) = (
+            stratum as usize % self.x_strata,
+            stratum as usize / self.x_strata,
+        );
+        (
+            (x as f64 + delta_x) / self.x_strata as f64,
+            (y as f64 + delta_y) / self.y_strata as f64,
+        )
+    }
+
+    fn samples_per_pixel(&self) -> usize {
+        self.x_strata * self.y_strata
+    }
+
+    fn clone_sampler(&self) -> Box<Sampler> {
+        Box::new(self.clone())
+    }
+}
+
+/// A sampler that generates Correlated Multi-Jittered patterns, which are stratified
+/// both in 2D and along each of their 1D projections.
+///
+/// Source: Andrew Kensler, `Correlated Multi-Jittered Sampling`
+/// (http://graphics.pixar.com/library/MultiJitteredSampling/paper.pdf)
+#[derive(Clone, Debug)]
+pub struct CorrelatedMultiJitteredSampler {
+    /// The number of columns in the subpixel grid
+    pub m: usize,
+    /// The number of rows in the subpixel grid
+    pub n: usize,
+    seed: u64,
+    pixel: (u32, u32),
+    sample_index: usize,
+    dimension: u64,
+}
+
+impl CorrelatedMultiJitteredSampler {
+    /// Constructs a new CorrelatedMultiJitteredSampler, which generates `m * n` samples per pixel
+    /// #### Arguments:
+    /// - `m`: The number of columns in the subpixel grid
+    /// - `n`: The number of rows in the subpixel grid
+    /// - `seed`: The render-wide seed from which every pattern is derived
+    pub fn new(m: usize, n: usize, seed: u64) -> Self {
+        CorrelatedMultiJitteredSampler {
+            m,
+            n,
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Selects the pattern used by the next dimension
+    fn next_pattern(&mut self) -> u32 {
+        let pattern = utils::hash(&[
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            self.dimension,
+            self.seed,
+        ]);
+        self.dimension += 1;
+        pattern as u32
+    }
+}
+
+impl Sampler for CorrelatedMultiJitteredSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        // In one dimension, a multi-jittered pattern reduces to a shuffled, jittered stratification
+        let pattern = self.next_pattern();
+        let num_samples = (self.m * self.n) as u32;
+        let stratum = utils::permute(
+            self.sample_index as u32,
+            num_samples,
+            pattern.wrapping_mul(0x68bc_21eb),
+        );
+        let jitter = utils::rand_float(self.sample_index as u32, pattern.wrapping_mul(0x967a_889b));
+        (stratum as f64 + jitter) / num_samples as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let pattern = self.next_pattern();
+        utils::cmj(
+            self.sample_index as u32,
+            self.m as u32,
+            self.n as u32,
+            pattern,
+        )
+    }
+
+    fn samples_per_pixel(&self) -> usize {
+        self.m * self.n
+    }
+
+    fn clone_sampler(&self) -> Box<Sampler> {
+        Box::new(self.clone())
+    }
+}
+
+/// A sampler that generates points of the Owen-scrambled Sobol sequence.
+///
+/// Each pair of dimensions is drawn from the (well stratified) first two dimensions of
+/// the sequence, with its points shuffled and scrambled independently of every other pair.
+/// The best stratification is obtained when `samples_per_pixel` is a power of two.
+///
+/// Source: Brent Burley, `Practical Hash-based Owen Scrambling`
+#[derive(Clone, Debug)]
+pub struct SobolSampler {
+    pub samples_per_pixel: usize,
+    seed: u64,
+    pixel: (u32, u32),
+    sample_index: usize,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        SobolSampler {
+            samples_per_pixel,
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Computes the scrambled Sobol point of the current sample for the next dimension(s)
+    fn next_point(&mut self) -> (f64, f64) {
+        let dimension_seed = utils::hash(&[
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            self.dimension,
+            self.seed,
+        ]);
+        self.dimension += 1;
+        let index = utils::owen_scramble(self.sample_index as u32, dimension_seed as u32);
+        let x_seed = utils::mix_bits(dimension_seed ^ 0x1) as u32;
+        let y_seed = utils::mix_bits(dimension_seed ^ 0x2) as u32;
+        (
+            utils::fixed_to_float(utils::owen_scramble(utils::sobol(index, 0), x_seed)),
+            utils::fixed_to_float(utils::owen_scramble(utils::sobol(index, 1), y_seed)),
+        )
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.next_point().0
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        self.next_point()
+    }
+
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    fn clone_sampler(&self) -> Box<Sampler> {
+        Box::new(self.clone())
+    }
+}
+
+/// A sampler that generates points of the Halton sequence, where the `i`-th dimension
+/// is the radical inverse of the sample index in the base of the `i`-th prime.
+///
+/// Each pixel's points are Owen-scrambled independently, which both decorrelates
+/// neighbouring pixels and breaks up the correlation between the higher dimensions.
+#[derive(Clone, Debug)]
+pub struct HaltonSampler {
+    pub samples_per_pixel: usize,
+    seed: u64,
+    pixel: (u32, u32),
+    sample_index: usize,
+    dimension: u64,
+}
+
+impl HaltonSampler {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        HaltonSampler {
+            samples_per_pixel,
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        // Dimensions beyond the number of available primes wrap around,
+        // relying on their distinct scrambles for decorrelation.
+        let base_index = self.dimension as usize % utils::PRIMES.len();
+        let dimension_seed = utils::hash(&[
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            self.dimension,
+            self.seed,
+        ]);
+        self.dimension += 1;
+        utils::owen_scrambled_radical_inverse(base_index, self.sample_index as u64, dimension_seed)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let x = self.get_1d();
+        (x, self.get_1d())
+    }
+
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    fn clone_sampler(&self) -> Box<Sampler> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generates the values of the first few dimensions of every sample of a pixel,
+    /// alternating between 2D and 1D requests
+    fn pixel_values(sampler: &mut Sampler, pixel: (u32, u32)) -> Vec<Vec<f64>> {
+        (0..sampler.samples_per_pixel())
+            .map(|sample_index| {
+                sampler.start_pixel_sample(pixel, sample_index);
+                let mut values = vec![];
+                for _ in 0..4 {
+                    let (x, y) = sampler.get_2d();
+                    values.extend_from_slice(&[x, y, sampler.get_1d()]);
+                }
+                values
+            })
+            .collect()
+    }
+
+    /// Checks that `values` place the same number of values in each of `strata` equal
+    /// intervals of [0, 1)
+    fn assert_stratified(values: &[f64], strata: usize) {
+        let mut counts = vec![0; strata];
+        for &value in values {
+            counts[(value * strata as f64) as usize] += 1;
+        }
+        assert!(
+            counts.iter().all(|&count| count == values.len() / strata),
+            "{} values are not stratified into {} strata: {:?}",
+            values.len(),
+            strata,
+            counts
+        );
+    }
+
+    fn samplers() -> Vec<Box<Sampler>> {
+        vec![
+            Box::new(IndependentSampler::new(64, 7)),
+            Box::new(StratifiedSampler::new(8, 4, true, 7)),
+            Box::new(StratifiedSampler::new(4, 4, false, 7)),
+            Box::new(CorrelatedMultiJitteredSampler::new(8, 4, 7)),
+            Box::new(SobolSampler::new(64, 7)),
+            Box::new(HaltonSampler::new(64, 7)),
+        ]
+    }
+
+    #[test]
+    fn values_lie_in_unit_interval() {
+        for mut sampler in samplers() {
+            for pixel in &[(0, 0), (13, 7), (4000, 3000)] {
+                for values in pixel_values(&mut *sampler, *pixel) {
+                    assert!(values.iter().all(|&v| v >= 0.0 && v < 1.0), "{:?}", values);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_and_pixel_give_same_values() {
+        for mut sampler in samplers() {
+            let mut clone = sampler.clone_sampler();
+            let values = pixel_values(&mut *sampler, (5, 9));
+            // Interleave another pixel, which must not affect the next
+            pixel_values(&mut *clone, (6, 9));
+            assert_eq!(values, pixel_values(&mut *clone, (5, 9)));
+            assert_ne!(values, pixel_values(&mut *sampler, (6, 9)));
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_values() {
+        let mut a = SobolSampler::new(16, 1);
+        let mut b = SobolSampler::new(16, 2);
+        assert_ne!(pixel_values(&mut a, (0, 0)), pixel_values(&mut b, (0, 0)));
+    }
+
+    #[test]
+    fn stratified_sampler_is_stratified() {
+        let mut sampler = StratifiedSampler::new(8, 4, true, 3);
+        let values = pixel_values(&mut sampler, (2, 3));
+        for dimension in 0..12 {
+            let projection: Vec<f64> = values.iter().map(|v| v[dimension]).collect();
+            // Points of 2D strata are only stratified into the columns and rows of the grid
+            let strata = match dimension % 3 {
+                0 => 8,
+                1 => 4,
+                _ => 32,
+            };
+            assert_stratified(&projection, strata);
+        }
+    }
+
+    #[test]
+    fn correlated_multi_jittered_sampler_is_stratified() {
+        let mut sampler = CorrelatedMultiJitteredSampler::new(8, 4, 3);
+        let values = pixel_values(&mut sampler, (2, 3));
+        for dimension in 0..12 {
+            let projection: Vec<f64> = values.iter().map(|v| v[dimension]).collect();
+            // Every 1D projection of a multi-jittered pattern is fully stratified
+            assert_stratified(&projection, 32);
+        }
+        // As is its 2D grid of strata
+        for dimension in 0..4 {
+            let mut cells: Vec<(usize, usize)> = values
+                .iter()
+                .map(|v| {
+                    (
+                        (v[3 * dimension] * 8.0) as usize,
+                        (v[3 * dimension + 1] * 4.0) as usize,
+                    )
+                })
+                .collect();
+            cells.sort();
+            cells.dedup();
+            assert_eq!(cells.len(), 32);
+        }
+    }
+
+    #[test]
+    fn sobol_sampler_is_stratified() {
+        let mut sampler = SobolSampler::new(64, 3);
+        let values = pixel_values(&mut sampler, (2, 3));
+        for dimension in 0..12 {
+            let projection: Vec<f64> = values.iter().map(|v| v[dimension]).collect();
+            assert_stratified(&projection, 64);
+        }
+    }
+
+    #[test]
+    fn halton_sampler_is_stratified() {
+        // The i-th dimension is stratified over any power of the i-th prime samples
+        for &(dimension, base) in &[(0, 2), (1, 3), (2, 5)] {
+            let num_samples = (base as usize).pow(if base == 2 { 6 } else { 3 });
+            let mut sampler = HaltonSampler::new(num_samples, 3);
+            let projection: Vec<f64> = pixel_values(&mut sampler, (2, 3))
+                .iter()
+                .map(|v| v[dimension])
+                .collect();
+            assert_stratified(&projection, num_samples);
+        }
+    }
+}
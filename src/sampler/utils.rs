@@ -0,0 +1,250 @@
+/// The largest f64 value that is strictly less than one
+pub const ONE_MINUS_EPSILON: f64 = 1.0 - std::f64::EPSILON / 2.0;
+
+/// The first 64 prime numbers, used as the bases of the Halton sequence
+pub const PRIMES: [u64; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// Scrambles the bits of a 64-bit value (the finalizer of the SplitMix64 generator)
+pub fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5_d329_728e_a185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81da_def4_bc2d_d44d);
+    v ^= v >> 33;
+    v
+}
+
+/// Combines the given values into a single well-distributed hash
+pub fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e37_79b9_7f4a_7c15, |acc, value| {
+        mix_bits(acc ^ value.wrapping_add(0x9e37_79b9_7f4a_7c15))
+    })
+}
+
+/// Computes the `i`-th element of a pseudo-random permutation of [0, `l`),
+/// where the permutation is selected by the seed `p`.
+///
+/// Source: Andrew Kensler, `Correlated Multi-Jittered Sampling`
+/// (http://graphics.pixar.com/library/MultiJitteredSampling/paper.pdf)
+pub fn permute(mut i: u32, l: u32, p: u32) -> u32 {
+    let mut w = l - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < l {
+            break;
+        }
+    }
+    i.wrapping_add(p) % l
+}
+
+/// Computes a pseudo-random value in [0, 1) from the index `i` and the seed `p`.
+///
+/// Source: Andrew Kensler, `Correlated Multi-Jittered Sampling`
+pub fn rand_float(mut i: u32, p: u32) -> f64 {
+    i ^= p;
+    i ^= i >> 17;
+    i ^= i >> 10;
+    i = i.wrapping_mul(0xb365_34e5);
+    i ^= i >> 12;
+    i ^= i >> 21;
+    i = i.wrapping_mul(0x93fc_4795);
+    i ^= 0xdf6e_307f;
+    i ^= i >> 17;
+    i = i.wrapping_mul(1 | p >> 18);
+    (i as f64 / 4_294_967_808.0).min(ONE_MINUS_EPSILON)
+}
+
+/// Computes the `s`-th sample of an `m` x `n` correlated multi-jittered pattern,
+/// where the pattern is selected by the seed `p`.
+///
+/// Source: Andrew Kensler, `Correlated Multi-Jittered Sampling`
+pub fn cmj(s: u32, m: u32, n: u32, p: u32) -> (f64, f64) {
+    let s = permute(s, m * n, p.wrapping_mul(0x5163_3e2d));
+    let sx = permute(s % m, m, p.wrapping_mul(0xa511_e9b3));
+    let sy = permute(s / m, n, p.wrapping_mul(0x63d8_3595));
+    let jx = rand_float(s, p.wrapping_mul(0xa399_d265));
+    let jy = rand_float(s, p.wrapping_mul(0x711a_d6a5));
+    (
+        ((s % m) as f64 + (sy as f64 + jx) / n as f64) / m as f64,
+        ((s / m) as f64 + (sx as f64 + jy) / m as f64) / n as f64,
+    )
+}
+
+/// Computes the `index`-th point of the first (`dimension` = 0) or
+/// second (`dimension` = 1) dimension of the Sobol sequence, as a 32-bit fixed-point value
+pub fn sobol(mut index: u32, dimension: u32) -> u32 {
+    let mut result = 0;
+    // The first dimension is the van der Corput sequence, while the direction numbers
+    // of the second are generated by the primitive polynomial x + 1.
+    let mut direction: u32 = 1 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+        direction = if dimension == 0 {
+            direction >> 1
+        } else {
+            direction ^ (direction >> 1)
+        };
+        index >>= 1;
+    }
+    result
+}
+
+/// Performs a nested uniform (i.e. Owen) scramble of the bits of `x`
+///
+/// Source: Brent Burley, `Practical Hash-based Owen Scrambling`
+pub fn owen_scramble(x: u32, seed: u32) -> u32 {
+    let mut x = x.reverse_bits();
+    // Laine-Karras style permutation, which only lets bits affect higher bits
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x.reverse_bits()
+}
+
+/// Converts a 32-bit fixed-point value into a float in [0, 1)
+pub fn fixed_to_float(x: u32) -> f64 {
+    x as f64 / 4_294_967_296.0
+}
+
+/// Computes the radical inverse of `a` in the base `PRIMES[base_index]`, with each
+/// digit randomly permuted depending on the digits preceding it (i.e. Owen scrambling).
+pub fn owen_scrambled_radical_inverse(base_index: usize, mut a: u64, seed: u64) -> f64 {
+    let base = PRIMES[base_index];
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_m = 1.0;
+    let mut reversed_digits: u64 = 0;
+    // Stop once additional digits can no longer change the result
+    while 1.0 - (base - 1) as f64 * inv_base_m < 1.0 {
+        let next = a / base;
+        let digit_seed = mix_bits(seed ^ reversed_digits) as u32;
+        let digit = permute((a - next * base) as u32, base as u32, digit_seed) as u64;
+        reversed_digits = reversed_digits * base + digit;
+        inv_base_m *= inv_base;
+        a = next;
+    }
+    (reversed_digits as f64 * inv_base_m).min(ONE_MINUS_EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permute_is_a_permutation() {
+        for &l in &[1, 2, 7, 16, 100, 256] {
+            for &p in &[0, 1, 0xdead_beef] {
+                let mut values: Vec<u32> = (0..l).map(|i| permute(i, l, p)).collect();
+                values.sort();
+                assert_eq!(values, (0..l).collect::<Vec<u32>>());
+            }
+        }
+        // Different seeds select different permutations
+        let a: Vec<u32> = (0..64).map(|i| permute(i, 64, 1)).collect();
+        let b: Vec<u32> = (0..64).map(|i| permute(i, 64, 2)).collect();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn rand_float_lies_in_unit_interval() {
+        for i in 0..10_000 {
+            let value = rand_float(i, i.wrapping_mul(0x9e37_79b9));
+            assert!(value >= 0.0 && value < 1.0);
+        }
+        assert!(rand_float(0xffff_ffff, 0x0000_0001) < 1.0);
+    }
+
+    #[test]
+    fn cmj_is_multi_jittered() {
+        let (m, n) = (5, 3);
+        let samples: Vec<(f64, f64)> = (0..m * n).map(|s| cmj(s, m, n, 42)).collect();
+        let mut cells = vec![false; (m * n) as usize];
+        let mut x_strata = vec![false; (m * n) as usize];
+        let mut y_strata = vec![false; (m * n) as usize];
+        for &(x, y) in samples.iter() {
+            assert!(x >= 0.0 && x < 1.0 && y >= 0.0 && y < 1.0);
+            cells[(y * n as f64) as usize * m as usize + (x * m as f64) as usize] = true;
+            x_strata[(x * (m * n) as f64) as usize] = true;
+            y_strata[(y * (m * n) as f64) as usize] = true;
+        }
+        assert!(cells.iter().all(|&c| c));
+        assert!(x_strata.iter().all(|&c| c));
+        assert!(y_strata.iter().all(|&c| c));
+    }
+
+    #[test]
+    fn sobol_matches_known_values() {
+        let first: Vec<f64> = (0..8).map(|i| fixed_to_float(sobol(i, 0))).collect();
+        assert_eq!(
+            first,
+            vec![0.0, 0.5, 0.25, 0.75, 0.125, 0.625, 0.375, 0.875]
+        );
+        let second: Vec<f64> = (0..8).map(|i| fixed_to_float(sobol(i, 1))).collect();
+        assert_eq!(
+            second,
+            vec![0.0, 0.5, 0.75, 0.25, 0.625, 0.125, 0.375, 0.875]
+        );
+    }
+
+    #[test]
+    fn owen_scrambling_preserves_stratification() {
+        for &seed in &[0, 1, 0x1234_5678] {
+            for dimension in 0..2 {
+                let mut strata: Vec<u32> = (0..32)
+                    .map(|i| owen_scramble(sobol(i, dimension), seed) >> 27)
+                    .collect();
+                strata.sort();
+                assert_eq!(strata, (0..32).collect::<Vec<u32>>());
+            }
+        }
+        assert_ne!(owen_scramble(0, 1), owen_scramble(0, 2));
+        assert!(fixed_to_float(0xffff_ffff) < 1.0);
+    }
+
+    #[test]
+    fn radical_inverse_is_stratified() {
+        for base_index in 0..4 {
+            let base = PRIMES[base_index];
+            let num_values = base.pow(3);
+            let mut strata: Vec<u64> = (0..num_values)
+                .map(|a| {
+                    let value = owen_scrambled_radical_inverse(base_index, a, 99);
+                    assert!(value >= 0.0 && value < 1.0);
+                    (value * num_values as f64) as u64
+                })
+                .collect();
+            strata.sort();
+            assert_eq!(strata, (0..num_values).collect::<Vec<u64>>());
+        }
+    }
+}
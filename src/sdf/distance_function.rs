@@ -0,0 +1,11 @@
+use std::marker::{Send, Sync};
+use vec3::Vec3;
+
+/// A signed distance function, describing a surface implicitly as the points at which it is zero.
+///
+/// The distance is negative inside the surface and positive outside of it. Its magnitude must
+/// never exceed the true distance to the surface (otherwise sphere tracing may step through it).
+pub trait DistanceFunction: Send + Sync {
+    /// Calculates the signed distance from `point` to the surface
+    fn distance(&self, point: &Vec3) -> f64;
+}
@@ -0,0 +1,227 @@
+use sdf::distance_function::DistanceFunction;
+use std::sync::Arc;
+use vec3::Vec3;
+
+// Distance functions for shapes, which are all centered on the origin
+// (use `Offset` to move them elsewhere)
+
+/// A sphere centered on the origin
+#[derive(Copy, Clone, Debug)]
+pub struct SdfSphere {
+    pub radius: f64,
+}
+
+impl DistanceFunction for SdfSphere {
+    fn distance(&self, point: &Vec3) -> f64 {
+        point.length() - self.radius
+    }
+}
+
+/// A box centered on the origin, whose edges are rounded off by `radius`
+#[derive(Copy, Clone, Debug)]
+pub struct SdfRoundedBox {
+    /// The distances from the center of the box to its faces (before rounding) along each axis
+    pub half_extents: Vec3,
+    pub radius: f64,
+}
+
+impl DistanceFunction for SdfRoundedBox {
+    fn distance(&self, point: &Vec3) -> f64 {
+        let q = Vec3::new(
+            point.x().abs() - self.half_extents.x(),
+            point.y().abs() - self.half_extents.y(),
+            point.z().abs() - self.half_extents.z(),
+        );
+        let outside = Vec3::new(q.x().max(0.0), q.y().max(0.0), q.z().max(0.0)).length();
+        let inside = q.x().max(q.y()).max(q.z()).min(0.0);
+        outside + inside - self.radius
+    }
+}
+
+/// A torus centered on the origin, lying in the X-Z plane
+#[derive(Copy, Clone, Debug)]
+pub struct SdfTorus {
+    /// The distance from the center of the torus to the center of its tube
+    pub major_radius: f64,
+    /// The radius of the tube
+    pub minor_radius: f64,
+}
+
+impl DistanceFunction for SdfTorus {
+    fn distance(&self, point: &Vec3) -> f64 {
+        let ring = (point.x() * point.x() + point.z() * point.z()).sqrt() - self.major_radius;
+        (ring * ring + point.y() * point.y()).sqrt() - self.minor_radius
+    }
+}
+
+/// A Mandelbulb fractal centered on the origin (which lies within a radius of about 1.2)
+#[derive(Copy, Clone, Debug)]
+pub struct SdfMandelbulb {
+    /// The power of the fractal's iteration (8 gives the classic Mandelbulb)
+    pub power: f64,
+    /// The number of iterations used to estimate the distance (more iterations give finer detail)
+    pub iterations: usize,
+}
+
+impl DistanceFunction for SdfMandelbulb {
+    fn distance(&self, point: &Vec3) -> f64 {
+        // Source: Inigo Quilez, `Mandelbulb` (the distance is estimated from the running derivative)
+        let mut z = *point;
+        let mut derivative = 1.0;
+        let mut radius = z.length();
+        for _ in 0..self.iterations {
+            if radius > 2.0 {
+                break;
+            }
+            let theta = (z.y() / radius).acos() * self.power;
+            let phi = z.z().atan2(z.x()) * self.power;
+            derivative = self.power * radius.powf(self.power - 1.0) * derivative + 1.0;
+            let scale = radius.powf(self.power);
+            z = scale
+                * Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                )
+                + *point;
+            radius = z.length();
+        }
+        0.5 * radius.ln() * radius / derivative
+    }
+}
+
+// Operators, which transform or combine other distance functions
+
+/// Polynomial smooth minimum (by Inigo Quilez) of two distances,
+/// which blends them over a distance of `smoothness`
+fn smooth_min(a: f64, b: f64, smoothness: f64) -> f64 {
+    if smoothness <= 0.0 {
+        return a.min(b);
+    }
+    let h = (0.5 + 0.5 * (b - a) / smoothness).max(0.0).min(1.0);
+    b * (1.0 - h) + a * h - smoothness * h * (1.0 - h)
+}
+
+/// The union of two shapes, whose seam is blended over a distance of `smoothness`
+/// (a smoothness of zero gives a sharp union)
+#[derive(Clone)]
+pub struct SmoothUnion {
+    pub a: Arc<DistanceFunction>,
+    pub b: Arc<DistanceFunction>,
+    pub smoothness: f64,
+}
+
+impl DistanceFunction for SmoothUnion {
+    fn distance(&self, point: &Vec3) -> f64 {
+        smooth_min(
+            self.a.distance(point),
+            self.b.distance(point),
+            self.smoothness,
+        )
+    }
+}
+
+/// The intersection of two shapes, whose seam is blended over a distance of `smoothness`
+#[derive(Clone)]
+pub struct SmoothIntersection {
+    pub a: Arc<DistanceFunction>,
+    pub b: Arc<DistanceFunction>,
+    pub smoothness: f64,
+}
+
+impl DistanceFunction for SmoothIntersection {
+    fn distance(&self, point: &Vec3) -> f64 {
+        -smooth_min(
+            -self.a.distance(point),
+            -self.b.distance(point),
+            self.smoothness,
+        )
+    }
+}
+
+/// Shape `a` with shape `b` carved out of it, whose seam is blended over a distance of `smoothness`
+#[derive(Clone)]
+pub struct SmoothDifference {
+    pub a: Arc<DistanceFunction>,
+    pub b: Arc<DistanceFunction>,
+    pub smoothness: f64,
+}
+
+impl DistanceFunction for SmoothDifference {
+    fn distance(&self, point: &Vec3) -> f64 {
+        -smooth_min(
+            -self.a.distance(point),
+            self.b.distance(point),
+            self.smoothness,
+        )
+    }
+}
+
+/// Moves a shape by `offset`
+#[derive(Clone)]
+pub struct Offset {
+    pub shape: Arc<DistanceFunction>,
+    pub offset: Vec3,
+}
+
+impl DistanceFunction for Offset {
+    fn distance(&self, point: &Vec3) -> f64 {
+        self.shape.distance(&(*point - self.offset))
+    }
+}
+
+/// Uniformly scales a shape (about the origin) by `scale`
+#[derive(Clone)]
+pub struct Scale {
+    pub shape: Arc<DistanceFunction>,
+    pub scale: f64,
+}
+
+impl DistanceFunction for Scale {
+    fn distance(&self, point: &Vec3) -> f64 {
+        self.shape.distance(&(*point / self.scale)) * self.scale
+    }
+}
+
+/// Repeats a shape infinitely along each axis, with the given spacing along each axis
+/// (a spacing of zero leaves an axis unrepeated). The shape should fit within a single cell.
+#[derive(Clone)]
+pub struct Repeat {
+    pub shape: Arc<DistanceFunction>,
+    pub spacing: Vec3,
+}
+
+impl DistanceFunction for Repeat {
+    fn distance(&self, point: &Vec3) -> f64 {
+        let mut local = *point;
+        for axis in 0..3 {
+            let spacing = self.spacing[axis];
+            if spacing > 0.0 {
+                local[axis] = point[axis] - spacing * (point[axis] / spacing).round();
+            }
+        }
+        self.shape.distance(&local)
+    }
+}
+
+/// Twists a shape about the Y axis by `rate` radians per unit of height.
+///
+/// Twisting stretches space, so the resulting distances are overestimates; the
+/// surface's step scale should be reduced accordingly (e.g. to 0.5 for gentle twists).
+#[derive(Clone)]
+pub struct Twist {
+    pub shape: Arc<DistanceFunction>,
+    pub rate: f64,
+}
+
+impl DistanceFunction for Twist {
+    fn distance(&self, point: &Vec3) -> f64 {
+        let (sin_theta, cos_theta) = (self.rate * point.y()).sin_cos();
+        let local = Vec3::new(
+            cos_theta * point.x() - sin_theta * point.z(),
+            point.y(),
+            sin_theta * point.x() + cos_theta * point.z(),
+        );
+        self.shape.distance(&local)
+    }
+}
@@ -0,0 +1,2 @@
+pub mod distance_function;
+pub mod distance_functions;
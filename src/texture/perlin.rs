@@ -1,4 +1,4 @@
-use rand::Rng;
+use sampler::rng;
 use vec3::{dot, unit_vector, Vec3};
 
 /// Represents a Perlin Noise generator
@@ -11,16 +11,16 @@ pub struct Perlin {
 }
 
 impl Perlin {
-    /// Constructs a new Perlin Noise generator
+    /// Constructs a new Perlin Noise generator, drawing its gradients and
+    /// permutation tables from the (seeded) scene construction generator
     pub fn new() -> Self {
         let mut rand_vec: [Vec3; 256] = [Vec3::new(0.0, 0.0, 0.0); 256];
-        let mut rng = rand::thread_rng();
 
         for i in 0..256 {
             rand_vec[i] = unit_vector(Vec3::new(
-                -1.0 + 2.0 * rng.gen::<f64>(),
-                -1.0 + 2.0 * rng.gen::<f64>(),
-                -1.0 + 2.0 * rng.gen::<f64>(),
+                -1.0 + 2.0 * rng::scene_random(),
+                -1.0 + 2.0 * rng::scene_random(),
+                -1.0 + 2.0 * rng::scene_random(),
             ));
         }
 
@@ -76,12 +76,11 @@ impl Perlin {
 /// Generates a randomly shuffled array of the discrete values 0-255
 fn gen_perm() -> [u32; 256] {
     let mut perm: [u32; 256] = [0; 256];
-    let mut rng = rand::thread_rng();
     for i in 0..256 {
         perm[i] = i as u32;
     }
-    for i in 255usize..0 {
-        let target = (rng.gen::<f64>() * (i + 1) as f64) as usize;
+    for i in (1..256usize).rev() {
+        let target = (rng::scene_random() * (i + 1) as f64) as usize;
         let temp = perm[i];
         perm[i] = perm[target];
         perm[target] = temp;
@@ -1,3 +1,4 @@
+use hitable::hit_record::HitRecord;
 use std::marker::{Send, Sync};
 use vec3::Vec3;
 
@@ -5,4 +6,9 @@ pub trait Texture: Send + Sync {
     /// Calculates the value for the texture given the parameters
     /// `u` and `v` at the hit-point of a Ray
     fn value(&self, u: f64, v: f64, hit_point: &Vec3) -> Vec3;
+    /// Calculates the value for the texture at a Ray's hit, which gives textures access
+    /// to the surface's attributes (e.g. its color) as well as its texture coordinates
+    fn value_at_hit(&self, hit_record: &HitRecord) -> Vec3 {
+        self.value(hit_record.u, hit_record.v, &hit_record.hit_point)
+    }
 }
@@ -1,5 +1,7 @@
 extern crate image;
 
+use animation::track::Track;
+use hitable::hit_record::HitRecord;
 use image::GenericImageView;
 use std::sync::Arc;
 use texture::perlin::Perlin;
@@ -24,6 +26,30 @@ impl Texture for ConstantTexture {
     }
 }
 
+/// A Texture whose color is keyframed over time. It is evaluated at the time of each
+/// hit, so its color changes during the camera's shutter interval as well as between frames.
+#[derive(Clone)]
+pub struct AnimatedTexture {
+    pub color: Track<Vec3>,
+}
+
+impl AnimatedTexture {
+    pub fn new(color: Track<Vec3>) -> Self {
+        AnimatedTexture { color }
+    }
+}
+
+impl Texture for AnimatedTexture {
+    /// Lookups without a hit have no time, so they take the color at time 0
+    fn value(&self, _u: f64, _v: f64, _hit_point: &Vec3) -> Vec3 {
+        self.color.evaluate(0.0)
+    }
+
+    fn value_at_hit(&self, hit_record: &HitRecord) -> Vec3 {
+        self.color.evaluate(hit_record.time)
+    }
+}
+
 /// A texture representing a checkerboard pattern,
 /// alternating between two textures `even` and `odd`
 #[derive(Clone)]
@@ -36,18 +62,31 @@ impl CheckerTexture {
     pub fn new(even: Arc<Texture>, odd: Arc<Texture>) -> Self {
         CheckerTexture { even, odd }
     }
-}
 
-impl Texture for CheckerTexture {
-    fn value(&self, u: f64, v: f64, hit_point: &Vec3) -> Vec3 {
+    /// Whether a point lies in one of the `odd` cells of the checkerboard
+    fn is_odd(hit_point: &Vec3) -> bool {
         let sines: f64 = (10.0 * hit_point.x()).sin()
             * (10.0 * hit_point.y()).sin()
             * (10.0 * hit_point.z()).sin();
-        return if sines < 0.0 {
+        sines < 0.0
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, hit_point: &Vec3) -> Vec3 {
+        if CheckerTexture::is_odd(hit_point) {
             self.odd.value(u, v, hit_point)
         } else {
             self.even.value(u, v, hit_point)
-        };
+        }
+    }
+
+    fn value_at_hit(&self, hit_record: &HitRecord) -> Vec3 {
+        if CheckerTexture::is_odd(&hit_record.hit_point) {
+            self.odd.value_at_hit(hit_record)
+        } else {
+            self.even.value_at_hit(hit_record)
+        }
     }
 }
 
@@ -131,3 +170,30 @@ impl Texture for ImageTexture {
         Vec3::new(r, g, b)
     }
 }
+
+/// A texture giving the color attribute of the surface that was hit (e.g. the color of a
+/// point in a point cloud, or the interpolated vertex colors of a mesh), falling back to
+/// another texture wherever the surface has no color
+#[derive(Clone)]
+pub struct VertexColorTexture {
+    pub fallback: Arc<Texture>,
+}
+
+impl VertexColorTexture {
+    pub fn new(fallback: Arc<Texture>) -> Self {
+        VertexColorTexture { fallback }
+    }
+}
+
+impl Texture for VertexColorTexture {
+    fn value(&self, u: f64, v: f64, hit_point: &Vec3) -> Vec3 {
+        self.fallback.value(u, v, hit_point)
+    }
+
+    fn value_at_hit(&self, hit_record: &HitRecord) -> Vec3 {
+        match hit_record.color {
+            Some(color) => color,
+            None => self.fallback.value_at_hit(hit_record),
+        }
+    }
+}